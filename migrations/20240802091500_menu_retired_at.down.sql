@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE Menu DROP COLUMN IF EXISTS retired_at;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE Menu ADD COLUMN retired_at TIMESTAMP DEFAULT NULL;
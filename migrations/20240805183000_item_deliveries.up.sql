@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE item_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    items_id UUID NOT NULL REFERENCES Items(id),
+    quantity INTEGER NOT NULL,
+    delivered_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    delivered_by VARCHAR(255)
+);
+
+CREATE INDEX item_deliveries_items_id_idx ON item_deliveries (items_id);
+
+UPDATE items
+SET delivered_at = updated_at
+WHERE delivered_at IS NULL
+  AND delivered_quantity >= quantity;
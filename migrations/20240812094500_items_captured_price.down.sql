@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE Items DROP COLUMN unit_price;
+ALTER TABLE Items DROP COLUMN menu_name;
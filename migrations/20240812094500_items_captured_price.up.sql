@@ -0,0 +1,12 @@
+-- Add up migration script here
+ALTER TABLE Items ADD COLUMN menu_name VARCHAR(255);
+ALTER TABLE Items ADD COLUMN unit_price DECIMAL(10, 2);
+
+UPDATE items
+SET menu_name = Menu.name,
+    unit_price = Menu.price
+FROM Menu
+WHERE items.menu_id = Menu.id;
+
+ALTER TABLE Items ALTER COLUMN menu_name SET NOT NULL;
+ALTER TABLE Items ALTER COLUMN unit_price SET NOT NULL;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS check_items;
+DROP TABLE IF EXISTS checks;
@@ -0,0 +1,22 @@
+-- Add up migration script here
+CREATE TABLE checks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tables_id UUID NOT NULL REFERENCES Tables(id),
+    name VARCHAR(255) NOT NULL,
+    position INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    created_by VARCHAR(255)
+);
+
+CREATE INDEX checks_tables_id_idx ON checks (tables_id);
+
+CREATE TABLE check_items (
+    check_id UUID NOT NULL REFERENCES checks(id) ON DELETE CASCADE,
+    items_id UUID NOT NULL REFERENCES Items(id),
+    share_numerator INTEGER NOT NULL,
+    share_denominator INTEGER NOT NULL,
+    PRIMARY KEY (check_id, items_id),
+    CHECK (share_numerator > 0 AND share_numerator <= share_denominator)
+);
+
+CREATE INDEX check_items_items_id_idx ON check_items (items_id);
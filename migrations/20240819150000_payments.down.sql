@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE checks DROP COLUMN session_id;
+ALTER TABLE Items DROP COLUMN paid_at;
+ALTER TABLE Items DROP COLUMN session_id;
+DROP TABLE IF EXISTS payments;
+DROP TABLE IF EXISTS table_sessions;
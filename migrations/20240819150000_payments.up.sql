@@ -0,0 +1,30 @@
+-- Add up migration script here
+CREATE TABLE table_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tables_id UUID NOT NULL REFERENCES Tables(id),
+    opened_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    closed_at TIMESTAMP DEFAULT NULL,
+    closed_by VARCHAR(255)
+);
+
+CREATE UNIQUE INDEX table_sessions_open_idx ON table_sessions (tables_id) WHERE closed_at IS NULL;
+
+CREATE TABLE payments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    session_id UUID NOT NULL REFERENCES table_sessions(id),
+    check_id UUID REFERENCES checks(id) ON DELETE SET NULL,
+    tender VARCHAR(16) NOT NULL CHECK (tender IN ('cash', 'card', 'voucher')),
+    amount DECIMAL(10, 2) NOT NULL,
+    tip DECIMAL(10, 2) NOT NULL DEFAULT 0,
+    tendered DECIMAL(10, 2) NOT NULL,
+    change DECIMAL(10, 2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    created_by VARCHAR(255)
+);
+
+CREATE INDEX payments_session_id_idx ON payments (session_id);
+
+ALTER TABLE Items ADD COLUMN session_id UUID REFERENCES table_sessions(id);
+ALTER TABLE Items ADD COLUMN paid_at TIMESTAMP DEFAULT NULL;
+
+ALTER TABLE checks ADD COLUMN session_id UUID REFERENCES table_sessions(id);
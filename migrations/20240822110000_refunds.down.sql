@@ -0,0 +1,10 @@
+-- Add down migration script here
+ALTER TABLE Items DROP COLUMN refunded_by;
+ALTER TABLE Items DROP COLUMN refunded_at;
+ALTER TABLE Items DROP COLUMN refunded_quantity;
+DROP INDEX IF EXISTS payments_created_at_idx;
+DROP INDEX IF EXISTS payments_refund_of_idx;
+ALTER TABLE payments DROP CONSTRAINT payments_refund_reason_check;
+ALTER TABLE payments DROP COLUMN approved_by;
+ALTER TABLE payments DROP COLUMN reason;
+ALTER TABLE payments DROP COLUMN refund_of;
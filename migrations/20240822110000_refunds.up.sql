@@ -0,0 +1,14 @@
+-- Add up migration script here
+ALTER TABLE payments ADD COLUMN refund_of UUID REFERENCES payments(id);
+ALTER TABLE payments ADD COLUMN reason VARCHAR(32)
+    CHECK (reason IN ('quality', 'wrong_item', 'overcharge', 'goodwill', 'other'));
+ALTER TABLE payments ADD COLUMN approved_by VARCHAR(255);
+ALTER TABLE payments ADD CONSTRAINT payments_refund_reason_check
+    CHECK (refund_of IS NULL OR (reason IS NOT NULL AND approved_by IS NOT NULL));
+
+CREATE INDEX payments_refund_of_idx ON payments (refund_of);
+CREATE INDEX payments_created_at_idx ON payments (created_at);
+
+ALTER TABLE Items ADD COLUMN refunded_quantity INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE Items ADD COLUMN refunded_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE Items ADD COLUMN refunded_by VARCHAR(255);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE Tables DROP COLUMN covers;
+DROP TABLE service_charge_rules;
+ALTER TABLE Menu DROP COLUMN tax_category_id;
+DROP TABLE tax_categories;
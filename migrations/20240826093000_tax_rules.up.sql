@@ -0,0 +1,20 @@
+-- Add up migration script here
+CREATE TABLE tax_categories (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL UNIQUE,
+    rate DECIMAL(6, 4) NOT NULL CHECK (rate >= 0 AND rate <= 1),
+    inclusive BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+ALTER TABLE Menu ADD COLUMN tax_category_id UUID REFERENCES tax_categories(id) ON DELETE SET NULL;
+
+CREATE TABLE service_charge_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    rate DECIMAL(6, 4) NOT NULL CHECK (rate > 0 AND rate <= 1),
+    min_covers INTEGER NOT NULL CHECK (min_covers > 0),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+ALTER TABLE Tables ADD COLUMN covers INTEGER CHECK (covers > 0);
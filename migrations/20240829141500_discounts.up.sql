@@ -0,0 +1,30 @@
+-- Add up migration script here
+CREATE TABLE promo_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(64) NOT NULL UNIQUE,
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('percent', 'fixed')),
+    value DECIMAL(10, 2) NOT NULL CHECK (value > 0),
+    valid_from TIMESTAMP DEFAULT NULL,
+    valid_until TIMESTAMP DEFAULT NULL,
+    max_uses INTEGER CHECK (max_uses > 0),
+    uses INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    CHECK (max_uses IS NULL OR uses <= max_uses)
+);
+
+CREATE TABLE discounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tables_id UUID NOT NULL REFERENCES Tables(id),
+    items_id UUID REFERENCES Items(id),
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('percent', 'fixed')),
+    value DECIMAL(10, 2) NOT NULL CHECK (value > 0),
+    reason VARCHAR(255),
+    promo_code_id UUID REFERENCES promo_codes(id),
+    applied_by VARCHAR(255) NOT NULL,
+    session_id UUID REFERENCES table_sessions(id),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    created_by VARCHAR(255)
+);
+
+CREATE INDEX discounts_tables_id_idx ON discounts (tables_id);
+CREATE INDEX discounts_items_id_idx ON discounts (items_id);
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE table_sessions DROP COLUMN receipt_number;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE SEQUENCE receipt_numbers;
+
+ALTER TABLE table_sessions ADD COLUMN receipt_number BIGINT NOT NULL DEFAULT nextval('receipt_numbers') UNIQUE;
+
+ALTER SEQUENCE receipt_numbers OWNED BY table_sessions.receipt_number;
@@ -0,0 +1,7 @@
+-- Add down migration script here
+ALTER TABLE Items DROP COLUMN z_report_id;
+ALTER TABLE payments DROP COLUMN z_report_id;
+ALTER TABLE table_sessions DROP COLUMN z_report_id;
+ALTER TABLE table_sessions DROP COLUMN covers;
+
+DROP TABLE z_reports;
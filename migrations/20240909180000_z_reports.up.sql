@@ -0,0 +1,18 @@
+-- Add up migration script here
+CREATE TABLE z_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    number BIGINT NOT NULL UNIQUE,
+    opened_at TIMESTAMP DEFAULT NULL,
+    closed_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    closed_by VARCHAR(255),
+    totals JSONB NOT NULL
+);
+
+ALTER TABLE table_sessions ADD COLUMN covers INTEGER;
+ALTER TABLE table_sessions ADD COLUMN z_report_id UUID REFERENCES z_reports(id);
+ALTER TABLE payments ADD COLUMN z_report_id UUID REFERENCES z_reports(id);
+ALTER TABLE Items ADD COLUMN z_report_id UUID REFERENCES z_reports(id);
+
+CREATE INDEX table_sessions_unreported_idx ON table_sessions (closed_at) WHERE z_report_id IS NULL;
+CREATE INDEX payments_unreported_idx ON payments (created_at) WHERE z_report_id IS NULL;
+CREATE INDEX items_unreported_voids_idx ON items (deleted_at) WHERE z_report_id IS NULL AND deleted_at IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE Items DROP COLUMN served_at;
+ALTER TABLE Items DROP COLUMN ready_at;
+ALTER TABLE Items DROP COLUMN started_at;
+ALTER TABLE Items DROP COLUMN status;
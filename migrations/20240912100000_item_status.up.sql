@@ -0,0 +1,11 @@
+-- Add up migration script here
+ALTER TABLE Items ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT 'ordered'
+    CHECK (status IN ('ordered', 'in_preparation', 'ready', 'served'));
+ALTER TABLE Items ADD COLUMN started_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE Items ADD COLUMN ready_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE Items ADD COLUMN served_at TIMESTAMP DEFAULT NULL;
+
+UPDATE items
+SET status = 'served',
+    served_at = delivered_at
+WHERE delivered_at IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE item_alerts;
+DROP TABLE menu_alert_thresholds;
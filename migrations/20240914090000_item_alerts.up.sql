@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE menu_alert_thresholds (
+    menu_id UUID PRIMARY KEY REFERENCES Menu(id) ON DELETE CASCADE,
+    grace_minutes INTEGER NOT NULL CHECK (grace_minutes >= 0)
+);
+
+CREATE TABLE item_alerts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    items_id UUID NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    overdue_at TIMESTAMP NOT NULL,
+    raised_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    resolved_at TIMESTAMP DEFAULT NULL
+);
+
+CREATE UNIQUE INDEX item_alerts_open_idx ON item_alerts (items_id) WHERE resolved_at IS NULL;
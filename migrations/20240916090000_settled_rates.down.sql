@@ -0,0 +1,12 @@
+-- Add down migration script here
+ALTER TABLE table_sessions
+    DROP COLUMN service_charge_rate,
+    DROP COLUMN service_charge_name,
+    DROP COLUMN service_charge_rule_id,
+    DROP COLUMN tax_rate;
+
+ALTER TABLE items
+    DROP COLUMN tax_inclusive,
+    DROP COLUMN tax_rate,
+    DROP COLUMN tax_name,
+    DROP COLUMN tax_category_id;
@@ -0,0 +1,36 @@
+-- Add up migration script here
+ALTER TABLE items
+    ADD COLUMN tax_category_id UUID,
+    ADD COLUMN tax_name VARCHAR(255),
+    ADD COLUMN tax_rate DECIMAL(6, 4),
+    ADD COLUMN tax_inclusive BOOLEAN;
+
+ALTER TABLE table_sessions
+    ADD COLUMN tax_rate DECIMAL(6, 4),
+    ADD COLUMN service_charge_rule_id UUID,
+    ADD COLUMN service_charge_name VARCHAR(255),
+    ADD COLUMN service_charge_rate DECIMAL(6, 4);
+
+UPDATE items
+SET
+    tax_category_id = tax_categories.id,
+    tax_name = tax_categories.name,
+    tax_rate = tax_categories.rate,
+    tax_inclusive = tax_categories.inclusive
+FROM Menu
+JOIN tax_categories ON Menu.tax_category_id = tax_categories.id
+WHERE items.menu_id = Menu.id AND items.paid_at IS NOT NULL;
+
+UPDATE table_sessions
+SET
+    service_charge_rule_id = rule.id,
+    service_charge_name = rule.name,
+    service_charge_rate = rule.rate
+FROM (
+    SELECT DISTINCT ON (table_sessions.id) table_sessions.id as session_id, service_charge_rules.*
+    FROM table_sessions
+    JOIN service_charge_rules ON service_charge_rules.min_covers <= table_sessions.covers
+    WHERE table_sessions.closed_at IS NOT NULL
+    ORDER BY table_sessions.id, service_charge_rules.min_covers DESC, service_charge_rules.rate DESC
+) rule
+WHERE table_sessions.id = rule.session_id;
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE items DROP CONSTRAINT items_quantities_check;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE items ADD CONSTRAINT items_quantities_check
+    CHECK (quantity > 0 AND delivered_quantity >= 0 AND delivered_quantity <= quantity);
@@ -0,0 +1,240 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use uuid::Uuid;
+
+use crate::billing::checks::allocate;
+use crate::models::{
+    restaurant_models::{BillItem, ChargedTax, Discount, DiscountKind, ServiceChargeRule, TableSession},
+    route_models::{BillLine, BillResponse, DiscountLine, ServiceChargeLine, TaxLine},
+};
+
+/// Name of the tax line for dishes outside any tax category.
+const DEFAULT_TAX_NAME: &str = "Tax";
+
+/// Rounds an amount to whole cents, with halves rounded away from zero. The
+/// result always carries two decimals, so zero is reported as `0.00`.
+pub fn round_money(amount: Decimal) -> Decimal {
+    let mut rounded = amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+    rounded.rescale(2);
+    rounded
+}
+
+/// The tax on `amount` at `rate`, in whole cents. For inclusive prices this is
+/// the part of `amount` that is tax, otherwise it comes on top.
+pub fn tax_on(amount: Decimal, rate: Decimal, inclusive: bool) -> Decimal {
+    if inclusive {
+        round_money(amount * rate / (Decimal::ONE + rate))
+    } else {
+        round_money(amount * rate)
+    }
+}
+
+/// What bills are priced with: the tax categories dishes can be in, the rate
+/// for dishes outside any category and the service charge rules.
+pub struct BillRules {
+    pub default_tax_rate: Decimal,
+    pub tax_categories: Vec<ChargedTax>,
+    pub service_charges: Vec<ServiceChargeRule>,
+}
+
+impl BillRules {
+    /// The rules a closed session was settled with: the tax categories its
+    /// items were charged in and the rate and service charge kept with it, as
+    /// they were at the time. Sessions settled before the rate was kept fall
+    /// back to `default_tax_rate`.
+    pub fn settled(session: &TableSession, tax_categories: Vec<ChargedTax>, default_tax_rate: Decimal) -> BillRules {
+        let service_charge = match (
+            session.service_charge_rule_id,
+            &session.service_charge_name,
+            session.service_charge_rate,
+            session.covers,
+            session.closed_at,
+        ) {
+            (Some(id), Some(name), Some(rate), Some(covers), Some(closed_at)) => Some(ServiceChargeRule {
+                id,
+                name: name.clone(),
+                rate,
+                min_covers: covers,
+                created_at: closed_at,
+            }),
+            _ => None,
+        };
+        BillRules {
+            default_tax_rate: session.tax_rate.unwrap_or(default_tax_rate),
+            tax_categories,
+            service_charges: service_charge.into_iter().collect(),
+        }
+    }
+
+    fn tax_category(&self, tax_category_id: Option<Uuid>) -> Option<&ChargedTax> {
+        let tax_category_id = tax_category_id?;
+        self.tax_categories.iter().find(|category| category.id == tax_category_id)
+    }
+
+    /// The rate a dish is taxed at and whether its price already includes it.
+    pub fn tax_for(&self, tax_category_id: Option<Uuid>) -> (Decimal, bool) {
+        self.tax_category(tax_category_id)
+            .map_or((self.default_tax_rate, false), |category| (category.rate, category.inclusive))
+    }
+
+    /// The rule with the highest threshold the party reaches, if any.
+    pub fn service_charge_for(&self, covers: Option<i32>) -> Option<&ServiceChargeRule> {
+        let covers = covers?;
+        self.service_charges
+            .iter()
+            .filter(|rule| rule.min_covers <= covers)
+            .max_by_key(|rule| (rule.min_covers, rule.rate))
+    }
+}
+
+/// What a discount takes off `base`, which is never more than `base` itself.
+pub fn discount_amount(kind: DiscountKind, value: Decimal, base: Decimal) -> Decimal {
+    let amount = match kind {
+        DiscountKind::Percent => round_money(base * value / Decimal::ONE_HUNDRED),
+        DiscountKind::Fixed => round_money(value),
+    };
+    amount.min(base).max(Decimal::ZERO)
+}
+
+fn discount_line(discount: &Discount, amount: Decimal) -> DiscountLine {
+    DiscountLine {
+        discount_id: discount.id,
+        item_id: discount.items_id,
+        reason: discount.reason.clone(),
+        kind: discount.kind,
+        value: discount.value,
+        amount,
+        applied_by: discount.applied_by.clone(),
+    }
+}
+
+/// What each line of `bill` comes to once its own discounts and its share of
+/// the table discounts are taken off. Table discounts are shared out in
+/// proportion to the discounted lines, as they are between tax categories.
+pub fn discounted_lines(bill: &BillResponse) -> Vec<Decimal> {
+    let table_discount: Decimal = bill
+        .discounts
+        .iter()
+        .filter(|discount| discount.item_id.is_none())
+        .map(|discount| discount.amount)
+        .sum();
+    let amounts: Vec<Decimal> = bill.lines.iter().map(|line| line.line_total - line.discount).collect();
+    let shares = allocate(table_discount, &amounts);
+    amounts.into_iter().zip(shares).map(|(amount, share)| amount - share).collect()
+}
+
+/// Prices every item and totals the bill. Item discounts come off their line
+/// first and table discounts off what is left, in the order they were given;
+/// discounts on items no longer on the bill are ignored. Tax is charged once
+/// per tax category on the discounted lines in it, with table discounts shared
+/// out between the categories. The service charge applies to the discounted
+/// subtotal and is not taxed.
+pub fn build_bill(
+    tables_id: Uuid,
+    covers: Option<i32>,
+    items: Vec<BillItem>,
+    discounts: &[Discount],
+    rules: &BillRules,
+) -> BillResponse {
+    let mut lines: Vec<BillLine> = items
+        .into_iter()
+        .map(|item| BillLine {
+            line_total: round_money(item.unit_price * Decimal::from(item.quantity)),
+            discount: round_money(Decimal::ZERO),
+            item_id: item.item_id,
+            menu_id: item.menu_id,
+            name: item.name,
+            unit_price: item.unit_price,
+            quantity: item.quantity,
+            tax_category_id: rules.tax_category(item.tax_category_id).map(|category| category.id),
+        })
+        .collect();
+
+    let subtotal = round_money(lines.iter().map(|line| line.line_total).sum());
+
+    let mut discounts: Vec<&Discount> = discounts.iter().collect();
+    discounts.sort_by_key(|discount| (discount.items_id.is_none(), discount.created_at, discount.id));
+    let mut discount_lines = vec![];
+    let mut left = subtotal;
+    let mut table_discount = Decimal::ZERO;
+    for discount in discounts {
+        let amount = match discount.items_id {
+            Some(item_id) => {
+                let Some(line) = lines.iter_mut().find(|line| line.item_id == item_id) else {
+                    continue;
+                };
+                let amount = discount_amount(discount.kind, discount.value, line.line_total - line.discount);
+                line.discount = round_money(line.discount + amount);
+                amount
+            }
+            None => {
+                let amount = discount_amount(discount.kind, discount.value, left);
+                table_discount += amount;
+                amount
+            }
+        };
+        left -= amount;
+        discount_lines.push(discount_line(discount, amount));
+    }
+
+    let mut groups: Vec<(Option<Uuid>, Decimal)> = vec![];
+    for line in &lines {
+        let amount = line.line_total - line.discount;
+        match groups.iter_mut().find(|(tax_category_id, _)| *tax_category_id == line.tax_category_id) {
+            Some((_, group_amount)) => *group_amount += amount,
+            None => groups.push((line.tax_category_id, amount)),
+        }
+    }
+    let weights: Vec<Decimal> = groups.iter().map(|(_, amount)| *amount).collect();
+    let table_discounts = allocate(table_discount, &weights);
+
+    let mut taxes: Vec<TaxLine> = groups
+        .into_iter()
+        .zip(table_discounts)
+        .filter_map(|((tax_category_id, amount), table_discount)| {
+            let (rate, inclusive) = rules.tax_for(tax_category_id);
+            if rate.is_zero() {
+                return None;
+            }
+            let amount = amount - table_discount;
+            let tax = tax_on(amount, rate, inclusive);
+            let name = rules
+                .tax_category(tax_category_id)
+                .map_or(DEFAULT_TAX_NAME.to_string(), |category| category.name.clone());
+            Some(TaxLine {
+                tax_category_id,
+                name,
+                rate,
+                inclusive,
+                taxable: round_money(if inclusive { amount - tax } else { amount }),
+                tax,
+            })
+        })
+        .collect();
+    // Categories are listed by name, with the default rate last.
+    taxes.sort_by(|a, b| (a.tax_category_id.is_none(), &a.name).cmp(&(b.tax_category_id.is_none(), &b.name)));
+
+    let discount = round_money(subtotal - left);
+    let discounted = subtotal - discount;
+    let tax = round_money(taxes.iter().map(|line| line.tax).sum());
+    let added_tax: Decimal = taxes.iter().filter(|line| !line.inclusive).map(|line| line.tax).sum();
+    let service_charge = rules.service_charge_for(covers).map(|rule| ServiceChargeLine {
+        rule_id: rule.id,
+        name: rule.name.clone(),
+        rate: rule.rate,
+        amount: round_money(discounted * rule.rate),
+    });
+    let service_amount = service_charge.as_ref().map_or(Decimal::ZERO, |line| line.amount);
+
+    BillResponse {
+        tables_id,
+        covers,
+        lines,
+        subtotal,
+        discounts: discount_lines,
+        discount,
+        taxes,
+        tax,
+        service_charge,
+        total: round_money(discounted + added_tax + service_amount),
+    }
+}
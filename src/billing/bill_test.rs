@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use crate::billing::bill::{build_bill, round_money, BillRules};
+    use crate::models::restaurant_models::{BillItem, Discount, DiscountKind, ChargedTax, ServiceChargeRule};
+
+    fn bill_item(name: &str, unit_price: Decimal, quantity: i32, tax_category_id: Option<Uuid>) -> BillItem {
+        BillItem {
+            item_id: Uuid::new_v4(),
+            menu_id: Uuid::new_v4(),
+            name: name.to_string(),
+            unit_price,
+            quantity,
+            tax_category_id,
+        }
+    }
+
+    fn rules(default_tax_rate: Decimal) -> BillRules {
+        BillRules {
+            default_tax_rate,
+            tax_categories: vec![],
+            service_charges: vec![],
+        }
+    }
+
+    fn tax_category(name: &str, rate: Decimal, inclusive: bool) -> ChargedTax {
+        ChargedTax {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            rate,
+            inclusive,
+        }
+    }
+
+    fn service_charge(rate: Decimal, min_covers: i32) -> ServiceChargeRule {
+        ServiceChargeRule {
+            id: Uuid::new_v4(),
+            name: format!("Parties of {}+", min_covers),
+            rate,
+            min_covers,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    fn discount(items_id: Option<Uuid>, kind: DiscountKind, value: Decimal, minutes_ago: i64) -> Discount {
+        Discount {
+            id: Uuid::new_v4(),
+            tables_id: Uuid::new_v4(),
+            items_id,
+            kind,
+            value,
+            reason: None,
+            promo_code_id: None,
+            applied_by: "Alice".to_string(),
+            session_id: None,
+            created_at: Utc::now().naive_utc() - Duration::minutes(minutes_ago),
+            created_by: None,
+        }
+    }
+
+    #[test]
+    fn test_bill_totals_lines_and_tax() {
+        let items = vec![
+            bill_item("Burger", Decimal::new(599, 2), 3, None),
+            bill_item("Pizza", Decimal::new(899, 2), 1, None),
+        ];
+        let bill = build_bill(Uuid::new_v4(), None, items, &[], &rules(Decimal::new(10, 2)));
+
+        let line_totals: Vec<Decimal> = bill.lines.iter().map(|line| line.line_total).collect();
+        assert_eq!(line_totals, vec![Decimal::new(1797, 2), Decimal::new(899, 2)]);
+        assert_eq!(bill.subtotal, Decimal::new(2696, 2));
+        assert_eq!(bill.tax, Decimal::new(270, 2));
+        assert_eq!(bill.taxes.len(), 1);
+        assert_eq!(bill.total, Decimal::new(2966, 2));
+    }
+
+    #[test]
+    fn test_empty_bill_and_rounding() {
+        let bill = build_bill(Uuid::new_v4(), None, vec![], &[], &rules(Decimal::new(10, 2)));
+        assert!(bill.lines.is_empty());
+        assert!(bill.taxes.is_empty());
+        assert_eq!(bill.total, Decimal::ZERO);
+
+        assert_eq!(round_money(Decimal::new(125, 3)), Decimal::new(13, 2));
+        assert_eq!(round_money(Decimal::new(-125, 3)), Decimal::new(-13, 2));
+    }
+
+    #[test]
+    fn test_tax_categories_and_service_charge() {
+        let food = tax_category("Food", Decimal::new(5, 2), false);
+        let alcohol = tax_category("Alcohol", Decimal::new(20, 2), true);
+        let mut rules = rules(Decimal::ZERO);
+        rules.tax_categories = vec![food.clone(), alcohol.clone()];
+        rules.service_charges = vec![service_charge(Decimal::new(10, 2), 4), service_charge(Decimal::new(125, 3), 8)];
+
+        let items = vec![
+            bill_item("Steak", Decimal::new(2000, 2), 2, Some(food.id)),
+            bill_item("Wine", Decimal::new(3000, 2), 1, Some(alcohol.id)),
+            bill_item("Water", Decimal::new(250, 2), 2, None),
+            bill_item("Salad", Decimal::new(800, 2), 1, Some(food.id)),
+        ];
+        let bill = build_bill(Uuid::new_v4(), Some(8), items, &[], &rules);
+
+        let taxes: Vec<(&str, Decimal, Decimal)> = bill.taxes.iter().map(|line| (line.name.as_str(), line.taxable, line.tax)).collect();
+        assert_eq!(
+            taxes,
+            vec![
+                ("Alcohol", Decimal::new(2500, 2), Decimal::new(500, 2)),
+                ("Food", Decimal::new(4800, 2), Decimal::new(240, 2)),
+            ]
+        );
+        assert_eq!(bill.subtotal, Decimal::new(8300, 2));
+        assert_eq!(bill.tax, Decimal::new(740, 2));
+        let service_charge = bill.service_charge.as_ref().expect("Party of 8 pays service");
+        assert_eq!(service_charge.amount, Decimal::new(1038, 2));
+        // Only the exclusive food tax comes on top of the menu prices.
+        assert_eq!(bill.total, Decimal::new(9578, 2));
+
+        let small_party = build_bill(Uuid::new_v4(), Some(5), vec![bill_item("Water", Decimal::new(1000, 2), 1, None)], &[], &rules);
+        assert_eq!(small_party.service_charge.map(|line| line.amount), Some(Decimal::new(100, 2)));
+        let unknown_party = build_bill(Uuid::new_v4(), None, vec![bill_item("Water", Decimal::new(1000, 2), 1, None)], &[], &rules);
+        assert!(unknown_party.service_charge.is_none());
+    }
+    #[test]
+    fn test_discounts_come_off_lines_before_tax() {
+        let alcohol = tax_category("Alcohol", Decimal::new(20, 2), true);
+        let mut rules = rules(Decimal::new(10, 2));
+        rules.tax_categories = vec![alcohol.clone()];
+
+        let burger = bill_item("Burger", Decimal::new(1000, 2), 2, None);
+        let wine = bill_item("Wine", Decimal::new(3000, 2), 1, Some(alcohol.id));
+        // The table discount was given first but still applies to what is left after the item discount.
+        let discounts = vec![
+            discount(None, DiscountKind::Percent, Decimal::from(10), 10),
+            discount(Some(burger.item_id), DiscountKind::Fixed, Decimal::new(500, 2), 5),
+            discount(Some(Uuid::new_v4()), DiscountKind::Fixed, Decimal::new(100, 2), 1),
+        ];
+        let burger_id = burger.item_id;
+        let bill = build_bill(Uuid::new_v4(), None, vec![burger, wine], &discounts, &rules);
+
+        let amounts: Vec<(Option<Uuid>, Decimal)> = bill.discounts.iter().map(|line| (line.item_id, line.amount)).collect();
+        assert_eq!(amounts, vec![(Some(burger_id), Decimal::new(500, 2)), (None, Decimal::new(450, 2))]);
+        assert_eq!(bill.lines[0].discount, Decimal::new(500, 2));
+        assert_eq!(bill.lines[1].discount, Decimal::new(0, 2));
+        assert_eq!(bill.subtotal, Decimal::new(5000, 2));
+        assert_eq!(bill.discount, Decimal::new(950, 2));
+
+        // The table discount is shared 1.50 / 3.00 between the two rates.
+        let taxes: Vec<(&str, Decimal, Decimal)> = bill.taxes.iter().map(|line| (line.name.as_str(), line.taxable, line.tax)).collect();
+        assert_eq!(
+            taxes,
+            vec![
+                ("Alcohol", Decimal::new(2250, 2), Decimal::new(450, 2)),
+                ("Tax", Decimal::new(1350, 2), Decimal::new(135, 2)),
+            ]
+        );
+        assert_eq!(bill.total, Decimal::new(4185, 2));
+
+        let comped = build_bill(
+            Uuid::new_v4(),
+            None,
+            vec![bill_item("Water", Decimal::new(300, 2), 1, None)],
+            &[discount(None, DiscountKind::Fixed, Decimal::new(1000, 2), 1)],
+            &rules,
+        );
+        assert_eq!(comped.discount, Decimal::new(300, 2));
+        assert_eq!(comped.total, Decimal::new(0, 2));
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use uuid::Uuid;
+
+use crate::billing::{bill::round_money, payments::paid_on_check};
+use crate::db::connection::{CheckItemShare, NewCheckRequest};
+use crate::models::{
+    restaurant_models::{BillItem, Check, CheckItem, Payment},
+    route_models::{BillLine, BillResponse, CheckLine, CheckSummary, ChecksResponse, UnassignedAmount},
+};
+
+/// Splits `total` into whole cents in proportion to `weights`. Cents left over
+/// after rounding down go to the largest remainders, earlier parts first on a
+/// tie, so the parts always add up to exactly `total`. With no weight at all the
+/// whole total goes to the last part.
+pub fn allocate(total: Decimal, weights: &[Decimal]) -> Vec<Decimal> {
+    let Some(last) = weights.len().checked_sub(1) else {
+        return vec![];
+    };
+
+    let total_cents = (total * Decimal::ONE_HUNDRED).round().to_i64().unwrap_or_default();
+    let weight_sum: Decimal = weights.iter().sum();
+    if weight_sum <= Decimal::ZERO {
+        let mut cents = vec![0; weights.len()];
+        cents[last] = total_cents;
+        return cents.into_iter().map(|cents| Decimal::new(cents, 2)).collect();
+    }
+
+    let exact: Vec<Decimal> = weights
+        .iter()
+        .map(|weight| Decimal::from(total_cents) * *weight / weight_sum)
+        .collect();
+    let mut cents: Vec<i64> = exact.iter().map(|exact| exact.floor().to_i64().unwrap_or_default()).collect();
+
+    let mut by_remainder: Vec<usize> = (0..weights.len()).collect();
+    by_remainder.sort_by(|&a, &b| (exact[b] - exact[b].floor()).cmp(&(exact[a] - exact[a].floor())).then(a.cmp(&b)));
+
+    let left_over = total_cents - cents.iter().sum::<i64>();
+    for index in by_remainder.into_iter().cycle().take(left_over.max(0) as usize) {
+        cents[index] += 1;
+    }
+
+    cents.into_iter().map(|cents| Decimal::new(cents, 2)).collect()
+}
+
+/// Puts an equal share of every item on one check per guest.
+pub fn even_split(guests: i32, items: &[BillItem]) -> Vec<NewCheckRequest> {
+    (1..=guests)
+        .map(|guest| NewCheckRequest {
+            name: format!("Guest {}", guest),
+            items: items
+                .iter()
+                .map(|item| CheckItemShare {
+                    item_id: item.item_id,
+                    numerator: 1,
+                    denominator: guests,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Spreads the bill over the checks. Items that were deleted since the split
+/// are ignored and anything not on a check is reported as unassigned, so the
+/// checks and the unassigned amount always add up to the bill. Payments made
+/// on a check count towards its balance.
+pub fn split_bill(bill: &BillResponse, mut checks: Vec<Check>, check_items: Vec<CheckItem>, payments: &[Payment]) -> ChecksResponse {
+    checks.sort_by_key(|check| check.position);
+    let lines: HashMap<Uuid, &BillLine> = bill.lines.iter().map(|line| (line.item_id, line)).collect();
+
+    let mut check_lines: Vec<Vec<CheckLine>> = Vec::with_capacity(checks.len());
+    let mut weights: Vec<Decimal> = Vec::with_capacity(checks.len() + 1);
+    for check in &checks {
+        let mut amount = Decimal::ZERO;
+        let mut lines_on_check = vec![];
+        for share in check_items.iter().filter(|share| share.check_id == check.id) {
+            let Some(line) = lines.get(&share.items_id) else {
+                continue;
+            };
+            amount += (line.line_total - line.discount) * Decimal::from(share.share_numerator)
+                / Decimal::from(share.share_denominator);
+            lines_on_check.push(CheckLine {
+                item_id: line.item_id,
+                name: line.name.clone(),
+                unit_price: line.unit_price,
+                quantity: line.quantity,
+                share_numerator: share.share_numerator,
+                share_denominator: share.share_denominator,
+            });
+        }
+        check_lines.push(lines_on_check);
+        weights.push(amount);
+    }
+    let assigned: Decimal = weights.iter().sum();
+    let lines_after_discounts: Decimal = bill.lines.iter().map(|line| line.line_total - line.discount).sum();
+    weights.push((lines_after_discounts - assigned).max(Decimal::ZERO));
+
+    // Totals, taxes and the service charge are shared out separately so guests
+    // splitting evenly pay amounts at most a cent apart; each subtotal is what
+    // remains of its total once exclusive tax and service charge are taken off,
+    // so discounts are already deducted from it.
+    let service_charge = bill.service_charge.as_ref().map_or(Decimal::ZERO, |line| line.amount);
+    let added_tax: Decimal = bill.taxes.iter().filter(|line| !line.inclusive).map(|line| line.tax).sum();
+    let totals = allocate(bill.total, &weights);
+    let taxes = allocate(bill.tax, &weights);
+    let service_charges = allocate(service_charge, &weights);
+    let added_taxes = allocate(added_tax, &weights);
+    let subtotals: Vec<Decimal> = (0..weights.len())
+        .map(|index| totals[index] - added_taxes[index] - service_charges[index])
+        .collect();
+
+    let summaries = checks
+        .into_iter()
+        .zip(check_lines)
+        .enumerate()
+        .map(|(index, (check, lines))| {
+            let paid = round_money(paid_on_check(payments, check.id));
+            CheckSummary {
+                id: check.id,
+                name: check.name,
+                lines,
+                subtotal: subtotals[index],
+                tax: taxes[index],
+                service_charge: service_charges[index],
+                total: totals[index],
+                paid,
+                balance: round_money(totals[index] - paid),
+            }
+        })
+        .collect::<Vec<CheckSummary>>();
+
+    let unassigned = summaries.len();
+    ChecksResponse {
+        tables_id: bill.tables_id,
+        checks: summaries,
+        unassigned: UnassignedAmount {
+            subtotal: subtotals[unassigned],
+            tax: taxes[unassigned],
+            service_charge: service_charges[unassigned],
+            total: totals[unassigned],
+        },
+        subtotal: bill.subtotal,
+        discount: bill.discount,
+        tax: bill.tax,
+        service_charge: round_money(service_charge),
+        total: bill.total,
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use crate::billing::bill::{build_bill, BillRules};
+    use crate::billing::checks::{allocate, even_split, split_bill};
+    use crate::models::restaurant_models::{BillItem, Check, CheckItem};
+
+    fn bill_item(unit_price: Decimal, quantity: i32) -> BillItem {
+        BillItem {
+            item_id: Uuid::new_v4(),
+            menu_id: Uuid::new_v4(),
+            name: "Dish".to_string(),
+            unit_price,
+            quantity,
+            tax_category_id: None,
+        }
+    }
+
+    fn rules(default_tax_rate: Decimal) -> BillRules {
+        BillRules {
+            default_tax_rate,
+            tax_categories: vec![],
+            service_charges: vec![],
+        }
+    }
+
+    fn check(tables_id: Uuid, position: i32) -> Check {
+        Check {
+            id: Uuid::new_v4(),
+            tables_id,
+            name: format!("Seat {}", position + 1),
+            position,
+            created_at: Utc::now().naive_utc(),
+            created_by: None,
+            session_id: None,
+        }
+    }
+
+    fn share(check: &Check, item: &BillItem, share_numerator: i32, share_denominator: i32) -> CheckItem {
+        CheckItem {
+            check_id: check.id,
+            items_id: item.item_id,
+            share_numerator,
+            share_denominator,
+        }
+    }
+
+    #[test]
+    fn test_allocate_distributes_remainder_cents() {
+        let parts = allocate(Decimal::new(1000, 2), &[Decimal::ONE, Decimal::ONE, Decimal::ONE]);
+        assert_eq!(parts, vec![Decimal::new(334, 2), Decimal::new(333, 2), Decimal::new(333, 2)]);
+
+        let parts = allocate(Decimal::new(101, 2), &[Decimal::ONE, Decimal::TWO]);
+        assert_eq!(parts, vec![Decimal::new(34, 2), Decimal::new(67, 2)]);
+
+        let parts = allocate(Decimal::new(500, 2), &[Decimal::ZERO, Decimal::ZERO]);
+        assert_eq!(parts, vec![Decimal::ZERO, Decimal::new(500, 2)]);
+        assert!(allocate(Decimal::ONE, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_even_split_reconciles_with_bill() {
+        let tables_id = Uuid::new_v4();
+        let items = vec![bill_item(Decimal::new(1000, 2), 1)];
+        let bill = build_bill(tables_id, None, items.clone(), &[], &rules(Decimal::new(10, 2)));
+
+        let checks: Vec<Check> = (0..3).map(|position| check(tables_id, position)).collect();
+        let check_items: Vec<CheckItem> = even_split(3, &items)
+            .into_iter()
+            .zip(&checks)
+            .flat_map(|(new_check, check)| {
+                new_check
+                    .items
+                    .into_iter()
+                    .map(|item_share| CheckItem {
+                        check_id: check.id,
+                        items_id: item_share.item_id,
+                        share_numerator: item_share.numerator,
+                        share_denominator: item_share.denominator,
+                    })
+                    .collect::<Vec<CheckItem>>()
+            })
+            .collect();
+
+        let split = split_bill(&bill, checks, check_items, &[]);
+        let totals: Vec<Decimal> = split.checks.iter().map(|check| check.total).collect();
+        assert_eq!(totals, vec![Decimal::new(367, 2), Decimal::new(367, 2), Decimal::new(366, 2)]);
+        assert_eq!(split.unassigned.total, Decimal::ZERO);
+        assert_eq!(totals.iter().sum::<Decimal>(), bill.total);
+    }
+
+    #[test]
+    fn test_partial_split_reports_unassigned_amount() {
+        let tables_id = Uuid::new_v4();
+        let burger = bill_item(Decimal::new(599, 2), 2);
+        let pizza = bill_item(Decimal::new(899, 2), 1);
+        let wine = bill_item(Decimal::new(2450, 2), 1);
+        let bill = build_bill(tables_id, None, vec![burger.clone(), pizza.clone(), wine.clone()], &[], &rules(Decimal::new(8, 2)));
+
+        let first = check(tables_id, 0);
+        let second = check(tables_id, 1);
+        let check_items = vec![
+            share(&first, &burger, 1, 1),
+            share(&first, &wine, 1, 3),
+            share(&second, &pizza, 1, 1),
+            share(&second, &wine, 1, 3),
+        ];
+        let split = split_bill(&bill, vec![second.clone(), first.clone()], check_items, &[]);
+
+        assert_eq!(split.checks[0].id, first.id);
+        assert_eq!(split.checks[0].subtotal, Decimal::new(2015, 2));
+        assert_eq!(split.checks[1].subtotal, Decimal::new(1716, 2));
+        assert_eq!(split.unassigned.subtotal, Decimal::new(816, 2));
+        assert_eq!(split.unassigned.tax, Decimal::new(66, 2));
+
+        let subtotal: Decimal = split.checks.iter().map(|check| check.subtotal).sum::<Decimal>() + split.unassigned.subtotal;
+        let tax: Decimal = split.checks.iter().map(|check| check.tax).sum::<Decimal>() + split.unassigned.tax;
+        assert_eq!(subtotal, bill.subtotal);
+        assert_eq!(tax, bill.tax);
+    }
+}
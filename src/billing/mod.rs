@@ -0,0 +1,8 @@
+pub mod bill;
+pub mod checks;
+pub mod payments;
+pub mod pricing;
+pub mod refunds;
+mod bill_test;
+mod checks_test;
+mod pricing_test;
\ No newline at end of file
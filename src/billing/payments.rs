@@ -0,0 +1,37 @@
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::billing::bill::round_money;
+use crate::models::{restaurant_models::Payment, route_models::PaymentsResponse};
+
+/// What has been paid towards the bill, tips left out.
+pub fn paid_amount<'a>(payments: impl IntoIterator<Item = &'a Payment>) -> Decimal {
+    payments.into_iter().map(|payment| payment.amount).sum()
+}
+
+/// What has been paid towards a single check.
+pub fn paid_on_check(payments: &[Payment], check_id: Uuid) -> Decimal {
+    paid_amount(payments.iter().filter(|payment| payment.check_id == Some(check_id)))
+}
+
+pub fn summarize_payments(
+    tables_id: Uuid,
+    session_id: Option<Uuid>,
+    payments: Vec<Payment>,
+    total: Decimal,
+    closed: bool,
+) -> PaymentsResponse {
+    let paid = round_money(paid_amount(&payments));
+    let tips = round_money(payments.iter().map(|payment| payment.tip).sum());
+
+    PaymentsResponse {
+        tables_id,
+        session_id,
+        payments,
+        total,
+        paid,
+        tips,
+        balance: round_money(total - paid),
+        closed,
+    }
+}
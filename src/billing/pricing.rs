@@ -0,0 +1,29 @@
+use chrono::{Datelike, Duration, NaiveDateTime};
+use rust_decimal::Decimal;
+
+use crate::models::restaurant_models::PriceRule;
+
+/// Whether the rule is in effect at `at`. Rule days and times are in UTC,
+/// like `at` and every other time the API stores, so a venue away from UTC
+/// sets its windows shifted by its offset. Windows running past midnight
+/// belong to the day they start on.
+pub fn rule_applies(rule: &PriceRule, at: NaiveDateTime) -> bool {
+    let runs_on = |moment: NaiveDateTime| rule.days.contains(&(moment.weekday().number_from_monday() as i32));
+    let time = at.time();
+    if rule.start_time < rule.end_time {
+        runs_on(at) && rule.start_time <= time && time < rule.end_time
+    } else {
+        (runs_on(at) && time >= rule.start_time) || (runs_on(at - Duration::days(1)) && time < rule.end_time)
+    }
+}
+
+/// The price of a dish at `at`: the lowest price of the rules in effect, or
+/// the menu price when none are.
+pub fn price_at(menu_price: Decimal, rules: &[PriceRule], at: NaiveDateTime) -> Decimal {
+    rules
+        .iter()
+        .filter(|rule| rule_applies(rule, at))
+        .map(|rule| rule.price)
+        .min()
+        .unwrap_or(menu_price)
+}
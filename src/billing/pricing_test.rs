@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use crate::billing::pricing::{price_at, rule_applies};
+    use crate::models::restaurant_models::PriceRule;
+
+    fn rule(price: Decimal, days: Vec<i32>, start: (u32, u32), end: (u32, u32)) -> PriceRule {
+        PriceRule {
+            id: Uuid::new_v4(),
+            menu_id: Uuid::new_v4(),
+            name: "Happy hour".to_string(),
+            price,
+            days,
+            start_time: NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(end.0, end.1, 0).unwrap(),
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    /// 2 September 2024 was a Monday.
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 9, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_rules_apply_on_their_days_and_window() {
+        let weekdays = rule(Decimal::new(400, 2), vec![1, 2, 3, 4, 5], (17, 0), (19, 0));
+        assert!(rule_applies(&weekdays, at(2, 17, 0)));
+        assert!(rule_applies(&weekdays, at(6, 18, 59)));
+        assert!(!rule_applies(&weekdays, at(2, 19, 0)));
+        assert!(!rule_applies(&weekdays, at(2, 16, 59)));
+        assert!(!rule_applies(&weekdays, at(7, 17, 30)));
+
+        // Friday late night runs into Saturday morning, but not Friday morning.
+        let late = rule(Decimal::new(300, 2), vec![5], (22, 0), (2, 0));
+        assert!(rule_applies(&late, at(6, 23, 0)));
+        assert!(rule_applies(&late, at(7, 1, 59)));
+        assert!(!rule_applies(&late, at(7, 2, 0)));
+        assert!(!rule_applies(&late, at(6, 1, 0)));
+    }
+
+    #[test]
+    fn test_rule_times_are_utc() {
+        let happy_hour = rule(Decimal::new(400, 2), vec![1], (17, 0), (19, 0));
+        let venue = FixedOffset::east_opt(2 * 3600).unwrap();
+
+        // 17:30 at a venue two hours ahead of UTC is 15:30 UTC, outside the window.
+        let local = venue.from_local_datetime(&at(2, 17, 30)).unwrap();
+        assert!(!rule_applies(&happy_hour, local.naive_utc()));
+        let local = venue.from_local_datetime(&at(2, 19, 30)).unwrap();
+        assert!(rule_applies(&happy_hour, local.naive_utc()));
+
+        // 01:00 on Tuesday at the venue is still Monday in UTC.
+        let late = rule(Decimal::new(300, 2), vec![1], (22, 0), (23, 30));
+        let local = venue.from_local_datetime(&at(3, 1, 0)).unwrap();
+        assert!(rule_applies(&late, local.naive_utc()));
+    }
+
+    #[test]
+    fn test_lowest_price_in_effect_wins() {
+        let menu_price = Decimal::new(600, 2);
+        let rules = vec![
+            rule(Decimal::new(450, 2), vec![1, 2, 3, 4, 5, 6, 7], (16, 0), (20, 0)),
+            rule(Decimal::new(350, 2), vec![1], (17, 0), (18, 0)),
+        ];
+        assert_eq!(price_at(menu_price, &rules, at(2, 12, 0)), menu_price);
+        assert_eq!(price_at(menu_price, &rules, at(2, 16, 30)), Decimal::new(450, 2));
+        assert_eq!(price_at(menu_price, &rules, at(2, 17, 30)), Decimal::new(350, 2));
+        assert_eq!(price_at(menu_price, &rules, at(3, 17, 30)), Decimal::new(450, 2));
+        assert_eq!(price_at(menu_price, &[], at(3, 17, 30)), menu_price);
+    }
+}
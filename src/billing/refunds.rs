@@ -0,0 +1,14 @@
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::models::restaurant_models::Payment;
+
+/// What has been refunded of a payment so far, as `(amount, tip)`.
+pub fn refunded_amounts(payments: &[Payment], payment_id: Uuid) -> (Decimal, Decimal) {
+    payments
+        .iter()
+        .filter(|payment| payment.refund_of == Some(payment_id))
+        .fold((Decimal::ZERO, Decimal::ZERO), |(amount, tip), refund| {
+            (amount - refund.amount, tip - refund.tip)
+        })
+}
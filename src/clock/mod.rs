@@ -0,0 +1,37 @@
+use chrono::{NaiveDateTime, Utc};
+
+/// Source of the current time for rules that depend on it, so they can be
+/// tested at a chosen moment. Times are UTC, like the rest of the database.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> NaiveDateTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Utc::now().naive_utc()
+    }
+}
+
+/// A clock that only moves when told to.
+#[cfg(test)]
+pub struct FixedClock(std::sync::Mutex<NaiveDateTime>);
+
+#[cfg(test)]
+impl FixedClock {
+    pub fn new(now: NaiveDateTime) -> Self {
+        FixedClock(std::sync::Mutex::new(now))
+    }
+
+    pub fn set(&self, now: NaiveDateTime) {
+        *self.0.lock().expect("Clock lock poisoned") = now;
+    }
+}
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> NaiveDateTime {
+        *self.0.lock().expect("Clock lock poisoned")
+    }
+}
@@ -1,14 +1,89 @@
 use std::env;
+use std::time::Duration;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use log::info;
+use rust_decimal::Decimal;
 
 
+/// What is printed around every receipt, and how wide the paper is.
+#[derive(Debug, Clone)]
+pub struct ReceiptSettings {
+    pub name: String,
+    pub header: Vec<String>,
+    pub footer: Vec<String>,
+    /// Characters per line, 42 for most 80mm printers and 32 for 58mm ones.
+    pub width: usize,
+}
+
+impl ReceiptSettings {
+    fn from_env() -> Result<Self> {
+        let lines = |var: &str| -> Vec<String> {
+            env::var(var)
+                .map(|value| value.split('|').map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect())
+                .unwrap_or_default()
+        };
+        let width = match env::var("RECEIPT_WIDTH") {
+            Ok(value) => value.trim().parse::<usize>().context("RECEIPT_WIDTH must be a number of characters")?,
+            Err(_) => 42,
+        };
+        if !(24..=64).contains(&width) {
+            bail!("RECEIPT_WIDTH must be between 24 and 64, got {}", width);
+        }
+
+        Ok(ReceiptSettings {
+            name: env::var("RECEIPT_NAME").unwrap_or_else(|_| "Restaurant".to_string()),
+            header: lines("RECEIPT_HEADER"),
+            footer: lines("RECEIPT_FOOTER"),
+            width,
+        })
+    }
+}
+
+/// When items count as overdue, and how often the kitchen is checked for them.
+#[derive(Debug, Clone)]
+pub struct AlertSettings {
+    /// Minutes an item may wait past its dish's `prep_time` before an alert
+    /// is raised, unless the dish sets its own.
+    pub grace_minutes: i32,
+    pub scan_interval: Duration,
+}
+
+impl AlertSettings {
+    fn from_env() -> Result<Self> {
+        let grace_minutes = match env::var("OVERDUE_GRACE_MINUTES") {
+            Ok(value) => value.trim().parse::<i32>().context("OVERDUE_GRACE_MINUTES must be a number of minutes")?,
+            Err(_) => 5,
+        };
+        if grace_minutes < 0 {
+            bail!("OVERDUE_GRACE_MINUTES cannot be negative, got {}", grace_minutes);
+        }
+        let scan_seconds = match env::var("OVERDUE_SCAN_SECONDS") {
+            Ok(value) => value.trim().parse::<u64>().context("OVERDUE_SCAN_SECONDS must be a number of seconds")?,
+            Err(_) => 60,
+        };
+        if scan_seconds == 0 {
+            bail!("OVERDUE_SCAN_SECONDS must be at least 1");
+        }
+
+        Ok(AlertSettings {
+            grace_minutes,
+            scan_interval: Duration::from_secs(scan_seconds),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Config {
     pub host: String,
     pub port: String,
     pub db_url: String,
+    /// Sales tax applied to bills, as a fraction (`0.10` for 10%).
+    pub tax_rate: Decimal,
+    pub receipt: ReceiptSettings,
+    /// Cooks working in parallel, for estimating when items will be ready.
+    pub kitchen_cooks: usize,
+    pub alerts: AlertSettings,
 }
 
 
@@ -19,13 +94,40 @@ impl Config {
             .context("PORT environment variable is not set")?;
         let db_url = env::var("DB_URL")
             .context("DB_URL environment variable is not set")?;
+        let tax_rate = match env::var("TAX_RATE") {
+            Ok(value) => value
+                .trim()
+                .parse::<Decimal>()
+                .context("TAX_RATE must be a decimal such as 0.10")?,
+            Err(_) => Decimal::ZERO,
+        };
+        if tax_rate < Decimal::ZERO || tax_rate > Decimal::ONE {
+            bail!("TAX_RATE must be between 0 and 1, got {}", tax_rate);
+        }
+
+        let receipt = ReceiptSettings::from_env()?;
+        let kitchen_cooks = match env::var("KITCHEN_COOKS") {
+            Ok(value) => value.trim().parse::<usize>().context("KITCHEN_COOKS must be a number of cooks")?,
+            Err(_) => 1,
+        };
+        if kitchen_cooks == 0 {
+            bail!("KITCHEN_COOKS must be at least 1");
+        }
+        let alerts = AlertSettings::from_env()?;
 
-        info!("Configuration loaded: host={}, port={}, db_url={}", host, port, db_url);
+        info!(
+            "Configuration loaded: host={}, port={}, db_url={}, tax_rate={}, kitchen_cooks={}",
+            host, port, db_url, tax_rate, kitchen_cooks
+        );
 
         Ok(Config {
             host,
             port,
             db_url,
+            tax_rate,
+            receipt,
+            kitchen_cooks,
+            alerts,
         })
     }
 }
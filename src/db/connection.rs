@@ -1,11 +1,20 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, NaiveTime};
 use log::{info, error};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::{Error, PgPool};
+use sqlx::{postgres::PgConnectOptions, Error, Executor, PgPool, Postgres, Transaction};
 use uuid::Uuid;
 // use chrono::Utc;
 
-use crate::models::{restaurant_models::{PartialItem, PartialItemReturn, Table, Menu}, route_models::{FilterParams, Pagination}};
+use crate::billing::pricing::price_at;
+use crate::db::repository::{AlertRepository, BillingRepository, CheckRepository, DayTotals, DeviceRepository, DiscountRepository, ItemRepository, KitchenRepository, MenuRepository, PaymentRepository, PriceRuleRepository, ReportRepository, TableRepository, TaxRepository};
+use crate::error::AppError;
+use crate::models::{restaurant_models::{AlertThreshold, BillItem, ChargedTax, Check, CheckItem, DeletedItem, Device, Discount, DiscountKind, ItemAlert, ItemDelivery, ItemStatus, KitchenQueueItem, PartialItem, PartialItemReturn, Payment, PriceRule, PromoCode, RefundReason, RefundableItem, ServiceChargeRule, Table, TableSession, TaxCategory, Tender, Menu, ZReport}, route_models::{FilterParams, Pagination, MAX_ITEMS_LIMIT}};
+use crate::validation::items::check_item_updates;
 
 pub struct Database {
     pub pool: PgPool,
@@ -16,130 +25,2783 @@ pub struct NewItemRequest {
     pub menu_id: Uuid,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct BulkNewItemRequest {
-    pub items: Vec<NewItemRequest>,
-}
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BulkNewItemRequest {
+    pub items: Vec<NewItemRequest>,
+}
+
+/// How a numeric item field is changed: `{"set": 3}` replaces the stored value,
+/// `{"increment": 1}` adds to it (use a negative value to decrement).
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldUpdate {
+    Set(i32),
+    Increment(i32),
+}
+
+impl FieldUpdate {
+    pub fn apply(&self, current: i32) -> i32 {
+        match self {
+            FieldUpdate::Set(value) => *value,
+            FieldUpdate::Increment(delta) => current.saturating_add(*delta),
+        }
+    }
+
+    /// Splits an optional update into the `(set, increment)` pair bound by the
+    /// update queries. A missing update binds two NULLs and keeps the stored value.
+    fn binds(update: Option<FieldUpdate>) -> (Option<i32>, Option<i32>) {
+        match update {
+            Some(FieldUpdate::Set(value)) => (Some(value), None),
+            Some(FieldUpdate::Increment(delta)) => (None, Some(delta)),
+            None => (None, None),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateItemRequest {
+    pub id: Uuid,
+    pub quantity: Option<FieldUpdate>,
+    pub delivered_quantity: Option<FieldUpdate>
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BulkUpdateItemRequest {
+    pub items: Vec<UpdateItemRequest>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewTableRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateTableRequest {
+    pub name: Option<String>,
+    pub covers: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewMenuRequest {
+    pub name: String,
+    pub price: Decimal,
+    pub prep_time: i32,
+    pub tax_category_id: Option<Uuid>,
+}
+
+/// How a dish's tax category is changed: `{"set": "<tax category id>"}` moves
+/// it to that category, `"clear"` takes it out of any so it is taxed at the
+/// default rate.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxCategoryUpdate {
+    Set(Uuid),
+    Clear,
+}
+
+impl TaxCategoryUpdate {
+    pub fn apply(&self) -> Option<Uuid> {
+        match self {
+            TaxCategoryUpdate::Set(tax_category_id) => Some(*tax_category_id),
+            TaxCategoryUpdate::Clear => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateMenuRequest {
+    pub price: Option<Decimal>,
+    pub prep_time: Option<i32>,
+    pub tax_category_id: Option<TaxCategoryUpdate>,
+}
+
+/// A price rule as created or replaced through the API. `days` are numbered
+/// `1` for Monday to `7` for Sunday, and both days and times are in UTC.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewPriceRuleRequest {
+    pub name: String,
+    pub price: Decimal,
+    pub days: Vec<i32>,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewTaxCategoryRequest {
+    pub name: String,
+    pub rate: Decimal,
+    #[serde(default)]
+    pub inclusive: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateTaxCategoryRequest {
+    pub name: Option<String>,
+    pub rate: Option<Decimal>,
+    pub inclusive: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewServiceChargeRuleRequest {
+    pub name: String,
+    pub rate: Decimal,
+    pub min_covers: i32,
+}
+
+fn whole_share() -> i32 {
+    1
+}
+
+/// Part of an item put on a check. Leaving out the fraction puts the whole item on the check.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CheckItemShare {
+    pub item_id: Uuid,
+    #[serde(default = "whole_share")]
+    pub numerator: i32,
+    #[serde(default = "whole_share")]
+    pub denominator: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewCheckRequest {
+    pub name: String,
+    pub items: Vec<CheckItemShare>,
+}
+
+/// How a table's bill is split: `{"even": 3}` shares every item equally between
+/// three guests, `{"checks": [...]}` lists the checks and the items on each.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitChecksRequest {
+    Even(i32),
+    Checks(Vec<NewCheckRequest>),
+}
+
+/// A payment as sent by the till. Leaving out `amount` pays whatever is left,
+/// on the check if one is given, and `tendered` is only needed for cash.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewPaymentRequest {
+    pub tender: Tender,
+    pub amount: Option<Decimal>,
+    pub tip: Option<Decimal>,
+    pub tendered: Option<Decimal>,
+    pub check_id: Option<Uuid>,
+}
+
+/// A checked payment, ready to be recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewPayment {
+    pub check_id: Option<Uuid>,
+    pub tender: Tender,
+    pub amount: Decimal,
+    pub tip: Decimal,
+    pub tendered: Decimal,
+    pub change: Decimal,
+}
+
+/// What a payment that pays off the table settles: the items on the bill,
+/// each with the tax category it was charged in, and the rate and service
+/// charge the bill was priced with. They are kept with the session so its
+/// bill reads the same after the rules change.
+#[derive(Debug, Clone)]
+pub struct Settlement {
+    pub items: Vec<SettledItem>,
+    pub tax_rate: Decimal,
+    pub service_charge: Option<ServiceChargeRule>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SettledItem {
+    pub item_id: Uuid,
+    pub tax_category: Option<ChargedTax>,
+}
+
+#[derive(Debug)]
+pub enum AddPaymentOutcome {
+    Recorded { payment: Payment, closed: bool },
+    /// Another payment was recorded in the meantime, so the balance it was checked against is out of date.
+    Stale,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RefundItemRequest {
+    pub item_id: Uuid,
+    pub quantity: i32,
+}
+
+/// A refund of a payment. Leaving out `amount` refunds the listed items at the
+/// price they were paid, tax included.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewRefundRequest {
+    pub amount: Option<Decimal>,
+    pub tip: Option<Decimal>,
+    pub reason: RefundReason,
+    pub approved_by: String,
+    #[serde(default)]
+    pub items: Vec<RefundItemRequest>,
+}
+
+/// A checked refund, ready to be recorded. Amounts are positive here and
+/// stored negated.
+#[derive(Debug)]
+pub struct NewRefund {
+    pub amount: Decimal,
+    pub tip: Decimal,
+    pub reason: RefundReason,
+    pub approved_by: String,
+    pub items: Vec<RefundItemRequest>,
+}
+
+#[derive(Debug)]
+pub enum AddRefundOutcome {
+    Recorded(Payment),
+    /// Something was refunded in the meantime, so the refund was checked against out of date amounts.
+    Stale,
+}
+
+/// A discount to apply to a table, or to one of its items with `item_id`.
+/// Either `promo_code` is given, or `kind` and `value` for a manual discount.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewDiscountRequest {
+    pub item_id: Option<Uuid>,
+    pub kind: Option<DiscountKind>,
+    pub value: Option<Decimal>,
+    pub promo_code: Option<String>,
+    pub reason: Option<String>,
+    pub applied_by: String,
+}
+
+#[derive(Debug)]
+pub struct NewDiscount {
+    pub items_id: Option<Uuid>,
+    pub kind: DiscountKind,
+    pub value: Decimal,
+    pub reason: Option<String>,
+    pub promo_code_id: Option<Uuid>,
+    pub applied_by: String,
+}
+
+#[derive(Debug)]
+pub enum AddDiscountOutcome {
+    Added(Discount),
+    /// The promo code reached its usage limit in the meantime.
+    PromoCodeUsedUp,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NewPromoCodeRequest {
+    pub code: String,
+    pub kind: DiscountKind,
+    pub value: Decimal,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+    pub max_uses: Option<i32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RetireMenuOutcome {
+    Deleted,
+    Retired,
+    NotFound,
+}
+
+/// What a business day close covers: the sessions settled, the payments and
+/// refunds taken and the items voided since the last close.
+#[derive(Debug, Clone)]
+pub struct UnreportedSales {
+    pub sessions: Vec<TableSession>,
+    pub payments: Vec<Payment>,
+    pub voids: Vec<BillItem>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateItemStatusRequest {
+    pub status: ItemStatus,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AlertThresholdRequest {
+    pub grace_minutes: i32,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ItemStatusOutcome {
+    /// With the overdue alert serving the item resolved, if it had one.
+    Updated(Option<Uuid>),
+    NotFound,
+    /// The item is in the given status, from which the requested one cannot be reached.
+    Illegal(ItemStatus),
+}
+
+/// The error for an item update its status does not allow, see
+/// `ItemStatus::after_delivery`.
+pub fn delivery_refused(item_id: Uuid, status: ItemStatus) -> AppError {
+    match status {
+        ItemStatus::Served => AppError::Conflict(format!("Item with id {} has been served and can no longer be changed", item_id)),
+        status => AppError::Conflict(format!("Item with id {} is {} and cannot be delivered until it is ready", item_id, status)),
+    }
+}
+
+/// An item `update_items` changed, whether the change delivered some of it,
+/// and the overdue alert delivering all of it resolved, if it had one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpdatedItem {
+    pub id: Uuid,
+    pub delivered: bool,
+    pub resolved_alert: Option<Uuid>,
+}
+
+/// What a scan for overdue items changed: the alerts it raised, and those of
+/// items since deleted or delivered it resolved.
+#[derive(Debug, Default)]
+pub struct OverdueAlerts {
+    pub raised: Vec<ItemAlert>,
+    pub resolved: Vec<ItemAlert>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DeleteTableOutcome {
+    /// The table is gone, with the items it had, as they were.
+    Deleted(Vec<PartialItemReturn>),
+    NotFound,
+    PendingItems(i64),
+    /// The table took payments or had items settled or voided, which are kept
+    /// for the reports.
+    HasSales,
+}
+
+/// A `z_reports` row, with the totals still as stored JSON.
+struct ZReportRow {
+    id: Uuid,
+    number: i64,
+    opened_at: Option<NaiveDateTime>,
+    closed_at: NaiveDateTime,
+    closed_by: Option<String>,
+    totals: String,
+}
+
+impl ZReportRow {
+    fn into_report(self) -> Result<ZReport, Error> {
+        Ok(ZReport {
+            id: self.id,
+            number: self.number,
+            opened_at: self.opened_at,
+            closed_at: self.closed_at,
+            closed_by: self.closed_by,
+            totals: serde_json::from_str(&self.totals).map_err(|error| Error::Decode(Box::new(error)))?,
+        })
+    }
+}
+
+impl Database {
+    pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
+        let pool = PgPool::connect_with(Self::connect_options(database_url)?).await?;
+        Ok(Database { pool })
+    }
+
+    /// Connects in UTC, so the timestamps the database stamps itself are on
+    /// the same clock as those the server passes in.
+    pub fn connect_options(database_url: &str) -> Result<PgConnectOptions, sqlx::Error> {
+        Ok(PgConnectOptions::from_str(database_url)?.options([("timezone", "UTC")]))
+    }
+
+    /// Returns the given alerts, the longest overdue first.
+    async fn alerts_by_id<'e>(executor: impl Executor<'e, Database = Postgres>, alert_ids: &[Uuid]) -> Result<Vec<ItemAlert>, Error> {
+        sqlx::query_as!(
+            ItemAlert,
+            r#"
+            SELECT
+                item_alerts.id,
+                item_alerts.items_id,
+                items.tables_id,
+                Tables.name as table_name,
+                items.menu_id,
+                items.menu_name,
+                items.quantity,
+                items.delivered_quantity,
+                item_alerts.overdue_at,
+                item_alerts.raised_at,
+                item_alerts.resolved_at
+            FROM item_alerts
+            JOIN items ON item_alerts.items_id = items.id
+            JOIN Tables ON items.tables_id = Tables.id
+            WHERE item_alerts.id = ANY($1)
+            ORDER BY item_alerts.overdue_at, item_alerts.id
+            "#,
+            alert_ids
+        )
+        .fetch_all(executor)
+        .await
+    }
+
+    /// Locks the items, returning their `(quantity, delivered_quantity, status)`.
+    async fn lock_item_quantities(
+        tx: &mut Transaction<'_, Postgres>,
+        item_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, (i32, i32, ItemStatus)>, Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, quantity, delivered_quantity, status as "status: ItemStatus"
+            FROM items
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+            item_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.id, (row.quantity, row.delivered_quantity, row.status)))
+            .collect())
+    }
+
+    /// Reads the sales the next close reports. Settlements and payments wait
+    /// until the close is over and the voids it reads can no longer be
+    /// restored, so the sales cannot change while they are totalled.
+    async fn lock_unreported_sales(tx: &mut Transaction<'_, Postgres>) -> Result<UnreportedSales, Error> {
+        sqlx::query!("LOCK TABLE table_sessions, payments IN SHARE MODE")
+            .execute(&mut *tx)
+            .await?;
+
+        let sessions = sqlx::query_as!(
+            TableSession,
+            r#"
+            SELECT
+                id,
+                tables_id,
+                opened_at,
+                closed_at,
+                closed_by,
+                receipt_number,
+                covers,
+                z_report_id,
+                tax_rate,
+                service_charge_rule_id,
+                service_charge_name,
+                service_charge_rate
+            FROM table_sessions
+            WHERE closed_at IS NOT NULL AND z_report_id IS NULL
+            ORDER BY closed_at, receipt_number
+            "#
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let payments = sqlx::query_as!(
+            Payment,
+            r#"
+            SELECT
+                id,
+                session_id,
+                check_id,
+                tender as "tender: Tender",
+                amount,
+                tip,
+                tendered,
+                change,
+                refund_of,
+                reason as "reason: RefundReason",
+                approved_by,
+                created_at,
+                created_by
+            FROM payments
+            WHERE z_report_id IS NULL
+            ORDER BY created_at, id
+            "#
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let voids = sqlx::query_as!(
+            BillItem,
+            r#"
+            SELECT
+                items.id as item_id,
+                items.menu_id,
+                items.menu_name as name,
+                items.unit_price,
+                items.quantity,
+                Menu.tax_category_id
+            FROM items
+            LEFT JOIN Menu ON items.menu_id = Menu.id
+            WHERE items.deleted_at IS NOT NULL
+              AND items.z_report_id IS NULL
+            ORDER BY items.deleted_at, items.id
+            FOR UPDATE OF items
+            "#
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        Ok(UnreportedSales { sessions, payments, voids })
+    }
+
+    /// Logs every change in `delivered_quantity` as a delivery event, keeps
+    /// `delivered_at` in sync with whether the item is fully delivered and
+    /// serves ready items once they are. The changes must have been checked
+    /// with `ItemStatus::after_delivery`. Returns the overdue alerts the
+    /// deliveries resolved, by item id.
+    async fn record_deliveries(
+        tx: &mut Transaction<'_, Postgres>,
+        previous: &HashMap<Uuid, (i32, i32, ItemStatus)>,
+        updated: &[(Uuid, i32, i32)],
+        actor: &str,
+    ) -> Result<HashMap<Uuid, Uuid>, Error> {
+        for (item_id, _, delivered_quantity) in updated {
+            let before = previous.get(item_id).map_or(*delivered_quantity, |(_, delivered_quantity, _)| *delivered_quantity);
+            let delivered = delivered_quantity - before;
+            if delivered == 0 {
+                continue;
+            }
+
+            sqlx::query!(
+                r#"
+                INSERT INTO item_deliveries (items_id, quantity, delivered_by)
+                VALUES ($1, $2, $3)
+                "#,
+                item_id,
+                delivered,
+                actor
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let item_ids: Vec<Uuid> = updated.iter().map(|(item_id, _, _)| *item_id).collect();
+        sqlx::query!(
+            r#"
+            UPDATE items
+            SET delivered_at = CASE
+                    WHEN delivered_quantity >= quantity THEN COALESCE(delivered_at, CURRENT_TIMESTAMP)
+                    ELSE NULL
+                END,
+                status = CASE
+                    WHEN status = 'ready' AND delivered_quantity >= quantity THEN 'served'
+                    ELSE status
+                END,
+                served_at = CASE
+                    WHEN status = 'ready' AND delivered_quantity >= quantity THEN CURRENT_TIMESTAMP
+                    ELSE served_at
+                END
+            WHERE id = ANY($1)
+            "#,
+            &item_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let resolved = sqlx::query!(
+            r#"
+            UPDATE item_alerts
+            SET resolved_at = CURRENT_TIMESTAMP
+            FROM items
+            WHERE item_alerts.items_id = items.id
+              AND items.id = ANY($1)
+              AND items.delivered_quantity >= items.quantity
+              AND item_alerts.resolved_at IS NULL
+            RETURNING item_alerts.items_id, item_alerts.id
+            "#,
+            &item_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        Ok(resolved.into_iter().map(|alert| (alert.items_id, alert.id)).collect())
+    }
+}
+
+#[async_trait]
+impl DeviceRepository for Database {
+    async fn get_device(&self, device_id: Uuid) -> Result<Device, Error> {
+        let device = sqlx::query_as!(
+            Device,
+            r#"
+            SELECT
+                id,
+                name
+            FROM device
+            WHERE id = $1
+            "#,
+            device_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(device)
+    }
+}
+
+#[async_trait]
+impl TableRepository for Database {
+    async fn get_tables(&self) -> Result<Vec<Table>, Error> {
+        let tables = sqlx::query_as!(
+            Table,
+            r#"
+            SELECT
+                id,
+                name,
+                covers
+            FROM tables
+        "#)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(tables)
+    }
+
+    async fn add_table(&self, name: String) -> Result<Table, Error> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO tables (id, name)
+            VALUES ($1, $2)
+            "#,
+            id,
+            name
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(Table { id, name, covers: None })
+    }
+
+    async fn get_table(&self, tables_id: Uuid) -> Result<Table, Error> {
+        let table = sqlx::query_as!(
+            Table,
+            r#"
+            SELECT
+                id,
+                name,
+                covers
+            FROM tables
+            WHERE id = $1
+            "#,
+            tables_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(table)
+    }
+
+    async fn update_table(&self, tables_id: Uuid, updated_table: UpdateTableRequest) -> Result<Option<Table>, Error> {
+        let table = sqlx::query_as!(
+            Table,
+            r#"
+            UPDATE tables
+            SET
+                name = COALESCE($2, name),
+                covers = COALESCE($3, covers)
+            WHERE id = $1
+            RETURNING id, name, covers
+            "#,
+            tables_id,
+            updated_table.name,
+            updated_table.covers
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(table)
+    }
+
+    async fn delete_table(&self, tables_id: Uuid) -> Result<DeleteTableOutcome, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let exists = sqlx::query!(
+            r#"
+            SELECT id
+            FROM tables
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            tables_id
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+
+        if exists.is_none() {
+            return Ok(DeleteTableOutcome::NotFound);
+        }
+
+        let pending = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM items
+            WHERE tables_id = $1
+              AND quantity > delivered_quantity
+              AND deleted_at IS NULL
+            "#,
+            tables_id
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        if pending > 0 {
+            return Ok(DeleteTableOutcome::PendingItems(pending));
+        }
+
+        let has_sales = sqlx::query_scalar!(
+            r#"
+            SELECT
+                EXISTS (SELECT 1 FROM table_sessions WHERE tables_id = $1)
+                OR EXISTS (
+                    SELECT 1
+                    FROM items
+                    WHERE tables_id = $1 AND (session_id IS NOT NULL OR deleted_at IS NOT NULL)
+                ) as "has_sales!"
+            "#,
+            tables_id
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        if has_sales {
+            return Ok(DeleteTableOutcome::HasSales);
+        }
+
+        // The table row is locked, so no item can be added to it any more.
+        let items = sqlx::query_as!(
+            PartialItemReturn,
+            r#"
+            SELECT
+                items.id,
+                items.tables_id,
+                items.menu_id,
+                items.menu_name,
+                items.unit_price,
+                items.quantity,
+                items.delivered_quantity,
+                items.delivered_at,
+                items.paid_at,
+                items.refunded_quantity,
+                items.created_at,
+                Menu.prep_time as prep_time,
+                items.status as "status: ItemStatus",
+                items.started_at,
+                items.ready_at,
+                items.served_at
+            FROM items
+            LEFT JOIN Menu on menu_id = Menu.id
+            WHERE items.tables_id = $1
+            ORDER BY items.created_at, items.id
+            FOR UPDATE OF items
+            "#,
+            tables_id
+        )
+        .fetch_all(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM item_deliveries
+            WHERE items_id IN (SELECT id FROM items WHERE tables_id = $1)
+            "#,
+            tables_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM checks
+            WHERE tables_id = $1
+            "#,
+            tables_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        // Promo codes redeemed on the open bill can be used again.
+        sqlx::query!(
+            r#"
+            UPDATE promo_codes
+            SET uses = GREATEST(uses - redeemed.count, 0)
+            FROM (
+                SELECT promo_code_id, COUNT(*) as count
+                FROM discounts
+                WHERE tables_id = $1 AND promo_code_id IS NOT NULL
+                GROUP BY promo_code_id
+            ) redeemed
+            WHERE promo_codes.id = redeemed.promo_code_id
+            "#,
+            tables_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM discounts
+            WHERE tables_id = $1
+            "#,
+            tables_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM items
+            WHERE tables_id = $1
+            "#,
+            tables_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM tables
+            WHERE id = $1
+            "#,
+            tables_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(DeleteTableOutcome::Deleted(items))
+    }
+}
+
+#[async_trait]
+impl MenuRepository for Database {
+    async fn get_menu(&self, include_retired: bool) -> Result<Vec<Menu>, Error> {
+        let menu = sqlx::query_as!(
+            Menu,
+            r#"
+            SELECT *
+            FROM Menu
+            WHERE $1 OR retired_at IS NULL
+            ORDER BY name
+        "#,
+            include_retired
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(menu)
+    }
+
+    async fn get_menu_item(&self, menu_id: Uuid) -> Result<Menu, Error> {
+        let menu = sqlx::query_as!(
+            Menu,
+            r#"
+            SELECT *
+            FROM Menu
+            WHERE id = $1
+            "#,
+            menu_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(menu)
+    }
+
+    async fn add_menu(&self, name: String, price: Decimal, prep_time: i32, tax_category_id: Option<Uuid>) -> Result<Menu, Error> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO Menu (id, name, price, prep_time, tax_category_id)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            id,
+            name,
+            price,
+            prep_time,
+            tax_category_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(Menu {
+            id,
+            name,
+            price,
+            prep_time,
+            retired_at: None,
+            tax_category_id,
+        })
+    }
+
+    async fn update_menu(&self, menu_id: Uuid, updated_menu: UpdateMenuRequest) -> Result<Option<Menu>, Error> {
+        let menu = sqlx::query_as!(
+            Menu,
+            r#"
+            UPDATE Menu
+            SET
+                price = COALESCE($2, price),
+                prep_time = COALESCE($3, prep_time),
+                tax_category_id = CASE WHEN $4 THEN $5 ELSE tax_category_id END
+            WHERE id = $1 AND retired_at IS NULL
+            RETURNING *
+            "#,
+            menu_id,
+            updated_menu.price,
+            updated_menu.prep_time,
+            updated_menu.tax_category_id.is_some(),
+            updated_menu.tax_category_id.and_then(|update| update.apply())
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(menu)
+    }
+
+    async fn retire_menu(&self, menu_id: Uuid) -> Result<RetireMenuOutcome, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let exists = sqlx::query!(
+            r#"
+            SELECT id
+            FROM Menu
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            menu_id
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+
+        if exists.is_none() {
+            return Ok(RetireMenuOutcome::NotFound);
+        }
+
+        let referenced = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM items WHERE menu_id = $1) as "referenced!"
+            "#,
+            menu_id
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        if referenced {
+            sqlx::query!(
+                r#"
+                UPDATE Menu
+                SET retired_at = COALESCE(retired_at, CURRENT_TIMESTAMP)
+                WHERE id = $1
+                "#,
+                menu_id
+            )
+            .execute(&mut tx)
+            .await?;
+        } else {
+            sqlx::query!(
+                r#"
+                DELETE FROM Menu
+                WHERE id = $1
+                "#,
+                menu_id
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        if referenced {
+            Ok(RetireMenuOutcome::Retired)
+        } else {
+            Ok(RetireMenuOutcome::Deleted)
+        }
+    }
+
+    async fn get_orderable_menu_ids(&self, menu_ids: &[Uuid]) -> Result<HashSet<Uuid>, Error> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM Menu
+            WHERE id = ANY($1) AND retired_at IS NULL
+            "#,
+            menu_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids.into_iter().collect())
+    }
+}
+
+#[async_trait]
+impl ItemRepository for Database {
+    async fn get_all_remaining_items_from_table(
+        &self,
+        tables_id: Uuid,
+        pagination: Pagination,
+        filters: FilterParams,
+    ) -> Result<Vec<PartialItemReturn>, sqlx::Error> {
+        let limit = pagination.limit();
+        let offset = pagination.offset();
+
+        let items = sqlx::query_as!(
+            PartialItemReturn,
+            r#"
+            SELECT
+                items.id,
+                items.tables_id,
+                items.menu_id,
+                items.menu_name,
+                items.unit_price,
+                items.quantity,
+                items.delivered_quantity,
+                items.delivered_at,
+                items.paid_at,
+                items.refunded_quantity,
+                items.created_at,
+                Menu.prep_time as prep_time,
+                items.status as "status: ItemStatus",
+                items.started_at,
+                items.ready_at,
+                items.served_at
+            FROM items
+            LEFT JOIN Menu on menu_id = Menu.id
+            WHERE tables_id = $1
+              AND ($2::uuid IS NULL OR menu_id = $2)
+              AND quantity > delivered_quantity
+              AND items.deleted_at IS NULL
+            ORDER BY items.created_at, items.id
+            LIMIT $3 OFFSET $4
+            "#,
+            tables_id,
+            filters.menu_id,
+            limit as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    async fn count_remaining_items_from_table(
+        &self,
+        tables_id: Uuid,
+        filters: &FilterParams,
+    ) -> Result<i64, sqlx::Error> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM items
+            WHERE tables_id = $1
+              AND ($2::uuid IS NULL OR menu_id = $2)
+              AND quantity > delivered_quantity
+              AND items.deleted_at IS NULL
+            "#,
+            tables_id,
+            filters.menu_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn get_item_quantities(&self, item_ids: &[Uuid]) -> Result<HashMap<Uuid, (i32, i32)>, Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, quantity, delivered_quantity
+            FROM items
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            "#,
+            item_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.id, (row.quantity, row.delivered_quantity))).collect())
+    }
+
+    async fn get_paid_item_ids(&self, item_ids: &[Uuid]) -> Result<HashSet<Uuid>, Error> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM items
+            WHERE id = ANY($1) AND paid_at IS NOT NULL
+            "#,
+            item_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids.into_iter().collect())
+    }
+
+    async fn create_items(
+        &self,
+        tables_id: Uuid,
+        new_items: Vec<NewItemRequest>,
+        ordered_at: NaiveDateTime,
+        actor: &str,
+    ) -> Result<Vec<PartialItem>, AppError> {
+        let mut query = String::from("INSERT INTO items (id, tables_id, menu_id, menu_name, unit_price, quantity, delivered_quantity, created_by, updated_by, created_at, updated_at) VALUES ");
+        let total_items = new_items.len();
+        let mut placeholders = vec![];
+
+        if total_items > MAX_ITEMS_LIMIT {
+            return Err(AppError::LimitExceeded { limit: MAX_ITEMS_LIMIT });
+        }
+
+        // Prices are copied onto the items so later menu changes leave existing orders alone.
+        let menu_ids: Vec<Uuid> = new_items.iter().map(|item| item.menu_id).collect();
+        let menus: HashMap<Uuid, (String, Decimal)> = sqlx::query!(
+            r#"
+            SELECT id, name, price
+            FROM Menu
+            WHERE id = ANY($1)
+            "#,
+            &menu_ids
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.id, (row.name, row.price)))
+        .collect();
+        let rules = self.get_price_rules(&menu_ids).await?;
+
+        for i in 0..total_items {
+            let start = i * 9 + 1;
+            placeholders.push(format!(
+                "(${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${}, ${})",
+                start, start + 1, start + 2, start + 3, start + 4, start + 5, start + 6, start + 7, start + 7, start + 8, start + 8
+            ));
+        }
+
+        query.push_str(&placeholders.join(", "));
+
+        info!("Query: {}", query);
+
+        let mut query_args = sqlx::query(&query);
+        let mut created_items = Vec::with_capacity(total_items);
+
+        for new_item in new_items {
+            let id = Uuid::new_v4();
+            let (menu_name, unit_price) = menus
+                .get(&new_item.menu_id)
+                .cloned()
+                .ok_or_else(|| AppError::Conflict(format!("Menu item {} does not exist", new_item.menu_id)))?;
+            let menu_rules: Vec<PriceRule> = rules.iter().filter(|rule| rule.menu_id == new_item.menu_id).cloned().collect();
+            let unit_price = price_at(unit_price, &menu_rules, ordered_at);
+
+            query_args = query_args
+                .bind(id)
+                .bind(tables_id)
+                .bind(new_item.menu_id)
+                .bind(menu_name.clone())
+                .bind(unit_price)
+                .bind(new_item.quantity)
+                .bind(0)
+                .bind(actor)
+                .bind(ordered_at);
+
+            created_items.push(PartialItem {
+                id,
+                tables_id,
+                menu_id: new_item.menu_id,
+                menu_name,
+                unit_price,
+                quantity: new_item.quantity,
+                delivered_quantity: 0
+            });
+        }
+
+        query_args.execute(&self.pool).await.map_err(|err| {
+            error!("Error creating items: {}", err);
+            err
+        })?;
+
+        Ok(created_items)
+    }
+
+    async fn create_item(
+        &self,
+        tables_id: Uuid,
+        new_item: NewItemRequest,
+        ordered_at: NaiveDateTime,
+        actor: &str,
+    ) -> Result<PartialItem, AppError> {
+        let mut created = self.create_items(tables_id, vec![new_item], ordered_at, actor).await?;
+        Ok(created.remove(0))
+    }
+
+    async fn get_item(&self, tables_id: Uuid, item_id: Uuid) -> Result<PartialItemReturn, Error> {
+        let item = sqlx::query_as!(
+            PartialItemReturn,
+            r#"
+            SELECT
+                items.id,
+                items.tables_id,
+                items.menu_id,
+                items.menu_name,
+                items.unit_price,
+                items.quantity,
+                items.delivered_quantity,
+                items.delivered_at,
+                items.paid_at,
+                items.refunded_quantity,
+                items.created_at,
+                Menu.prep_time as prep_time,
+                items.status as "status: ItemStatus",
+                items.started_at,
+                items.ready_at,
+                items.served_at
+            FROM items
+            LEFT JOIN Menu on menu_id = Menu.id
+            WHERE items.tables_id = $1 AND items.id = $2
+              AND items.deleted_at IS NULL
+            "#,
+            tables_id,
+            item_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(item)
+    }
+
+    async fn get_items(&self, item_ids: &[Uuid]) -> Result<Vec<PartialItemReturn>, Error> {
+        let items = sqlx::query_as!(
+            PartialItemReturn,
+            r#"
+            SELECT
+                items.id,
+                items.tables_id,
+                items.menu_id,
+                items.menu_name,
+                items.unit_price,
+                items.quantity,
+                items.delivered_quantity,
+                items.delivered_at,
+                items.paid_at,
+                items.refunded_quantity,
+                items.created_at,
+                Menu.prep_time as prep_time,
+                items.status as "status: ItemStatus",
+                items.started_at,
+                items.ready_at,
+                items.served_at
+            FROM items
+            LEFT JOIN Menu on menu_id = Menu.id
+            WHERE items.id = ANY($1)
+              AND items.deleted_at IS NULL
+            ORDER BY items.created_at, items.id
+            "#,
+            item_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    async fn delete_item(&self, tables_id: Uuid, item_id: Uuid, actor: &str) -> Result<bool, Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE items
+            SET
+                deleted_at = CURRENT_TIMESTAMP,
+                deleted_by = $3,
+                updated_at = CURRENT_TIMESTAMP,
+                updated_by = $3
+            WHERE tables_id = $1 AND id = $2
+              AND deleted_at IS NULL
+              AND paid_at IS NULL
+            "#,
+            tables_id,
+            item_id,
+            actor
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn restore_item(&self, tables_id: Uuid, item_id: Uuid, actor: &str) -> Result<bool, Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE items
+            SET
+                deleted_at = NULL,
+                deleted_by = NULL,
+                updated_at = CURRENT_TIMESTAMP,
+                updated_by = $3
+            WHERE tables_id = $1 AND id = $2
+              AND deleted_at IS NOT NULL
+              AND session_id IS NULL
+              AND z_report_id IS NULL
+            "#,
+            tables_id,
+            item_id,
+            actor
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_deleted_items(
+        &self,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+    ) -> Result<Vec<DeletedItem>, Error> {
+        let items = sqlx::query_as!(
+            DeletedItem,
+            r#"
+            SELECT
+                id,
+                tables_id,
+                menu_id,
+                quantity,
+                delivered_quantity,
+                deleted_at as "deleted_at!",
+                deleted_by
+            FROM items
+            WHERE deleted_at IS NOT NULL
+              AND deleted_at >= COALESCE($1::timestamp, LOCALTIMESTAMP - INTERVAL '1 day')
+              AND deleted_at <= COALESCE($2::timestamp, LOCALTIMESTAMP)
+            ORDER BY deleted_at DESC
+            "#,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    async fn update_item(&self, item_id: Uuid, updated_item: UpdateItemRequest, actor: &str) -> Result<bool, AppError> {
+        let updated_item = UpdateItemRequest {
+            id: item_id,
+            ..updated_item
+        };
+        let updated = self.update_items(vec![updated_item], actor).await?;
+
+        Ok(!updated.is_empty())
+    }
+
+    async fn update_items(&self, items: Vec<UpdateItemRequest>, actor: &str) -> Result<Vec<UpdatedItem>, AppError> {
+        if items.is_empty() {
+            return Ok(vec![]);
+        } else if items.len() > MAX_ITEMS_LIMIT {
+            return Err(AppError::LimitExceeded { limit: MAX_ITEMS_LIMIT });
+        }
+
+        let mut cases_quantity = String::from("quantity = CASE ");
+        let mut cases_delivered_quantity = String::from("delivered_quantity = CASE ");
+        let mut ids: Vec<String> = vec![];
+
+        for (i, _item) in items.iter().enumerate() {
+            let start = i * 5 + 1;
+            let id_placeholder = format!("${}", start);
+
+            cases_quantity.push_str(&format!(
+                "WHEN id = {} THEN COALESCE(${}, quantity + ${}, quantity) ",
+                id_placeholder, start + 1, start + 2
+            ));
+            cases_delivered_quantity.push_str(&format!(
+                "WHEN id = {} THEN COALESCE(${}, delivered_quantity + ${}, delivered_quantity) ",
+                id_placeholder, start + 3, start + 4
+            ));
+            ids.push(id_placeholder);
+        }
+
+        cases_quantity.push_str("ELSE quantity END");
+        cases_delivered_quantity.push_str("ELSE delivered_quantity END");
+
+        let actor_placeholder = format!("${}", items.len() * 5 + 1);
+
+        let query = format!(
+            "UPDATE items SET {}, {}, updated_at = CURRENT_TIMESTAMP, updated_by = {} WHERE id IN ({}) AND deleted_at IS NULL RETURNING id, quantity, delivered_quantity",
+            cases_quantity,
+            cases_delivered_quantity,
+            actor_placeholder,
+            ids.join(", ")
+        );
+
+        info!("Query: {}", query);
+
+        let item_ids: Vec<Uuid> = items.iter().map(|item| item.id).collect();
+        let mut tx = self.pool.begin().await?;
+        let previous = Self::lock_item_quantities(&mut tx, &item_ids).await?;
+        // The route checked the updates before they were locked, so check them again
+        // against the locked rows in case another update landed in between.
+        let locked: HashMap<Uuid, (i32, i32)> = previous
+            .iter()
+            .map(|(item_id, &(quantity, delivered_quantity, _))| (*item_id, (quantity, delivered_quantity)))
+            .collect();
+        check_item_updates(&items, &locked)?;
+
+        let mut query_args = sqlx::query_as::<_, (Uuid, i32, i32)>(&query);
+        for item in items.iter() {
+            let (set_quantity, increment_quantity) = FieldUpdate::binds(item.quantity);
+            let (set_delivered_quantity, increment_delivered_quantity) = FieldUpdate::binds(item.delivered_quantity);
+            query_args = query_args
+                .bind(item.id)
+                .bind(set_quantity)
+                .bind(increment_quantity)
+                .bind(set_delivered_quantity)
+                .bind(increment_delivered_quantity);
+        }
+        query_args = query_args.bind(actor);
+
+        let updated = query_args.fetch_all(&mut tx).await.map_err(|err| {
+            error!("Error updating items: {}", err);
+            err
+        })?;
+
+        // Dropping the transaction rolls the whole update back.
+        for (item_id, quantity, delivered_quantity) in &updated {
+            if let Some(&(quantity_before, delivered_before, status)) = previous.get(item_id) {
+                status
+                    .after_delivery((quantity_before, delivered_before), (*quantity, *delivered_quantity))
+                    .map_err(|status| delivery_refused(*item_id, status))?;
+            }
+        }
+
+        let resolved_alerts = Self::record_deliveries(&mut tx, &previous, &updated, actor).await?;
+        tx.commit().await?;
+
+        Ok(updated
+            .iter()
+            .map(|&(item_id, _, delivered_quantity)| UpdatedItem {
+                id: item_id,
+                delivered: previous
+                    .get(&item_id)
+                    .is_some_and(|&(_, delivered_before, _)| delivered_before != delivered_quantity),
+                resolved_alert: resolved_alerts.get(&item_id).copied(),
+            })
+            .collect())
+    }
+
+    async fn get_item_deliveries(&self, tables_id: Uuid, item_id: Uuid) -> Result<Vec<ItemDelivery>, Error> {
+        let deliveries = sqlx::query_as!(
+            ItemDelivery,
+            r#"
+            SELECT
+                item_deliveries.id,
+                item_deliveries.items_id,
+                item_deliveries.quantity,
+                item_deliveries.delivered_at,
+                item_deliveries.delivered_by
+            FROM item_deliveries
+            JOIN items ON items.id = item_deliveries.items_id
+            WHERE items.tables_id = $1 AND items.id = $2
+            ORDER BY item_deliveries.delivered_at, item_deliveries.id
+            "#,
+            tables_id,
+            item_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+}
+
+#[async_trait]
+impl BillingRepository for Database {
+    async fn get_bill_items(&self, tables_id: Uuid) -> Result<Vec<BillItem>, Error> {
+        let items = sqlx::query_as!(
+            BillItem,
+            r#"
+            SELECT
+                items.id as item_id,
+                items.menu_id,
+                items.menu_name as name,
+                items.unit_price,
+                items.quantity,
+                Menu.tax_category_id
+            FROM items
+            LEFT JOIN Menu ON items.menu_id = Menu.id
+            WHERE items.tables_id = $1
+              AND items.deleted_at IS NULL
+              AND items.session_id IS NULL
+            ORDER BY items.created_at, items.id
+            "#,
+            tables_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    async fn get_settled_bill_items(&self, session_id: Uuid) -> Result<Vec<BillItem>, Error> {
+        let items = sqlx::query_as!(
+            BillItem,
+            r#"
+            SELECT
+                items.id as item_id,
+                items.menu_id,
+                items.menu_name as name,
+                items.unit_price,
+                items.quantity,
+                items.tax_category_id
+            FROM items
+            WHERE items.session_id = $1
+              AND items.paid_at IS NOT NULL
+            ORDER BY items.created_at, items.id
+            "#,
+            session_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    async fn get_settled_taxes(&self, session_id: Uuid) -> Result<Vec<ChargedTax>, Error> {
+        let taxes = sqlx::query_as!(
+            ChargedTax,
+            r#"
+            SELECT DISTINCT ON (tax_category_id)
+                tax_category_id as "id!",
+                tax_name as "name!",
+                tax_rate as "rate!",
+                tax_inclusive as "inclusive!"
+            FROM items
+            WHERE session_id = $1
+              AND paid_at IS NOT NULL
+              AND tax_category_id IS NOT NULL
+            ORDER BY tax_category_id
+            "#,
+            session_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(taxes)
+    }
+}
+
+#[async_trait]
+impl CheckRepository for Database {
+    async fn get_checks(&self, tables_id: Uuid) -> Result<Vec<Check>, Error> {
+        let checks = sqlx::query_as!(
+            Check,
+            r#"
+            SELECT id, tables_id, name, position, created_at, created_by, session_id
+            FROM checks
+            WHERE tables_id = $1
+              AND session_id IS NULL
+            ORDER BY position
+            "#,
+            tables_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(checks)
+    }
+
+    async fn get_check_items(&self, tables_id: Uuid) -> Result<Vec<CheckItem>, Error> {
+        let check_items = sqlx::query_as!(
+            CheckItem,
+            r#"
+            SELECT
+                check_items.check_id,
+                check_items.items_id,
+                check_items.share_numerator,
+                check_items.share_denominator
+            FROM check_items
+            JOIN checks on check_id = checks.id
+            WHERE checks.tables_id = $1
+              AND checks.session_id IS NULL
+            "#,
+            tables_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(check_items)
+    }
+
+    async fn replace_checks(&self, tables_id: Uuid, checks: Vec<NewCheckRequest>, actor: &str) -> Result<Vec<Check>, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            SELECT id
+            FROM tables
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            tables_id
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM checks
+            WHERE tables_id = $1
+              AND session_id IS NULL
+            "#,
+            tables_id
+        )
+        .execute(&mut tx)
+        .await?;
+
+        let mut check_ids = Vec::with_capacity(checks.len());
+        let mut names = Vec::with_capacity(checks.len());
+        let mut positions = Vec::with_capacity(checks.len());
+        let mut share_check_ids = vec![];
+        let mut share_item_ids = vec![];
+        let mut numerators = vec![];
+        let mut denominators = vec![];
+        for (position, check) in checks.into_iter().enumerate() {
+            let check_id = Uuid::new_v4();
+            for share in check.items {
+                share_check_ids.push(check_id);
+                share_item_ids.push(share.item_id);
+                numerators.push(share.numerator);
+                denominators.push(share.denominator);
+            }
+            check_ids.push(check_id);
+            names.push(check.name.trim().to_string());
+            positions.push(position as i32);
+        }
+
+        let mut created = sqlx::query_as!(
+            Check,
+            r#"
+            INSERT INTO checks (id, tables_id, name, position, created_by)
+            SELECT new_checks.id, $2, new_checks.name, new_checks.position, $5
+            FROM UNNEST($1::uuid[], $3::varchar[], $4::int4[]) AS new_checks(id, name, position)
+            RETURNING id, tables_id, name, position, created_at, created_by, session_id
+            "#,
+            &check_ids,
+            tables_id,
+            &names,
+            &positions,
+            actor
+        )
+        .fetch_all(&mut tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO check_items (check_id, items_id, share_numerator, share_denominator)
+            SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::int4[], $4::int4[])
+            "#,
+            &share_check_ids,
+            &share_item_ids,
+            &numerators,
+            &denominators
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        created.sort_by_key(|check| check.position);
+        Ok(created)
+    }
+
+    async fn delete_checks(&self, tables_id: Uuid) -> Result<u64, Error> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM checks
+            WHERE tables_id = $1
+              AND session_id IS NULL
+            "#,
+            tables_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait]
+impl PaymentRepository for Database {
+    async fn get_open_session(&self, tables_id: Uuid) -> Result<Option<TableSession>, Error> {
+        let session = sqlx::query_as!(
+            TableSession,
+            r#"
+            SELECT
+                id,
+                tables_id,
+                opened_at,
+                closed_at,
+                closed_by,
+                receipt_number,
+                covers,
+                z_report_id,
+                tax_rate,
+                service_charge_rule_id,
+                service_charge_name,
+                service_charge_rate
+            FROM table_sessions
+            WHERE tables_id = $1 AND closed_at IS NULL
+            "#,
+            tables_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    async fn get_payments(&self, session_id: Uuid) -> Result<Vec<Payment>, Error> {
+        let payments = sqlx::query_as!(
+            Payment,
+            r#"
+            SELECT
+                id,
+                session_id,
+                check_id,
+                tender as "tender: Tender",
+                amount,
+                tip,
+                tendered,
+                change,
+                refund_of,
+                reason as "reason: RefundReason",
+                approved_by,
+                created_at,
+                created_by
+            FROM payments
+            WHERE session_id = $1
+            ORDER BY created_at, id
+            "#,
+            session_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(payments)
+    }
+
+    async fn add_payment(
+        &self,
+        tables_id: Uuid,
+        payment: NewPayment,
+        paid_before: Decimal,
+        settlement: Option<Settlement>,
+        actor: &str,
+    ) -> Result<AddPaymentOutcome, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            SELECT id
+            FROM tables
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            tables_id
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        let session_id = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM table_sessions
+            WHERE tables_id = $1 AND closed_at IS NULL
+            "#,
+            tables_id
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+
+        let paid = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(amount), 0) as "paid!"
+            FROM payments
+            WHERE session_id = $1
+            "#,
+            session_id
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        if paid != paid_before {
+            return Ok(AddPaymentOutcome::Stale);
+        }
+
+        let session_id = match session_id {
+            Some(session_id) => session_id,
+            None => {
+                sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO table_sessions (tables_id)
+                    VALUES ($1)
+                    RETURNING id
+                    "#,
+                    tables_id
+                )
+                .fetch_one(&mut tx)
+                .await?
+            }
+        };
+
+        let recorded = sqlx::query_as!(
+            Payment,
+            r#"
+            INSERT INTO payments (session_id, check_id, tender, amount, tip, tendered, change, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING
+                id,
+                session_id,
+                check_id,
+                tender as "tender: Tender",
+                amount,
+                tip,
+                tendered,
+                change,
+                refund_of,
+                reason as "reason: RefundReason",
+                approved_by,
+                created_at,
+                created_by
+            "#,
+            session_id,
+            payment.check_id,
+            payment.tender as Tender,
+            payment.amount,
+            payment.tip,
+            payment.tendered,
+            payment.change,
+            actor
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        let closed = settlement.is_some();
+        if let Some(settlement) = settlement {
+            let item_ids: Vec<Uuid> = settlement.items.iter().map(|item| item.item_id).collect();
+            // Deleted items go with the session too, so they cannot be restored onto a settled bill.
+            sqlx::query!(
+                r#"
+                UPDATE items
+                SET
+                    session_id = $1,
+                    paid_at = CASE WHEN deleted_at IS NULL THEN CURRENT_TIMESTAMP ELSE NULL END
+                WHERE tables_id = $2
+                  AND session_id IS NULL
+                  AND (id = ANY($3) OR deleted_at IS NOT NULL)
+                "#,
+                session_id,
+                tables_id,
+                &item_ids
+            )
+            .execute(&mut tx)
+            .await?;
+
+            let taxed: Vec<(Uuid, &ChargedTax)> = settlement
+                .items
+                .iter()
+                .filter_map(|item| Some((item.item_id, item.tax_category.as_ref()?)))
+                .collect();
+            sqlx::query!(
+                r#"
+                UPDATE items
+                SET
+                    tax_category_id = settled.tax_category_id,
+                    tax_name = settled.name,
+                    tax_rate = settled.rate,
+                    tax_inclusive = settled.inclusive
+                FROM UNNEST($1::uuid[], $2::uuid[], $3::text[], $4::numeric[], $5::bool[])
+                    AS settled(item_id, tax_category_id, name, rate, inclusive)
+                WHERE items.id = settled.item_id AND items.session_id = $6
+                "#,
+                &taxed.iter().map(|(item_id, _)| *item_id).collect::<Vec<Uuid>>(),
+                &taxed.iter().map(|(_, category)| category.id).collect::<Vec<Uuid>>(),
+                &taxed.iter().map(|(_, category)| category.name.clone()).collect::<Vec<String>>(),
+                &taxed.iter().map(|(_, category)| category.rate).collect::<Vec<Decimal>>(),
+                &taxed.iter().map(|(_, category)| category.inclusive).collect::<Vec<bool>>(),
+                session_id
+            )
+            .execute(&mut tx)
+            .await?;
+
+            sqlx::query!(
+                r#"
+                UPDATE checks
+                SET session_id = $1
+                WHERE tables_id = $2 AND session_id IS NULL
+                "#,
+                session_id,
+                tables_id
+            )
+            .execute(&mut tx)
+            .await?;
+            sqlx::query!(
+                r#"
+                UPDATE discounts
+                SET session_id = $1
+                WHERE tables_id = $2 AND session_id IS NULL
+                "#,
+                session_id,
+                tables_id
+            )
+            .execute(&mut tx)
+            .await?;
+
+            sqlx::query!(
+                r#"
+                UPDATE table_sessions
+                SET
+                    closed_at = CURRENT_TIMESTAMP,
+                    closed_by = $2,
+                    covers = (SELECT covers FROM tables WHERE id = table_sessions.tables_id),
+                    tax_rate = $3,
+                    service_charge_rule_id = $4,
+                    service_charge_name = $5,
+                    service_charge_rate = $6
+                WHERE id = $1
+                "#,
+                session_id,
+                actor,
+                settlement.tax_rate,
+                settlement.service_charge.as_ref().map(|rule| rule.id),
+                settlement.service_charge.as_ref().map(|rule| rule.name.clone()),
+                settlement.service_charge.as_ref().map(|rule| rule.rate)
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(AddPaymentOutcome::Recorded { payment: recorded, closed })
+    }
+
+    async fn get_payment(&self, payment_id: Uuid) -> Result<Payment, Error> {
+        let payment = sqlx::query_as!(
+            Payment,
+            r#"
+            SELECT
+                id,
+                session_id,
+                check_id,
+                tender as "tender: Tender",
+                amount,
+                tip,
+                tendered,
+                change,
+                refund_of,
+                reason as "reason: RefundReason",
+                approved_by,
+                created_at,
+                created_by
+            FROM payments
+            WHERE id = $1
+            "#,
+            payment_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(payment)
+    }
+
+    async fn get_session(&self, session_id: Uuid) -> Result<TableSession, Error> {
+        let session = sqlx::query_as!(
+            TableSession,
+            r#"
+            SELECT
+                id,
+                tables_id,
+                opened_at,
+                closed_at,
+                closed_by,
+                receipt_number,
+                covers,
+                z_report_id,
+                tax_rate,
+                service_charge_rule_id,
+                service_charge_name,
+                service_charge_rate
+            FROM table_sessions
+            WHERE id = $1
+            "#,
+            session_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    async fn get_last_session(&self, tables_id: Uuid) -> Result<Option<TableSession>, Error> {
+        let session = sqlx::query_as!(
+            TableSession,
+            r#"
+            SELECT
+                id,
+                tables_id,
+                opened_at,
+                closed_at,
+                closed_by,
+                receipt_number,
+                covers,
+                z_report_id,
+                tax_rate,
+                service_charge_rule_id,
+                service_charge_name,
+                service_charge_rate
+            FROM table_sessions
+            WHERE tables_id = $1
+            ORDER BY receipt_number DESC
+            LIMIT 1
+            "#,
+            tables_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    async fn get_refundable_items(&self, session_id: Uuid) -> Result<Vec<RefundableItem>, Error> {
+        let items = sqlx::query_as!(
+            RefundableItem,
+            r#"
+            SELECT
+                id as item_id,
+                quantity,
+                refunded_quantity
+            FROM items
+            WHERE session_id = $1 AND paid_at IS NOT NULL
+            ORDER BY created_at, id
+            "#,
+            session_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    async fn add_refund(
+        &self,
+        payment_id: Uuid,
+        refund: NewRefund,
+        refunded_before: Decimal,
+        actor: &str,
+    ) -> Result<AddRefundOutcome, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let original = sqlx::query!(
+            r#"
+            SELECT session_id, check_id, tender as "tender: Tender"
+            FROM payments
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            payment_id
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        let refunded = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(-(amount + tip)), 0) as "refunded!"
+            FROM payments
+            WHERE refund_of = $1
+            "#,
+            payment_id
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        if refunded != refunded_before {
+            return Ok(AddRefundOutcome::Stale);
+        }
+
+        for item in &refund.items {
+            let result = sqlx::query!(
+                r#"
+                UPDATE items
+                SET
+                    refunded_quantity = refunded_quantity + $1,
+                    refunded_at = CURRENT_TIMESTAMP,
+                    refunded_by = $2
+                WHERE id = $3
+                  AND session_id = $4
+                  AND paid_at IS NOT NULL
+                  AND refunded_quantity + $1 <= quantity
+                "#,
+                item.quantity,
+                actor,
+                item.item_id,
+                original.session_id
+            )
+            .execute(&mut tx)
+            .await?;
+
+            if result.rows_affected() == 0 {
+                return Ok(AddRefundOutcome::Stale);
+            }
+        }
+
+        let recorded = sqlx::query_as!(
+            Payment,
+            r#"
+            INSERT INTO payments (
+                session_id, check_id, tender, amount, tip, tendered, change,
+                refund_of, reason, approved_by, created_by
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, 0, $7, $8, $9, $10)
+            RETURNING
+                id,
+                session_id,
+                check_id,
+                tender as "tender: Tender",
+                amount,
+                tip,
+                tendered,
+                change,
+                refund_of,
+                reason as "reason: RefundReason",
+                approved_by,
+                created_at,
+                created_by
+            "#,
+            original.session_id,
+            original.check_id,
+            original.tender as Tender,
+            -refund.amount,
+            -refund.tip,
+            -(refund.amount + refund.tip),
+            payment_id,
+            refund.reason as RefundReason,
+            refund.approved_by,
+            actor
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(AddRefundOutcome::Recorded(recorded))
+    }
+}
+
+#[async_trait]
+impl ReportRepository for Database {
+    async fn get_payments_between(&self, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<Payment>, Error> {
+        let payments = sqlx::query_as!(
+            Payment,
+            r#"
+            SELECT
+                id,
+                session_id,
+                check_id,
+                tender as "tender: Tender",
+                amount,
+                tip,
+                tendered,
+                change,
+                refund_of,
+                reason as "reason: RefundReason",
+                approved_by,
+                created_at,
+                created_by
+            FROM payments
+            WHERE created_at >= $1 AND created_at < $2
+            ORDER BY created_at, id
+            "#,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(payments)
+    }
+    async fn close_day(&self, totals: &dyn DayTotals, actor: &str) -> Result<ZReport, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Closes run one at a time, so report numbers follow each other without gaps.
+        sqlx::query!("LOCK TABLE z_reports IN EXCLUSIVE MODE")
+            .execute(&mut tx)
+            .await?;
+        let sales = Self::lock_unreported_sales(&mut tx).await?;
+
+        let totals = totals.totals(&sales).await?;
+        let totals = serde_json::to_string(&totals)?;
+        let row = sqlx::query_as!(
+            ZReportRow,
+            r#"
+            INSERT INTO z_reports (number, opened_at, closed_by, totals)
+            SELECT COALESCE(MAX(number), 0) + 1, MAX(closed_at), $1, $2::text::jsonb
+            FROM z_reports
+            RETURNING id, number, opened_at, closed_at, closed_by, totals::text as "totals!"
+            "#,
+            actor,
+            totals
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        let session_ids: Vec<Uuid> = sales.sessions.iter().map(|session| session.id).collect();
+        sqlx::query!(
+            r#"
+            UPDATE table_sessions
+            SET z_report_id = $1
+            WHERE id = ANY($2)
+            "#,
+            row.id,
+            &session_ids
+        )
+        .execute(&mut tx)
+        .await?;
+
+        let payment_ids: Vec<Uuid> = sales.payments.iter().map(|payment| payment.id).collect();
+        sqlx::query!(
+            r#"
+            UPDATE payments
+            SET z_report_id = $1
+            WHERE id = ANY($2)
+            "#,
+            row.id,
+            &payment_ids
+        )
+        .execute(&mut tx)
+        .await?;
+
+        let void_ids: Vec<Uuid> = sales.voids.iter().map(|item| item.item_id).collect();
+        sqlx::query!(
+            r#"
+            UPDATE items
+            SET z_report_id = $1
+            WHERE id = ANY($2)
+            "#,
+            row.id,
+            &void_ids
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(row.into_report()?)
+    }
+
+    async fn get_z_reports(&self) -> Result<Vec<ZReport>, Error> {
+        let rows = sqlx::query_as!(
+            ZReportRow,
+            r#"
+            SELECT id, number, opened_at, closed_at, closed_by, totals::text as "totals!"
+            FROM z_reports
+            ORDER BY number DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(ZReportRow::into_report).collect()
+    }
+
+    async fn get_z_report(&self, number: i64) -> Result<ZReport, Error> {
+        let row = sqlx::query_as!(
+            ZReportRow,
+            r#"
+            SELECT id, number, opened_at, closed_at, closed_by, totals::text as "totals!"
+            FROM z_reports
+            WHERE number = $1
+            "#,
+            number
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        row.into_report()
+    }
+}
+
+#[async_trait]
+impl TaxRepository for Database {
+    async fn get_tax_categories(&self) -> Result<Vec<TaxCategory>, Error> {
+        let categories = sqlx::query_as!(
+            TaxCategory,
+            r#"
+            SELECT id, name, rate, inclusive, created_at
+            FROM tax_categories
+            ORDER BY name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(categories)
+    }
+
+    async fn get_tax_category(&self, tax_category_id: Uuid) -> Result<TaxCategory, Error> {
+        let category = sqlx::query_as!(
+            TaxCategory,
+            r#"
+            SELECT id, name, rate, inclusive, created_at
+            FROM tax_categories
+            WHERE id = $1
+            "#,
+            tax_category_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(category)
+    }
+
+    async fn add_tax_category(&self, new_category: NewTaxCategoryRequest) -> Result<TaxCategory, Error> {
+        let category = sqlx::query_as!(
+            TaxCategory,
+            r#"
+            INSERT INTO tax_categories (name, rate, inclusive)
+            VALUES ($1, $2, $3)
+            RETURNING id, name, rate, inclusive, created_at
+            "#,
+            new_category.name,
+            new_category.rate,
+            new_category.inclusive
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(category)
+    }
+
+    async fn update_tax_category(
+        &self,
+        tax_category_id: Uuid,
+        updated_category: UpdateTaxCategoryRequest,
+    ) -> Result<Option<TaxCategory>, Error> {
+        let category = sqlx::query_as!(
+            TaxCategory,
+            r#"
+            UPDATE tax_categories
+            SET
+                name = COALESCE($2, name),
+                rate = COALESCE($3, rate),
+                inclusive = COALESCE($4, inclusive)
+            WHERE id = $1
+            RETURNING id, name, rate, inclusive, created_at
+            "#,
+            tax_category_id,
+            updated_category.name,
+            updated_category.rate,
+            updated_category.inclusive
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(category)
+    }
+
+    async fn delete_tax_category(&self, tax_category_id: Uuid) -> Result<bool, Error> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM tax_categories
+            WHERE id = $1
+            "#,
+            tax_category_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_service_charge_rules(&self) -> Result<Vec<ServiceChargeRule>, Error> {
+        let rules = sqlx::query_as!(
+            ServiceChargeRule,
+            r#"
+            SELECT id, name, rate, min_covers, created_at
+            FROM service_charge_rules
+            ORDER BY min_covers, created_at
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rules)
+    }
+
+    async fn add_service_charge_rule(&self, new_rule: NewServiceChargeRuleRequest) -> Result<ServiceChargeRule, Error> {
+        let rule = sqlx::query_as!(
+            ServiceChargeRule,
+            r#"
+            INSERT INTO service_charge_rules (name, rate, min_covers)
+            VALUES ($1, $2, $3)
+            RETURNING id, name, rate, min_covers, created_at
+            "#,
+            new_rule.name,
+            new_rule.rate,
+            new_rule.min_covers
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(rule)
+    }
+
+    async fn delete_service_charge_rule(&self, rule_id: Uuid) -> Result<bool, Error> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM service_charge_rules
+            WHERE id = $1
+            "#,
+            rule_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl DiscountRepository for Database {
+    async fn get_discounts(&self, tables_id: Uuid) -> Result<Vec<Discount>, Error> {
+        let discounts = sqlx::query_as!(
+            Discount,
+            r#"
+            SELECT
+                id,
+                tables_id,
+                items_id,
+                kind as "kind: DiscountKind",
+                value,
+                reason,
+                promo_code_id,
+                applied_by,
+                session_id,
+                created_at,
+                created_by
+            FROM discounts
+            WHERE tables_id = $1 AND session_id IS NULL
+            ORDER BY created_at, id
+            "#,
+            tables_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(discounts)
+    }
+
+    async fn get_settled_discounts(&self, session_id: Uuid) -> Result<Vec<Discount>, Error> {
+        let discounts = sqlx::query_as!(
+            Discount,
+            r#"
+            SELECT
+                id,
+                tables_id,
+                items_id,
+                kind as "kind: DiscountKind",
+                value,
+                reason,
+                promo_code_id,
+                applied_by,
+                session_id,
+                created_at,
+                created_by
+            FROM discounts
+            WHERE session_id = $1
+            ORDER BY created_at, id
+            "#,
+            session_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(discounts)
+    }
+
+    async fn add_discount(&self, tables_id: Uuid, discount: NewDiscount, actor: &str) -> Result<AddDiscountOutcome, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(promo_code_id) = discount.promo_code_id {
+            let redeemed = sqlx::query!(
+                r#"
+                UPDATE promo_codes
+                SET uses = uses + 1
+                WHERE id = $1 AND (max_uses IS NULL OR uses < max_uses)
+                "#,
+                promo_code_id
+            )
+            .execute(&mut tx)
+            .await?;
+
+            if redeemed.rows_affected() == 0 {
+                return Ok(AddDiscountOutcome::PromoCodeUsedUp);
+            }
+        }
+
+        let added = sqlx::query_as!(
+            Discount,
+            r#"
+            INSERT INTO discounts (tables_id, items_id, kind, value, reason, promo_code_id, applied_by, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING
+                id,
+                tables_id,
+                items_id,
+                kind as "kind: DiscountKind",
+                value,
+                reason,
+                promo_code_id,
+                applied_by,
+                session_id,
+                created_at,
+                created_by
+            "#,
+            tables_id,
+            discount.items_id,
+            discount.kind as DiscountKind,
+            discount.value,
+            discount.reason,
+            discount.promo_code_id,
+            discount.applied_by,
+            actor
+        )
+        .fetch_one(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(AddDiscountOutcome::Added(added))
+    }
+
+    async fn delete_discount(&self, tables_id: Uuid, discount_id: Uuid) -> Result<bool, Error> {
+        let mut tx = self.pool.begin().await?;
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct UpdateItemRequest {
-    pub id: Uuid,
-    pub quantity: Option<i32>,
-    pub delivered_quantity: Option<i32>
-}
+        let deleted = sqlx::query_scalar!(
+            r#"
+            DELETE FROM discounts
+            WHERE id = $1 AND tables_id = $2 AND session_id IS NULL
+            RETURNING promo_code_id
+            "#,
+            discount_id,
+            tables_id
+        )
+        .fetch_optional(&mut tx)
+        .await?;
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct BulkUpdateItemRequest {
-    pub items: Vec<UpdateItemRequest>,
-}
+        let Some(promo_code_id) = deleted else {
+            return Ok(false);
+        };
 
-const MAX_ITEMS_LIMIT: usize = 100;
+        if let Some(promo_code_id) = promo_code_id {
+            sqlx::query!(
+                r#"
+                UPDATE promo_codes
+                SET uses = uses - 1
+                WHERE id = $1 AND uses > 0
+                "#,
+                promo_code_id
+            )
+            .execute(&mut tx)
+            .await?;
+        }
 
-impl Database {
-    pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
-        let pool = PgPool::connect(database_url).await?;
-        Ok(Database { pool })
+        tx.commit().await?;
+
+        Ok(true)
     }
 
-    pub async fn get_tables(&self) -> Result<Vec<Table>, Error> {
-        let tables = sqlx::query_as!(
-            Table,
+    async fn get_promo_codes(&self) -> Result<Vec<PromoCode>, Error> {
+        let promo_codes = sqlx::query_as!(
+            PromoCode,
             r#"
             SELECT
                 id,
-                name
-            FROM tables
-        "#)
-            .fetch_all(&self.pool)
-            .await?;
+                code,
+                kind as "kind: DiscountKind",
+                value,
+                valid_from,
+                valid_until,
+                max_uses,
+                uses,
+                created_at
+            FROM promo_codes
+            ORDER BY code
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(tables)
+        Ok(promo_codes)
     }
 
-    pub async fn add_table(&self, name: String) -> Result<Table, Error> {
-        let id = Uuid::new_v4();
-        sqlx::query!(
+    async fn get_promo_code_by_code(&self, code: &str) -> Result<Option<PromoCode>, Error> {
+        let promo_code = sqlx::query_as!(
+            PromoCode,
             r#"
-            INSERT INTO tables (id, name)
-            VALUES ($1, $2)
+            SELECT
+                id,
+                code,
+                kind as "kind: DiscountKind",
+                value,
+                valid_from,
+                valid_until,
+                max_uses,
+                uses,
+                created_at
+            FROM promo_codes
+            WHERE code = $1
             "#,
-            id,
-            name
+            code
         )
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(Table { id, name })
+        Ok(promo_code)
     }
 
+    async fn add_promo_code(&self, new_promo_code: NewPromoCodeRequest) -> Result<PromoCode, Error> {
+        let promo_code = sqlx::query_as!(
+            PromoCode,
+            r#"
+            INSERT INTO promo_codes (code, kind, value, valid_from, valid_until, max_uses)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING
+                id,
+                code,
+                kind as "kind: DiscountKind",
+                value,
+                valid_from,
+                valid_until,
+                max_uses,
+                uses,
+                created_at
+            "#,
+            new_promo_code.code,
+            new_promo_code.kind as DiscountKind,
+            new_promo_code.value,
+            new_promo_code.valid_from,
+            new_promo_code.valid_until,
+            new_promo_code.max_uses
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(promo_code)
+    }
+}
 
-    pub async fn get_menu(&self) -> Result<Vec<Menu>, Error> {
-        let menu = sqlx::query_as!(
-            Menu,
+#[async_trait]
+impl PriceRuleRepository for Database {
+    async fn get_price_rules(&self, menu_ids: &[Uuid]) -> Result<Vec<PriceRule>, Error> {
+        let rules = sqlx::query_as!(
+            PriceRule,
             r#"
-            SELECT *
-            FROM Menu
-        "#)
-            .fetch_all(&self.pool)
-            .await?;
+            SELECT id, menu_id, name, price, days, start_time, end_time, created_at
+            FROM price_rules
+            WHERE menu_id = ANY($1)
+            ORDER BY start_time, created_at
+            "#,
+            menu_ids
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(menu)
+        Ok(rules)
     }
 
-    pub async fn add_menu(&self, name: String, price: Decimal, prep_time: i32) -> Result<Menu, Error> {
-        let id = Uuid::new_v4();
-        sqlx::query!(
+    async fn add_price_rule(&self, menu_id: Uuid, new_rule: NewPriceRuleRequest) -> Result<PriceRule, Error> {
+        let rule = sqlx::query_as!(
+            PriceRule,
             r#"
-            INSERT INTO Menu (id, name, price, prep_time)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO price_rules (menu_id, name, price, days, start_time, end_time)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, menu_id, name, price, days, start_time, end_time, created_at
             "#,
-            id,
-            name,
-            price,
-            prep_time
+            menu_id,
+            new_rule.name,
+            new_rule.price,
+            &new_rule.days,
+            new_rule.start_time,
+            new_rule.end_time
         )
-        .execute(&self.pool)
+        .fetch_one(&self.pool)
         .await?;
 
-        Ok(Menu {
-            id,
-            name,
-            price,
-            prep_time,
-        })
+        Ok(rule)
     }
 
-    pub async fn get_all_remaining_items_from_table(
-        &self,
-        tables_id: Uuid,
-        pagination: Pagination,
-        filters: FilterParams,
-    ) -> Result<Vec<PartialItemReturn>, sqlx::Error> {
-        let limit = pagination.limit.unwrap_or(10);
-        let offset = pagination.offset.unwrap_or(0);
+    async fn update_price_rule(&self, menu_id: Uuid, rule_id: Uuid, updated_rule: NewPriceRuleRequest) -> Result<Option<PriceRule>, Error> {
+        let rule = sqlx::query_as!(
+            PriceRule,
+            r#"
+            UPDATE price_rules
+            SET name = $3, price = $4, days = $5, start_time = $6, end_time = $7
+            WHERE id = $1 AND menu_id = $2
+            RETURNING id, menu_id, name, price, days, start_time, end_time, created_at
+            "#,
+            rule_id,
+            menu_id,
+            updated_rule.name,
+            updated_rule.price,
+            &updated_rule.days,
+            updated_rule.start_time,
+            updated_rule.end_time
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(rule)
+    }
+
+    async fn delete_price_rule(&self, menu_id: Uuid, rule_id: Uuid) -> Result<bool, Error> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM price_rules
+            WHERE id = $1 AND menu_id = $2
+            "#,
+            rule_id,
+            menu_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
 
+#[async_trait]
+impl KitchenRepository for Database {
+    async fn get_kitchen_queue(&self, filters: &FilterParams) -> Result<Vec<KitchenQueueItem>, Error> {
         let items = sqlx::query_as!(
-            PartialItemReturn,
+            KitchenQueueItem,
             r#"
             SELECT
                 items.id,
                 items.tables_id,
+                Tables.name as table_name,
                 items.menu_id,
+                items.menu_name,
                 items.quantity,
                 items.delivered_quantity,
+                items.status as "status: ItemStatus",
                 items.created_at,
-                Menu.prep_time as prep_time
+                items.started_at,
+                items.ready_at,
+                Menu.prep_time,
+                items.created_at + make_interval(mins => Menu.prep_time) as "due_at!"
             FROM items
-            LEFT JOIN Menu on menu_id = Menu.id
-            WHERE tables_id = $1
-              AND ($2::uuid IS NULL OR menu_id = $2)
-              AND quantity > delivered_quantity
-            LIMIT $3 OFFSET $4
+            JOIN Tables ON items.tables_id = Tables.id
+            JOIN Menu ON items.menu_id = Menu.id
+            WHERE ($1::uuid IS NULL OR items.menu_id = $1)
+              AND items.quantity > items.delivered_quantity
+              AND items.deleted_at IS NULL
+            ORDER BY items.created_at + make_interval(mins => Menu.prep_time), items.created_at, items.id
             "#,
-            tables_id,
-            filters.menu_id,
-            limit as i64,
-            offset as i64
+            filters.menu_id
         )
         .fetch_all(&self.pool)
         .await?;
@@ -147,184 +2809,261 @@ impl Database {
         Ok(items)
     }
 
-    pub async fn create_items(&self, tables_id: Uuid, new_items: Vec<NewItemRequest>) -> Result<Vec<PartialItem>, anyhow::Error> {
-        let mut query = String::from("INSERT INTO items (id, tables_id, menu_id, quantity, delivered_quantity) VALUES ");
-        let total_items = new_items.len();
-        let mut placeholders = vec![];
-
-        if total_items > MAX_ITEMS_LIMIT {
-            return Err(anyhow::anyhow!("The number of items exceeds the limit of {}", MAX_ITEMS_LIMIT));
-        }
-
-        for i in 0..total_items {
-            let start = i * 5 + 1;
-            placeholders.push(format!("(${}, ${}, ${}, ${}, ${})", start, start + 1, start + 2, start + 3, start + 4));
-        }
-
-        query.push_str(&placeholders.join(", "));
-
-        info!("Query: {}", query);
+    async fn get_kitchen_queue_ahead_of(&self, tables_id: Uuid) -> Result<Vec<KitchenQueueItem>, Error> {
+        let items = sqlx::query_as!(
+            KitchenQueueItem,
+            r#"
+            SELECT
+                items.id,
+                items.tables_id,
+                Tables.name as table_name,
+                items.menu_id,
+                items.menu_name,
+                items.quantity,
+                items.delivered_quantity,
+                items.status as "status: ItemStatus",
+                items.created_at,
+                items.started_at,
+                items.ready_at,
+                Menu.prep_time,
+                items.created_at + make_interval(mins => Menu.prep_time) as "due_at!"
+            FROM items
+            JOIN Tables ON items.tables_id = Tables.id
+            JOIN Menu ON items.menu_id = Menu.id
+            WHERE items.quantity > items.delivered_quantity
+              AND items.deleted_at IS NULL
+              AND (
+                  items.tables_id = $1
+                  OR items.status = 'in_preparation'
+                  OR (items.status = 'ordered' AND (items.created_at + make_interval(mins => Menu.prep_time), items.created_at, items.id) <= (
+                      SELECT last.created_at + make_interval(mins => last_menu.prep_time), last.created_at, last.id
+                      FROM items last
+                      JOIN Menu last_menu ON last.menu_id = last_menu.id
+                      WHERE last.tables_id = $1
+                        AND last.status = 'ordered'
+                        AND last.quantity > last.delivered_quantity
+                        AND last.deleted_at IS NULL
+                      ORDER BY last.created_at + make_interval(mins => last_menu.prep_time) DESC, last.created_at DESC, last.id DESC
+                      LIMIT 1
+                  ))
+              )
+            ORDER BY items.created_at + make_interval(mins => Menu.prep_time), items.created_at, items.id
+            "#,
+            tables_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        let mut query_args = sqlx::query(&query);
-        let mut created_items = Vec::with_capacity(total_items);
+        Ok(items)
+    }
 
-        for new_item in new_items {
-            let id = Uuid::new_v4();
+    async fn update_item_status(
+        &self,
+        tables_id: Uuid,
+        item_id: Uuid,
+        status: ItemStatus,
+        now: NaiveDateTime,
+        actor: &str,
+    ) -> Result<ItemStatusOutcome, Error> {
+        let mut tx = self.pool.begin().await?;
 
-            query_args = query_args
-                .bind(id)
-                .bind(tables_id)
-                .bind(new_item.menu_id)
-                .bind(new_item.quantity)
-                .bind(0);
+        let current = sqlx::query_scalar!(
+            r#"
+            SELECT status as "status: ItemStatus"
+            FROM items
+            WHERE tables_id = $1 AND id = $2 AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+            tables_id,
+            item_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
 
-            created_items.push(PartialItem {
-                id,
-                tables_id,
-                menu_id: new_item.menu_id,
-                quantity: new_item.quantity,
-                delivered_quantity: 0
-            });
+        let Some(current) = current else {
+            return Ok(ItemStatusOutcome::NotFound);
+        };
+        if current.next() != Some(status) {
+            return Ok(ItemStatusOutcome::Illegal(current));
         }
 
-        query_args.execute(&self.pool).await.map_err(|err| {
-            error!("Error creating items: {}", err);
-            err
-        })?;
+        let resolved_alert = if status == ItemStatus::Served {
+            // Serving hands over whatever is still to be delivered, which
+            // `record_deliveries` logs and marks as served.
+            let previous = Self::lock_item_quantities(&mut tx, &[item_id]).await?;
+            let quantity = sqlx::query_scalar!(
+                r#"
+                UPDATE items
+                SET delivered_quantity = quantity, updated_at = CURRENT_TIMESTAMP, updated_by = $2
+                WHERE id = $1
+                RETURNING quantity
+                "#,
+                item_id,
+                actor
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            let resolved_alerts = Self::record_deliveries(&mut tx, &previous, &[(item_id, quantity, quantity)], actor).await?;
+            resolved_alerts.get(&item_id).copied()
+        } else {
+            sqlx::query!(
+                r#"
+                UPDATE items
+                SET status = $2::varchar,
+                    started_at = CASE WHEN $2::varchar = 'in_preparation' THEN $3 ELSE started_at END,
+                    ready_at = CASE WHEN $2::varchar = 'ready' THEN $3 ELSE ready_at END,
+                    updated_at = $3,
+                    updated_by = $4
+                WHERE id = $1
+                "#,
+                item_id,
+                status as ItemStatus,
+                now,
+                actor
+            )
+            .execute(&mut *tx)
+            .await?;
+            None
+        };
 
-        Ok(created_items)
+        tx.commit().await?;
+        Ok(ItemStatusOutcome::Updated(resolved_alert))
     }
+}
 
-    pub async fn create_item(&self, tables_id: Uuid, new_item: NewItemRequest) -> Result<(), Error> {
-        sqlx::query!(
+#[async_trait]
+impl AlertRepository for Database {
+    async fn raise_overdue_alerts(&self, now: NaiveDateTime, grace_minutes: i32) -> Result<OverdueAlerts, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let resolved_ids = sqlx::query_scalar!(
             r#"
-            INSERT INTO items (
-                id, tables_id, menu_id, quantity,
-                delivered_quantity
-            ) VALUES (
-                $1, $2, $3, $4,
-                $5
-            )
+            UPDATE item_alerts
+            SET resolved_at = $1
+            FROM items
+            WHERE item_alerts.items_id = items.id
+              AND item_alerts.resolved_at IS NULL
+              AND (items.deleted_at IS NOT NULL OR items.delivered_quantity >= items.quantity)
+            RETURNING item_alerts.id
             "#,
-            Uuid::new_v4(),
-            tables_id,
-            new_item.menu_id,
-            new_item.quantity,
-            0,
+            now
         )
-        .execute(&self.pool)
+        .fetch_all(&mut tx)
+        .await?;
+
+        let raised_ids = sqlx::query_scalar!(
+            r#"
+            INSERT INTO item_alerts (items_id, overdue_at, raised_at)
+            SELECT
+                items.id,
+                items.created_at + make_interval(mins => Menu.prep_time + COALESCE(menu_alert_thresholds.grace_minutes, $2)),
+                $1
+            FROM items
+            JOIN Menu ON items.menu_id = Menu.id
+            LEFT JOIN menu_alert_thresholds ON menu_alert_thresholds.menu_id = Menu.id
+            WHERE items.deleted_at IS NULL
+              AND items.quantity > items.delivered_quantity
+              AND items.created_at + make_interval(mins => Menu.prep_time + COALESCE(menu_alert_thresholds.grace_minutes, $2)) <= $1
+              AND NOT EXISTS (
+                  SELECT 1 FROM item_alerts
+                  WHERE item_alerts.items_id = items.id AND item_alerts.resolved_at IS NULL
+              )
+            ON CONFLICT (items_id) WHERE resolved_at IS NULL DO NOTHING
+            RETURNING id
+            "#,
+            now,
+            grace_minutes
+        )
+        .fetch_all(&mut tx)
         .await?;
 
-        Ok(())
+        let alerts = OverdueAlerts {
+            raised: Self::alerts_by_id(&mut tx, &raised_ids).await?,
+            resolved: Self::alerts_by_id(&mut tx, &resolved_ids).await?,
+        };
+        tx.commit().await?;
+        Ok(alerts)
     }
 
-    pub async fn get_item(&self, tables_id: Uuid, item_id: Uuid) -> Result<PartialItemReturn, Error> {
-        let item = sqlx::query_as!(
-            PartialItemReturn,
+    async fn get_alerts_by_id(&self, alert_ids: &[Uuid]) -> Result<Vec<ItemAlert>, Error> {
+        Self::alerts_by_id(&self.pool, alert_ids).await
+    }
+
+    async fn get_alerts(&self, include_resolved: bool) -> Result<Vec<ItemAlert>, Error> {
+        let alerts = sqlx::query_as!(
+            ItemAlert,
             r#"
             SELECT
-                items.id,
+                item_alerts.id,
+                item_alerts.items_id,
                 items.tables_id,
+                Tables.name as table_name,
                 items.menu_id,
+                items.menu_name,
                 items.quantity,
                 items.delivered_quantity,
-                items.created_at,
-                Menu.prep_time as prep_time
-            FROM items
-            LEFT JOIN Menu on menu_id = Menu.id
-            WHERE items.tables_id = $1 AND items.id = $2
+                item_alerts.overdue_at,
+                item_alerts.raised_at,
+                item_alerts.resolved_at
+            FROM item_alerts
+            JOIN items ON item_alerts.items_id = items.id
+            JOIN Tables ON items.tables_id = Tables.id
+            WHERE $1 OR item_alerts.resolved_at IS NULL
+            ORDER BY item_alerts.resolved_at IS NOT NULL, item_alerts.overdue_at, item_alerts.id
             "#,
-            tables_id,
-            item_id
+            include_resolved
         )
-        .fetch_one(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(item)
+        Ok(alerts)
     }
 
-    pub async fn delete_item(&self, tables_id: Uuid, item_id: Uuid) -> Result<bool, Error> {
-        let result = sqlx::query!(
+    async fn get_alert_threshold(&self, menu_id: Uuid) -> Result<Option<AlertThreshold>, Error> {
+        let threshold = sqlx::query_as!(
+            AlertThreshold,
             r#"
-            DELETE FROM items
-            WHERE tables_id = $1 AND id = $2
+            SELECT menu_id, grace_minutes
+            FROM menu_alert_thresholds
+            WHERE menu_id = $1
             "#,
-            tables_id,
-            item_id
+            menu_id
         )
-        .execute(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(result.rows_affected() > 0)
+        Ok(threshold)
     }
 
-    pub async fn update_item(&self, item_id: Uuid, updated_item: UpdateItemRequest) -> Result<(), Error> {
-        sqlx::query!(
+    async fn set_alert_threshold(&self, menu_id: Uuid, grace_minutes: i32) -> Result<AlertThreshold, Error> {
+        let threshold = sqlx::query_as!(
+            AlertThreshold,
             r#"
-            UPDATE items
-            SET
-                quantity = COALESCE($1, quantity),
-                delivered_quantity = COALESCE(delivered_quantity + $2, delivered_quantity)
-            WHERE id = $3
+            INSERT INTO menu_alert_thresholds (menu_id, grace_minutes)
+            VALUES ($1, $2)
+            ON CONFLICT (menu_id) DO UPDATE SET grace_minutes = EXCLUDED.grace_minutes
+            RETURNING menu_id, grace_minutes
             "#,
-            updated_item.quantity,
-            updated_item.delivered_quantity,
-            item_id
+            menu_id,
+            grace_minutes
         )
-        .execute(&self.pool)
+        .fetch_one(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(threshold)
     }
 
+    async fn delete_alert_threshold(&self, menu_id: Uuid) -> Result<bool, Error> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM menu_alert_thresholds
+            WHERE menu_id = $1
+            "#,
+            menu_id
+        )
+        .execute(&self.pool)
+        .await?;
 
-    pub async fn update_items(&self, items: Vec<UpdateItemRequest>) -> Result<usize, anyhow::Error> {
-        if items.is_empty() {
-            return Ok(0);
-        } else if items.len() > MAX_ITEMS_LIMIT {
-            return Err(anyhow::anyhow!("The number of items exceeds the limit of {}", MAX_ITEMS_LIMIT));
-        }
-
-        let mut cases_quantity = String::from("quantity = CASE ");
-        let mut cases_delivered_quantity = String::from("delivered_quantity = CASE ");
-        let mut ids: Vec<String> = vec![];
-
-        for (i, _item) in items.iter().enumerate() {
-            let id_placeholder = format!("${}", i * 3 + 1);
-            let quantity_placeholder = format!("${}", i * 3 + 2);
-            let delivered_quantity_placeholder = format!("${}", i * 3 + 3);
-
-            cases_quantity.push_str(&format!("WHEN id = {} THEN {} ", id_placeholder, quantity_placeholder));
-            cases_delivered_quantity.push_str(&format!("WHEN id = {} THEN {} ", id_placeholder, delivered_quantity_placeholder));
-            ids.push(id_placeholder);
-        }
-
-        cases_quantity.push_str("ELSE quantity END");
-        cases_delivered_quantity.push_str("ELSE delivered_quantity END");
-
-        let query = format!(
-            "UPDATE items SET {}, {} WHERE id IN ({})",
-            cases_quantity,
-            cases_delivered_quantity,
-            ids.join(", ")
-        );
-
-        info!("Query: {}", query);
-
-        let mut query_args = sqlx::query(&query);
-        for item in items.iter() {
-            query_args = query_args
-                .bind(item.id)
-                .bind(item.quantity)
-                .bind(item.delivered_quantity);
-        }
-
-        let result = query_args.execute(&self.pool).await.map_err(|err| {
-            error!("Error updating items: {}", err);
-            anyhow::anyhow!(err)
-        })?;
-
-        Ok(result.rows_affected() as usize)
+        Ok(result.rows_affected() > 0)
     }
-
 }
@@ -1,25 +1,37 @@
 #[cfg(test)]
 mod tests {
-    use crate::{db::connection::{Database, NewItemRequest, UpdateItemRequest}, models::route_models::{FilterParams, Pagination}};
+    use crate::{db::connection::{AddDiscountOutcome, AddPaymentOutcome, AddRefundOutcome, CheckItemShare, Database, DeleteTableOutcome, FieldUpdate, ItemStatusOutcome, NewCheckRequest, NewDiscount, NewItemRequest, NewPayment, NewPriceRuleRequest, NewPromoCodeRequest, NewRefund, NewServiceChargeRuleRequest, NewTaxCategoryRequest, RefundItemRequest, RetireMenuOutcome, SettledItem, Settlement, TaxCategoryUpdate, UnreportedSales, UpdateItemRequest, UpdateMenuRequest, UpdateTableRequest, UpdateTaxCategoryRequest}, models::{restaurant_models::{DiscountKind, ItemStatus, KitchenQueueItem, RefundReason, Tender}, route_models::{FilterParams, Pagination, ZReportTotals, MAX_ITEMS_LIMIT}}, error::AppError, reports::z_report::z_report_totals};
 
+    use crate::db::repository::{AlertRepository, BillingRepository, CheckRepository, DayTotals, DiscountRepository, ItemRepository, KitchenRepository, MenuRepository, PaymentRepository, PriceRuleRepository, ReportRepository, TableRepository, TaxRepository};
+
+    use async_trait::async_trait;
+    use chrono::{NaiveDate, NaiveTime, Utc};
     use rust_decimal::Decimal;
     use sqlx::{postgres::PgPoolOptions, PgPool};
-    use tokio;
+    use tokio::sync::{Mutex, MutexGuard};
+    use uuid::Uuid;
+
+    const TEST_ACTOR: &str = "test-device";
+
+    // Every test truncates the same tables, so they have to run one at a time.
+    static TEST_DB_LOCK: Mutex<()> = Mutex::const_new(());
 
     async fn cleanup_database(pool: &PgPool) -> Result<(), sqlx::Error> {
-        sqlx::query("TRUNCATE TABLE items, Menu, Tables, Device RESTART IDENTITY CASCADE")
+        sqlx::query("TRUNCATE TABLE items, Menu, Tables, Device, promo_codes, z_reports RESTART IDENTITY CASCADE")
             .execute(pool)
             .await?;
 
         Ok(())
     }
 
-    async fn setup_test_db() -> PgPool {
+    async fn setup_test_db() -> (MutexGuard<'static, ()>, PgPool) {
+        let guard = TEST_DB_LOCK.lock().await;
         let database_url = std::env::var("TEST_DATABASE_URL")
             .expect("TEST_DATABASE_URL must be set");
+        let options = Database::connect_options(&database_url).expect("Invalid TEST_DATABASE_URL");
         let pool = PgPoolOptions::new()
             .max_connections(5)
-            .connect(&database_url)
+            .connect_with(options)
             .await
             .expect("Failed to create test database pool");
 
@@ -30,26 +42,61 @@ mod tests {
             .await
             .expect("Failed to apply migrations");
 
-        pool
+        (guard, pool)
+    }
+
+    /// Takes the items through the kitchen, as only ready items can be delivered.
+    async fn make_ready(db: &Database, tables_id: Uuid, item_ids: &[Uuid]) {
+        for item_id in item_ids {
+            for status in [ItemStatus::InPreparation, ItemStatus::Ready] {
+                assert_eq!(db.update_item_status(tables_id, *item_id, status, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap(), ItemStatusOutcome::Updated(None));
+            }
+        }
     }
 
+    /// Settles the items at no tax and without a service charge.
+    fn settlement(item_ids: &[Uuid]) -> Settlement {
+        Settlement {
+            items: item_ids.iter().map(|item_id| SettledItem { item_id: *item_id, tax_category: None }).collect(),
+            tax_rate: Decimal::ZERO,
+            service_charge: None,
+        }
+    }
+
+    /// Totals only the payments and voids of a close, keeping the sales it was given.
+    #[derive(Default)]
+    struct KeptSales(std::sync::Mutex<Option<UnreportedSales>>);
+
+    impl KeptSales {
+        fn sales(&self) -> UnreportedSales {
+            self.0.lock().unwrap().clone().expect("No day was closed")
+        }
+    }
+
+    #[async_trait]
+    impl DayTotals for KeptSales {
+        async fn totals(&self, sales: &UnreportedSales) -> Result<ZReportTotals, AppError> {
+            *self.0.lock().unwrap() = Some(sales.clone());
+            Ok(z_report_totals(&[], &sales.payments, &sales.voids))
+        }
+    }
 
     #[tokio::test]
     async fn test_create_and_get_item() {
-        let pool = setup_test_db().await;
+        let (_guard, pool) = setup_test_db().await;
         let db = Database { pool };
 
         let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
         let tables_id = new_table.id;
 
-        let new_menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15).await.expect("Failed to add menu item");
+        let new_menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
         let menu_id = new_menu.id;
 
         let new_item = NewItemRequest {
             quantity: 2,
             menu_id,
         };
-        db.create_item(tables_id, new_item).await.unwrap();
+        db.create_item(tables_id, new_item, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
 
         let pagination = Pagination {
             limit: Some(10),
@@ -68,20 +115,20 @@ mod tests {
 
     #[tokio::test]
     async fn test_update_and_delete_item() {
-        let pool = setup_test_db().await;
+        let (_guard, pool) = setup_test_db().await;
         let db = Database { pool };
 
         let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
         let tables_id = new_table.id;
 
-        let new_menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15).await.expect("Failed to add menu item");
+        let new_menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
         let menu_id = new_menu.id;
 
         let new_item = NewItemRequest {
             quantity: 2,
             menu_id,
         };
-        db.create_item(tables_id, new_item).await.unwrap();
+        db.create_item(tables_id, new_item, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
 
         let pagination = Pagination {
             limit: Some(10),
@@ -92,22 +139,1114 @@ mod tests {
         assert!(!items.is_empty(), "No items found for the table");
         let item_id = items[0].id;
 
+        make_ready(&db, tables_id, &[item_id]).await;
         let update_request = UpdateItemRequest {
             id: item_id,
-            quantity: Some(3),
-            delivered_quantity: Some(1),
+            quantity: Some(FieldUpdate::Set(3)),
+            delivered_quantity: Some(FieldUpdate::Increment(1)),
         };
-        db.update_item(item_id, update_request).await.unwrap();
+        db.update_item(item_id, update_request, TEST_ACTOR).await.unwrap();
 
         let updated_item = db.get_item(tables_id, item_id).await.unwrap();
         assert_eq!(updated_item.quantity, 3);
         assert_eq!(updated_item.delivered_quantity, 1);
 
-        let deleted = db.delete_item(tables_id, item_id).await.unwrap();
+        let deleted = db.delete_item(tables_id, item_id, TEST_ACTOR).await.unwrap();
         assert!(deleted);
 
         let result = db.get_item(tables_id, item_id).await;
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_rename_and_delete_table() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let tables_id = new_table.id;
+
+        let rename = UpdateTableRequest {
+            name: Some("Terrace 1".to_string()),
+            covers: None,
+        };
+        let renamed = db.update_table(tables_id, rename).await.unwrap();
+        assert_eq!(renamed.map(|table| table.name), Some("Terrace 1".to_string()));
+        let seated = db.update_table(tables_id, UpdateTableRequest { name: None, covers: Some(6) }).await.unwrap();
+        assert_eq!(seated.map(|table| (table.name, table.covers)), Some(("Terrace 1".to_string(), Some(6))));
+        assert_eq!(db.get_table(tables_id).await.unwrap().name, "Terrace 1");
+
+        let new_menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let new_item = NewItemRequest {
+            quantity: 2,
+            menu_id: new_menu.id,
+        };
+        db.create_item(tables_id, new_item, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+
+        let outcome = db.delete_table(tables_id).await.unwrap();
+        assert_eq!(outcome, DeleteTableOutcome::PendingItems(1));
+
+        let pagination = Pagination {
+            limit: Some(10),
+            offset: Some(0),
+        };
+        let filters = FilterParams { menu_id: None };
+        let items = db.get_all_remaining_items_from_table(tables_id, pagination, filters).await.unwrap();
+        let item_id = items[0].id;
+        make_ready(&db, tables_id, &[item_id]).await;
+        let update_request = UpdateItemRequest {
+            id: item_id,
+            quantity: None,
+            delivered_quantity: Some(FieldUpdate::Increment(2)),
+        };
+        db.update_item(item_id, update_request, TEST_ACTOR).await.unwrap();
+
+        let DeleteTableOutcome::Deleted(deleted) = db.delete_table(tables_id).await.unwrap() else {
+            panic!("Table was not deleted");
+        };
+        assert_eq!(deleted.iter().map(|item| (item.id, item.delivered_quantity)).collect::<Vec<_>>(), vec![(item_id, 2)]);
+        assert!(db.get_table(tables_id).await.is_err());
+
+        let outcome = db.delete_table(tables_id).await.unwrap();
+        assert_eq!(outcome, DeleteTableOutcome::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_update_and_retire_menu() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let ordered = db.add_menu("Ordered Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let unused = db.add_menu("Unused Dish".to_string(), Decimal::new(900, 2), 5, None).await.expect("Failed to add menu item");
+
+        let update_request = UpdateMenuRequest {
+            price: Some(Decimal::new(1750, 2)),
+            prep_time: None,
+            tax_category_id: None,
+        };
+        let updated = db.update_menu(ordered.id, update_request).await.unwrap().expect("Menu item not found");
+        assert_eq!(updated.price, Decimal::new(1750, 2));
+        assert_eq!(updated.prep_time, 15);
+
+        let new_item = NewItemRequest {
+            quantity: 1,
+            menu_id: ordered.id,
+        };
+        db.create_item(new_table.id, new_item, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+
+        assert_eq!(db.retire_menu(ordered.id).await.unwrap(), RetireMenuOutcome::Retired);
+        assert_eq!(db.retire_menu(unused.id).await.unwrap(), RetireMenuOutcome::Deleted);
+        assert_eq!(db.retire_menu(unused.id).await.unwrap(), RetireMenuOutcome::NotFound);
+
+        let retired = db.get_menu_item(ordered.id).await.unwrap();
+        assert!(retired.retired_at.is_some());
+
+        let active_menu = db.get_menu(false).await.unwrap();
+        assert!(active_menu.iter().all(|menu| menu.id != ordered.id));
+        let full_menu = db.get_menu(true).await.unwrap();
+        assert!(full_menu.iter().any(|menu| menu.id == ordered.id));
+    }
+
+    #[tokio::test]
+    async fn test_items_keep_the_price_they_were_ordered_at() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+
+        let new_items = vec![NewItemRequest { quantity: 2, menu_id: menu.id }];
+        let created = db.create_items(new_table.id, new_items, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        assert_eq!(created[0].unit_price, Decimal::new(1500, 2));
+        assert_eq!(created[0].menu_name, "Test Dish");
+
+        let update_request = UpdateMenuRequest {
+            price: Some(Decimal::new(1999, 2)),
+            prep_time: None,
+            tax_category_id: None,
+        };
+        db.update_menu(menu.id, update_request).await.unwrap();
+        db.create_item(new_table.id, NewItemRequest { quantity: 1, menu_id: menu.id }, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+
+        let bill_items = db.get_bill_items(new_table.id).await.unwrap();
+        let mut prices: Vec<Decimal> = bill_items.iter().map(|item| item.unit_price).collect();
+        prices.sort();
+        assert_eq!(prices, vec![Decimal::new(1500, 2), Decimal::new(1999, 2)]);
+
+        let item = db.get_item(new_table.id, created[0].id).await.unwrap();
+        assert_eq!(item.unit_price, Decimal::new(1500, 2));
+    }
+
+    #[tokio::test]
+    async fn test_replace_and_delete_checks() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let new_items = vec![NewItemRequest { quantity: 1, menu_id: menu.id }, NewItemRequest { quantity: 2, menu_id: menu.id }];
+        let created = db.create_items(new_table.id, new_items, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+
+        let split = |name: &str, numerator: i32| NewCheckRequest {
+            name: name.to_string(),
+            items: vec![
+                CheckItemShare { item_id: created[0].id, numerator: 1, denominator: 1 },
+                CheckItemShare { item_id: created[1].id, numerator, denominator: 2 },
+            ],
+        };
+        db.replace_checks(new_table.id, vec![split("Old Check", 2)], TEST_ACTOR).await.unwrap();
+        let checks = db.replace_checks(new_table.id, vec![split("Seat 1", 1), split("Seat 2", 1)], TEST_ACTOR).await.unwrap();
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[1].name, "Seat 2");
+        assert_eq!(checks[0].created_by.as_deref(), Some(TEST_ACTOR));
+
+        let stored = db.get_checks(new_table.id).await.unwrap();
+        assert_eq!(stored.iter().map(|check| check.name.as_str()).collect::<Vec<_>>(), vec!["Seat 1", "Seat 2"]);
+        assert_eq!(db.get_check_items(new_table.id).await.unwrap().len(), 4);
+
+        assert_eq!(db.delete_checks(new_table.id).await.unwrap(), 2);
+        assert!(db.get_check_items(new_table.id).await.unwrap().is_empty());
+
+        db.replace_checks(new_table.id, vec![split("Seat 1", 1)], TEST_ACTOR).await.unwrap();
+        make_ready(&db, new_table.id, &created.iter().map(|item| item.id).collect::<Vec<_>>()).await;
+        db.update_items(
+            created.iter().map(|item| UpdateItemRequest {
+                id: item.id,
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Set(item.quantity)),
+            }).collect(),
+            TEST_ACTOR,
+        ).await.unwrap();
+        assert!(matches!(db.delete_table(new_table.id).await.unwrap(), DeleteTableOutcome::Deleted(_)));
+    }
+
+    #[tokio::test]
+    async fn test_payments_settle_the_session() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let created = db.create_items(new_table.id, vec![NewItemRequest { quantity: 2, menu_id: menu.id }], Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let item_ids: Vec<Uuid> = created.iter().map(|item| item.id).collect();
+
+        let payment = |tender: Tender, amount: Decimal| NewPayment {
+            check_id: None,
+            tender,
+            amount,
+            tip: Decimal::ZERO,
+            tendered: amount,
+            change: Decimal::ZERO,
+        };
+
+        let outcome = db.add_payment(new_table.id, payment(Tender::Card, Decimal::new(1000, 2)), Decimal::ZERO, None, TEST_ACTOR).await.unwrap();
+        assert!(matches!(outcome, AddPaymentOutcome::Recorded { closed: false, .. }));
+        let session = db.get_open_session(new_table.id).await.unwrap().expect("Session not opened");
+
+        let stale = db.add_payment(new_table.id, payment(Tender::Cash, Decimal::new(2000, 2)), Decimal::ZERO, None, TEST_ACTOR).await.unwrap();
+        assert!(matches!(stale, AddPaymentOutcome::Stale));
+
+        let outcome = db
+            .add_payment(new_table.id, payment(Tender::Voucher, Decimal::new(2000, 2)), Decimal::new(1000, 2), Some(settlement(&item_ids)), TEST_ACTOR)
+            .await
+            .unwrap();
+        assert!(matches!(outcome, AddPaymentOutcome::Recorded { closed: true, .. }));
+
+        let payments = db.get_payments(session.id).await.unwrap();
+        assert_eq!(payments.iter().map(|payment| payment.tender).collect::<Vec<_>>(), vec![Tender::Card, Tender::Voucher]);
+        assert!(db.get_open_session(new_table.id).await.unwrap().is_none());
+        assert!(db.get_bill_items(new_table.id).await.unwrap().is_empty());
+        assert_eq!(db.get_paid_item_ids(&item_ids).await.unwrap().len(), 1);
+
+        assert!(!db.delete_item(new_table.id, item_ids[0], TEST_ACTOR).await.unwrap());
+        make_ready(&db, new_table.id, &item_ids[..1]).await;
+        db.update_items(
+            vec![UpdateItemRequest {
+                id: item_ids[0],
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Set(2)),
+            }],
+            TEST_ACTOR,
+        ).await.unwrap();
+        let item = db.get_item(new_table.id, item_ids[0]).await.unwrap();
+        assert_eq!((item.quantity, item.delivered_quantity), (2, 2));
+        assert!(item.paid_at.is_some());
+
+        assert_eq!(db.delete_table(new_table.id).await.unwrap(), DeleteTableOutcome::HasSales);
+        assert_eq!(db.get_payments(session.id).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_settled_sessions_keep_their_rates() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let category = db
+            .add_tax_category(NewTaxCategoryRequest {
+                name: "Test Wine Tax".to_string(),
+                rate: Decimal::new(20, 2),
+                inclusive: true,
+            })
+            .await
+            .unwrap();
+        let rule = db
+            .add_service_charge_rule(NewServiceChargeRuleRequest {
+                name: "Test party".to_string(),
+                rate: Decimal::new(10, 2),
+                min_covers: 2,
+            })
+            .await
+            .unwrap();
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        db.update_table(new_table.id, UpdateTableRequest { name: None, covers: Some(2) }).await.unwrap();
+        let wine = db.add_menu("Test Wine".to_string(), Decimal::new(3000, 2), 1, Some(category.id)).await.expect("Failed to add menu item");
+        let bread = db.add_menu("Test Bread".to_string(), Decimal::new(400, 2), 1, None).await.expect("Failed to add menu item");
+        let created = db
+            .create_items(
+                new_table.id,
+                vec![NewItemRequest { quantity: 1, menu_id: wine.id }, NewItemRequest { quantity: 1, menu_id: bread.id }],
+                Utc::now().naive_utc(),
+                TEST_ACTOR,
+            )
+            .await
+            .unwrap();
+
+        let settlement = Settlement {
+            items: vec![
+                SettledItem { item_id: created[0].id, tax_category: Some(category.clone().into()) },
+                SettledItem { item_id: created[1].id, tax_category: None },
+            ],
+            tax_rate: Decimal::new(8, 2),
+            service_charge: Some(rule.clone()),
+        };
+        let payment = NewPayment {
+            check_id: None,
+            tender: Tender::Card,
+            amount: Decimal::new(3740, 2),
+            tip: Decimal::ZERO,
+            tendered: Decimal::new(3740, 2),
+            change: Decimal::ZERO,
+        };
+        db.add_payment(new_table.id, payment, Decimal::ZERO, Some(settlement), TEST_ACTOR).await.unwrap();
+        let session = db.get_last_session(new_table.id).await.unwrap().expect("Session not found");
+
+        let update = UpdateTaxCategoryRequest {
+            name: Some("Test Spirits Tax".to_string()),
+            rate: Some(Decimal::new(25, 2)),
+            inclusive: Some(false),
+        };
+        db.update_tax_category(category.id, update).await.unwrap().expect("Tax category not found");
+        assert!(db.delete_tax_category(category.id).await.unwrap());
+        assert!(db.delete_service_charge_rule(rule.id).await.unwrap());
+
+        let items = db.get_settled_bill_items(session.id).await.unwrap();
+        let settled_in = |item_id: Uuid| items.iter().find(|item| item.item_id == item_id).and_then(|item| item.tax_category_id);
+        assert_eq!((settled_in(created[0].id), settled_in(created[1].id)), (Some(category.id), None));
+        let categories = db.get_settled_taxes(session.id).await.unwrap();
+        assert_eq!(categories.len(), 1);
+        assert_eq!(
+            (categories[0].id, categories[0].name.as_str(), categories[0].rate, categories[0].inclusive),
+            (category.id, "Test Wine Tax", Decimal::new(20, 2), true)
+        );
+        assert_eq!(session.tax_rate, Some(Decimal::new(8, 2)));
+        assert_eq!(
+            (session.service_charge_rule_id, session.service_charge_name.as_deref(), session.service_charge_rate),
+            (Some(rule.id), Some("Test party"), Some(Decimal::new(10, 2)))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refunds_mark_items_and_count_in_the_day() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let created = db.create_items(new_table.id, vec![NewItemRequest { quantity: 2, menu_id: menu.id }], Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let item_id = created[0].id;
+
+        let outcome = db
+            .add_payment(
+                new_table.id,
+                NewPayment {
+                    check_id: None,
+                    tender: Tender::Card,
+                    amount: Decimal::new(3000, 2),
+                    tip: Decimal::new(200, 2),
+                    tendered: Decimal::new(3200, 2),
+                    change: Decimal::ZERO,
+                },
+                Decimal::ZERO,
+                Some(settlement(&[item_id])),
+                TEST_ACTOR,
+            )
+            .await
+            .unwrap();
+        let AddPaymentOutcome::Recorded { payment, closed: true } = outcome else {
+            panic!("Payment did not settle the table");
+        };
+
+        let refund = |quantity: i32| NewRefund {
+            amount: Decimal::new(1500, 2),
+            tip: Decimal::ZERO,
+            reason: RefundReason::Quality,
+            approved_by: "manager".to_string(),
+            items: vec![RefundItemRequest { item_id, quantity }],
+        };
+        let outcome = db.add_refund(payment.id, refund(1), Decimal::ZERO, TEST_ACTOR).await.unwrap();
+        let AddRefundOutcome::Recorded(recorded) = outcome else {
+            panic!("Refund was not recorded");
+        };
+        assert_eq!((recorded.amount, recorded.tender), (Decimal::new(-1500, 2), Tender::Card));
+        assert_eq!(recorded.refund_of, Some(payment.id));
+        assert_eq!(recorded.reason, Some(RefundReason::Quality));
+
+        let stale = db.add_refund(payment.id, refund(1), Decimal::ZERO, TEST_ACTOR).await.unwrap();
+        assert!(matches!(stale, AddRefundOutcome::Stale));
+        let too_many = db.add_refund(payment.id, refund(2), Decimal::new(1500, 2), TEST_ACTOR).await.unwrap();
+        assert!(matches!(too_many, AddRefundOutcome::Stale));
+
+        let items = db.get_refundable_items(payment.session_id).await.unwrap();
+        assert_eq!((items[0].quantity, items[0].refunded_quantity), (2, 1));
+
+        let from = recorded.created_at - chrono::Duration::minutes(1);
+        let day = db.get_payments_between(from, from + chrono::Duration::days(1)).await.unwrap();
+        let ids: Vec<Uuid> = day.iter().map(|payment| payment.id).collect();
+        assert!(ids.contains(&payment.id) && ids.contains(&recorded.id));
+
+        make_ready(&db, new_table.id, &[item_id]).await;
+        db.update_items(
+            vec![UpdateItemRequest {
+                id: item_id,
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Set(2)),
+            }],
+            TEST_ACTOR,
+        ).await.unwrap();
+        assert_eq!(db.delete_table(new_table.id).await.unwrap(), DeleteTableOutcome::HasSales);
+    }
+
+    #[tokio::test]
+    async fn test_tax_categories_follow_the_menu() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let category = db
+            .add_tax_category(NewTaxCategoryRequest {
+                name: "Test Alcohol".to_string(),
+                rate: Decimal::new(20, 2),
+                inclusive: true,
+            })
+            .await
+            .unwrap();
+        let update = UpdateTaxCategoryRequest {
+            name: None,
+            rate: Some(Decimal::new(21, 2)),
+            inclusive: None,
+        };
+        let updated = db.update_tax_category(category.id, update).await.unwrap().expect("Tax category not found");
+        assert_eq!((updated.rate, updated.inclusive), (Decimal::new(21, 2), true));
+
+        let rule = db
+            .add_service_charge_rule(NewServiceChargeRuleRequest {
+                name: "Large party".to_string(),
+                rate: Decimal::new(125, 3),
+                min_covers: 8,
+            })
+            .await
+            .unwrap();
+        assert!(db.get_service_charge_rules().await.unwrap().iter().any(|found| found.id == rule.id));
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Wine".to_string(), Decimal::new(3000, 2), 1, Some(category.id)).await.expect("Failed to add menu item");
+        db.create_item(new_table.id, NewItemRequest { quantity: 1, menu_id: menu.id }, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let items = db.get_bill_items(new_table.id).await.unwrap();
+        assert_eq!(items[0].tax_category_id, Some(category.id));
+
+        let clear = UpdateMenuRequest { price: None, prep_time: None, tax_category_id: Some(TaxCategoryUpdate::Clear) };
+        assert_eq!(db.update_menu(menu.id, clear).await.unwrap().expect("Menu item not found").tax_category_id, None);
+        let set = UpdateMenuRequest { price: None, prep_time: None, tax_category_id: Some(TaxCategoryUpdate::Set(category.id)) };
+        assert_eq!(db.update_menu(menu.id, set).await.unwrap().expect("Menu item not found").tax_category_id, Some(category.id));
+
+        assert!(db.delete_tax_category(category.id).await.unwrap());
+        assert_eq!(db.get_menu_item(menu.id).await.unwrap().tax_category_id, None);
+        assert!(db.delete_service_charge_rule(rule.id).await.unwrap());
+        assert!(!db.delete_service_charge_rule(rule.id).await.unwrap());
+
+        make_ready(&db, new_table.id, &[items[0].item_id]).await;
+        db.update_items(
+            vec![UpdateItemRequest {
+                id: items[0].item_id,
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Set(1)),
+            }],
+            TEST_ACTOR,
+        ).await.unwrap();
+        assert!(matches!(db.delete_table(new_table.id).await.unwrap(), DeleteTableOutcome::Deleted(_)));
+    }
+
+    #[tokio::test]
+    async fn test_discounts_redeem_promo_codes_and_settle_with_the_table() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let promo_code = db
+            .add_promo_code(NewPromoCodeRequest {
+                code: "SUMMER".to_string(),
+                kind: DiscountKind::Fixed,
+                value: Decimal::new(500, 2),
+                valid_from: None,
+                valid_until: None,
+                max_uses: Some(1),
+            })
+            .await
+            .unwrap();
+        let found = db.get_promo_code_by_code("SUMMER").await.unwrap().expect("Promo code not found");
+        assert_eq!(found.id, promo_code.id);
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Steak".to_string(), Decimal::new(2000, 2), 1, None).await.expect("Failed to add menu item");
+        db.create_item(new_table.id, NewItemRequest { quantity: 1, menu_id: menu.id }, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let items = db.get_bill_items(new_table.id).await.unwrap();
+
+        let redeem = || NewDiscount {
+            items_id: None,
+            kind: promo_code.kind,
+            value: promo_code.value,
+            reason: None,
+            promo_code_id: Some(promo_code.id),
+            applied_by: "Alice".to_string(),
+        };
+        let AddDiscountOutcome::Added(discount) = db.add_discount(new_table.id, redeem(), TEST_ACTOR).await.unwrap() else {
+            panic!("Promo code should have been redeemed");
+        };
+        assert_eq!(discount.applied_by, "Alice");
+        assert_eq!(discount.created_by.as_deref(), Some(TEST_ACTOR));
+        assert!(matches!(
+            db.add_discount(new_table.id, redeem(), TEST_ACTOR).await.unwrap(),
+            AddDiscountOutcome::PromoCodeUsedUp
+        ));
+
+        assert!(db.delete_discount(new_table.id, discount.id).await.unwrap());
+        assert!(!db.delete_discount(new_table.id, discount.id).await.unwrap());
+        assert_eq!(db.get_promo_codes().await.unwrap()[0].uses, 0);
+
+        let comp = NewDiscount {
+            items_id: Some(items[0].item_id),
+            kind: DiscountKind::Percent,
+            value: Decimal::from(50),
+            reason: Some("Overcooked".to_string()),
+            promo_code_id: None,
+            applied_by: "Bob".to_string(),
+        };
+        db.add_discount(new_table.id, comp, TEST_ACTOR).await.unwrap();
+        db.add_discount(new_table.id, redeem(), TEST_ACTOR).await.unwrap();
+        assert_eq!(db.get_discounts(new_table.id).await.unwrap().len(), 2);
+
+        let payment = NewPayment {
+            check_id: None,
+            tender: Tender::Cash,
+            amount: Decimal::new(550, 2),
+            tip: Decimal::ZERO,
+            tendered: Decimal::new(550, 2),
+            change: Decimal::ZERO,
+        };
+        db.add_payment(new_table.id, payment, Decimal::ZERO, Some(settlement(&[items[0].item_id])), TEST_ACTOR).await.unwrap();
+        assert!(db.get_discounts(new_table.id).await.unwrap().is_empty());
+
+        make_ready(&db, new_table.id, &[items[0].item_id]).await;
+        db.update_items(
+            vec![UpdateItemRequest {
+                id: items[0].item_id,
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Set(1)),
+            }],
+            TEST_ACTOR,
+        ).await.unwrap();
+        assert_eq!(db.delete_table(new_table.id).await.unwrap(), DeleteTableOutcome::HasSales);
+    }
+
+    #[tokio::test]
+    async fn test_deleting_a_table_keeps_its_sales() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let promo_code = db
+            .add_promo_code(NewPromoCodeRequest {
+                code: "WELCOME".to_string(),
+                kind: DiscountKind::Fixed,
+                value: Decimal::new(200, 2),
+                valid_from: None,
+                valid_until: None,
+                max_uses: Some(1),
+            })
+            .await
+            .unwrap();
+        let menu = db.add_menu("Test Soup".to_string(), Decimal::new(600, 2), 1, None).await.expect("Failed to add menu item");
+
+        let open_table = db.add_table("Open Table".to_string()).await.expect("Failed to add table");
+        let created = db.create_item(open_table.id, NewItemRequest { quantity: 1, menu_id: menu.id }, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        make_ready(&db, open_table.id, &[created.id]).await;
+        let delivery = UpdateItemRequest { id: created.id, quantity: None, delivered_quantity: Some(FieldUpdate::Set(1)) };
+        db.update_items(vec![delivery], TEST_ACTOR).await.unwrap();
+        let redeem = NewDiscount {
+            items_id: None,
+            kind: promo_code.kind,
+            value: promo_code.value,
+            reason: None,
+            promo_code_id: Some(promo_code.id),
+            applied_by: "Alice".to_string(),
+        };
+        db.add_discount(open_table.id, redeem, TEST_ACTOR).await.unwrap();
+        assert!(matches!(db.delete_table(open_table.id).await.unwrap(), DeleteTableOutcome::Deleted(_)));
+        assert_eq!(db.get_promo_codes().await.unwrap()[0].uses, 0);
+
+        let voided_table = db.add_table("Voided Table".to_string()).await.expect("Failed to add table");
+        let created = db.create_item(voided_table.id, NewItemRequest { quantity: 1, menu_id: menu.id }, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        assert!(db.delete_item(voided_table.id, created.id, TEST_ACTOR).await.unwrap());
+        assert_eq!(db.delete_table(voided_table.id).await.unwrap(), DeleteTableOutcome::HasSales);
+        let kept = KeptSales::default();
+        db.close_day(&kept, TEST_ACTOR).await.unwrap();
+        assert_eq!(kept.sales().voids.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_price_rules_set_the_price_at_order_time() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Beer".to_string(), Decimal::new(600, 2), 1, None).await.expect("Failed to add menu item");
+        let happy_hour = |price: Decimal| NewPriceRuleRequest {
+            name: "Happy hour".to_string(),
+            price,
+            days: vec![1, 2, 3, 4, 5],
+            start_time: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(19, 0, 0).unwrap(),
+        };
+        let rule = db.add_price_rule(menu.id, happy_hour(Decimal::new(400, 2))).await.unwrap();
+        assert_eq!(rule.days, vec![1, 2, 3, 4, 5]);
+
+        // 2 September 2024 was a Monday.
+        let monday = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        let order = || vec![NewItemRequest { quantity: 1, menu_id: menu.id }];
+        let during = db.create_items(new_table.id, order(), monday.and_hms_opt(17, 30, 0).unwrap(), TEST_ACTOR).await.unwrap();
+        assert_eq!(during[0].unit_price, Decimal::new(400, 2));
+        let after = db.create_items(new_table.id, order(), monday.and_hms_opt(19, 0, 0).unwrap(), TEST_ACTOR).await.unwrap();
+        assert_eq!(after[0].unit_price, Decimal::new(600, 2));
+        let single = NewItemRequest { quantity: 1, menu_id: menu.id };
+        db.create_item(new_table.id, single, monday.and_hms_opt(18, 0, 0).unwrap(), TEST_ACTOR).await.unwrap();
+
+        let updated = db.update_price_rule(menu.id, rule.id, happy_hour(Decimal::new(350, 2))).await.unwrap().expect("Price rule not found");
+        assert_eq!(updated.price, Decimal::new(350, 2));
+        assert!(db.update_price_rule(Uuid::new_v4(), rule.id, happy_hour(Decimal::ONE)).await.unwrap().is_none());
+        let items = db.get_bill_items(new_table.id).await.unwrap();
+        let mut prices: Vec<Decimal> = items.iter().map(|item| item.unit_price).collect();
+        prices.sort();
+        assert_eq!(prices, vec![Decimal::new(400, 2), Decimal::new(400, 2), Decimal::new(600, 2)]);
+
+        assert!(db.delete_price_rule(menu.id, rule.id).await.unwrap());
+        assert!(db.get_price_rules(&[menu.id]).await.unwrap().is_empty());
+
+        make_ready(&db, new_table.id, &items.iter().map(|item| item.item_id).collect::<Vec<_>>()).await;
+        db.update_items(
+            items
+                .iter()
+                .map(|item| UpdateItemRequest {
+                    id: item.item_id,
+                    quantity: None,
+                    delivered_quantity: Some(FieldUpdate::Set(1)),
+                })
+                .collect(),
+            TEST_ACTOR,
+        ).await.unwrap();
+        assert!(matches!(db.delete_table(new_table.id).await.unwrap(), DeleteTableOutcome::Deleted(_)));
+    }
+
+    #[tokio::test]
+    async fn test_paginate_and_filter_remaining_items() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let tables_id = new_table.id;
+
+        let burger = db.add_menu("Burger".to_string(), Decimal::new(599, 2), 5, None).await.expect("Failed to add menu item");
+        let pizza = db.add_menu("Pizza".to_string(), Decimal::new(899, 2), 8, None).await.expect("Failed to add menu item");
+
+        let new_items = (0..12)
+            .map(|i| NewItemRequest {
+                quantity: 1,
+                menu_id: if i % 3 == 0 { pizza.id } else { burger.id },
+            })
+            .collect();
+        db.create_items(tables_id, new_items, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+
+        let no_filters = FilterParams { menu_id: None };
+        assert_eq!(db.count_remaining_items_from_table(tables_id, &no_filters).await.unwrap(), 12);
+
+        let pagination = Pagination {
+            limit: Some(1000),
+            offset: Some(0),
+        };
+        assert_eq!(pagination.limit(), MAX_ITEMS_LIMIT);
+        let items = db.get_all_remaining_items_from_table(tables_id, pagination, no_filters).await.unwrap();
+        assert_eq!(items.len(), 12);
+
+        let pagination = Pagination {
+            limit: Some(5),
+            offset: Some(10),
+        };
+        let items = db.get_all_remaining_items_from_table(tables_id, pagination, FilterParams { menu_id: None }).await.unwrap();
+        assert_eq!(items.len(), 2);
+
+        let pizza_filter = FilterParams { menu_id: Some(pizza.id) };
+        assert_eq!(db.count_remaining_items_from_table(tables_id, &pizza_filter).await.unwrap(), 4);
+        let pagination = Pagination {
+            limit: None,
+            offset: None,
+        };
+        let items = db.get_all_remaining_items_from_table(tables_id, pagination, pizza_filter).await.unwrap();
+        assert_eq!(items.len(), 4);
+        assert!(items.iter().all(|item| item.menu_id == pizza.id));
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete_and_restore_item() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let tables_id = new_table.id;
+
+        let new_menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let new_item = NewItemRequest {
+            quantity: 2,
+            menu_id: new_menu.id,
+        };
+        let created = db.create_items(tables_id, vec![new_item], Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let item_id = created[0].id;
+
+        assert!(db.delete_item(tables_id, item_id, TEST_ACTOR).await.unwrap());
+        assert!(!db.delete_item(tables_id, item_id, TEST_ACTOR).await.unwrap());
+        assert!(db.get_item(tables_id, item_id).await.is_err());
+        assert_eq!(db.count_remaining_items_from_table(tables_id, &FilterParams { menu_id: None }).await.unwrap(), 0);
+
+        let deleted = db.get_deleted_items(None, None).await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].id, item_id);
+        assert_eq!(deleted[0].deleted_by.as_deref(), Some(TEST_ACTOR));
+
+        let window_start = deleted[0].deleted_at + chrono::Duration::seconds(1);
+        assert!(db.get_deleted_items(Some(window_start), None).await.unwrap().is_empty());
+
+        assert!(db.restore_item(tables_id, item_id, TEST_ACTOR).await.unwrap());
+        assert!(!db.restore_item(tables_id, item_id, TEST_ACTOR).await.unwrap());
+        let restored = db.get_item(tables_id, item_id).await.unwrap();
+        assert_eq!(restored.quantity, 2);
+
+        let (created_by, updated_by): (Option<String>, Option<String>) =
+            sqlx::query_as("SELECT created_by, updated_by FROM items WHERE id = $1")
+                .bind(item_id)
+                .fetch_one(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(created_by.as_deref(), Some(TEST_ACTOR));
+        assert_eq!(updated_by.as_deref(), Some(TEST_ACTOR));
+        assert!(db.get_deleted_items(None, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_partial_deliveries_are_logged() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let tables_id = new_table.id;
+
+        let new_menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let new_item = NewItemRequest {
+            quantity: 3,
+            menu_id: new_menu.id,
+        };
+        let created = db.create_items(tables_id, vec![new_item], Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let item_id = created[0].id;
+
+        make_ready(&db, tables_id, &[item_id]).await;
+        let update_request = UpdateItemRequest {
+            id: item_id,
+            quantity: None,
+            delivered_quantity: Some(FieldUpdate::Increment(1)),
+        };
+        db.update_item(item_id, update_request, TEST_ACTOR).await.unwrap();
+        assert!(db.get_item(tables_id, item_id).await.unwrap().delivered_at.is_none());
+
+        let update_request = UpdateItemRequest {
+            id: item_id,
+            quantity: Some(FieldUpdate::Set(3)),
+            delivered_quantity: Some(FieldUpdate::Set(3)),
+        };
+        assert_eq!(db.update_items(vec![update_request], TEST_ACTOR).await.unwrap().len(), 1);
+        let delivered = db.get_item(tables_id, item_id).await.unwrap();
+        assert_eq!(delivered.delivered_quantity, 3);
+        assert!(delivered.delivered_at.is_some());
+
+        let deliveries = db.get_item_deliveries(tables_id, item_id).await.unwrap();
+        assert_eq!(deliveries.iter().map(|delivery| delivery.quantity).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(deliveries.iter().all(|delivery| delivery.delivered_by.as_deref() == Some(TEST_ACTOR)));
+    }
+
+    #[tokio::test]
+    async fn test_set_and_increment_updates() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let tables_id = new_table.id;
+
+        let new_menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let new_items = vec![
+            NewItemRequest { quantity: 4, menu_id: new_menu.id },
+            NewItemRequest { quantity: 4, menu_id: new_menu.id },
+        ];
+        let created = db.create_items(tables_id, new_items, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let (first_id, second_id) = (created[0].id, created[1].id);
+
+        // Single item path: increment both fields, then set both fields.
+        make_ready(&db, tables_id, &[first_id, second_id]).await;
+        let update_request = UpdateItemRequest {
+            id: first_id,
+            quantity: Some(FieldUpdate::Increment(2)),
+            delivered_quantity: Some(FieldUpdate::Increment(1)),
+        };
+        assert!(db.update_item(first_id, update_request, TEST_ACTOR).await.unwrap());
+        let item = db.get_item(tables_id, first_id).await.unwrap();
+        assert_eq!((item.quantity, item.delivered_quantity), (6, 1));
+
+        let update_request = UpdateItemRequest {
+            id: first_id,
+            quantity: Some(FieldUpdate::Set(5)),
+            delivered_quantity: Some(FieldUpdate::Set(2)),
+        };
+        assert!(db.update_item(first_id, update_request, TEST_ACTOR).await.unwrap());
+        let item = db.get_item(tables_id, first_id).await.unwrap();
+        assert_eq!((item.quantity, item.delivered_quantity), (5, 2));
+
+        // Bulk path: mixed modes, and missing fields keep their stored values.
+        let update_requests = vec![
+            UpdateItemRequest {
+                id: first_id,
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Increment(2)),
+            },
+            UpdateItemRequest {
+                id: second_id,
+                quantity: Some(FieldUpdate::Increment(-1)),
+                delivered_quantity: None,
+            },
+        ];
+        assert_eq!(db.update_items(update_requests, TEST_ACTOR).await.unwrap().len(), 2);
+        let first = db.get_item(tables_id, first_id).await.unwrap();
+        assert_eq!((first.quantity, first.delivered_quantity), (5, 4));
+        let second = db.get_item(tables_id, second_id).await.unwrap();
+        assert_eq!((second.quantity, second.delivered_quantity), (3, 0));
+
+        let update_requests = vec![UpdateItemRequest {
+            id: second_id,
+            quantity: Some(FieldUpdate::Set(2)),
+            delivered_quantity: Some(FieldUpdate::Set(1)),
+        }];
+        assert_eq!(db.update_items(update_requests, TEST_ACTOR).await.unwrap().len(), 1);
+        let second = db.get_item(tables_id, second_id).await.unwrap();
+        assert_eq!((second.quantity, second.delivered_quantity), (2, 1));
+
+        let deliveries = db.get_item_deliveries(tables_id, first_id).await.unwrap();
+        assert_eq!(deliveries.iter().map(|delivery| delivery.quantity).collect::<Vec<_>>(), vec![1, 1, 2]);
+
+        // Updates that would leave the counts out of bounds are refused once the
+        // items are locked, and nothing of the batch is applied.
+        let update_requests = vec![
+            UpdateItemRequest {
+                id: second_id,
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Increment(1)),
+            },
+            UpdateItemRequest {
+                id: first_id,
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Increment(2)),
+            },
+        ];
+        let Err(AppError::Validation { errors, .. }) = db.update_items(update_requests, TEST_ACTOR).await else {
+            panic!("Over-delivery should have been refused");
+        };
+        assert_eq!((errors[0].index, errors[0].field.as_str()), (1, "delivered_quantity"));
+        let update_request = UpdateItemRequest {
+            id: second_id,
+            quantity: Some(FieldUpdate::Increment(-2)),
+            delivered_quantity: Some(FieldUpdate::Increment(-2)),
+        };
+        assert!(matches!(db.update_item(second_id, update_request, TEST_ACTOR).await, Err(AppError::Validation { .. })));
+        let second = db.get_item(tables_id, second_id).await.unwrap();
+        assert_eq!((second.quantity, second.delivered_quantity), (2, 1));
+
+        let over_delivered = sqlx::query("UPDATE items SET delivered_quantity = quantity + 1 WHERE id = $1")
+            .bind(first_id)
+            .execute(&db.pool)
+            .await;
+        assert!(over_delivered.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_closing_the_day_reports_sales_once() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let new_items = vec![NewItemRequest { quantity: 2, menu_id: menu.id }, NewItemRequest { quantity: 1, menu_id: menu.id }];
+        let created = db.create_items(new_table.id, new_items, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let (item_id, void_id) = (created[0].id, created[1].id);
+        assert!(db.delete_item(new_table.id, void_id, TEST_ACTOR).await.unwrap());
+        db.update_table(new_table.id, UpdateTableRequest { name: None, covers: Some(3) }).await.unwrap();
+
+        let payment = NewPayment {
+            check_id: None,
+            tender: Tender::Cash,
+            amount: Decimal::new(3000, 2),
+            tip: Decimal::ZERO,
+            tendered: Decimal::new(3000, 2),
+            change: Decimal::ZERO,
+        };
+        db.add_payment(new_table.id, payment, Decimal::ZERO, Some(settlement(&[item_id])), TEST_ACTOR).await.unwrap();
+
+        let kept = KeptSales::default();
+        let report = db.close_day(&kept, TEST_ACTOR).await.unwrap();
+        let sales = kept.sales();
+        assert_eq!(sales.sessions.len(), 1);
+        assert_eq!(sales.sessions[0].covers, Some(3));
+        assert_eq!(sales.payments.len(), 1);
+        assert_eq!(sales.voids.iter().map(|item| item.item_id).collect::<Vec<_>>(), vec![void_id]);
+        assert_eq!((report.number, report.opened_at), (1, None));
+        assert_eq!(report.totals.paid, Decimal::new(3000, 2));
+        assert_eq!(report.totals.voids.value, Decimal::new(1500, 2));
+        assert_eq!(db.get_session(sales.sessions[0].id).await.unwrap().z_report_id, Some(report.id));
+        assert!(!db.restore_item(new_table.id, void_id, TEST_ACTOR).await.unwrap());
+
+        let next = db.close_day(&kept, TEST_ACTOR).await.unwrap();
+        let sales = kept.sales();
+        assert!(sales.sessions.is_empty() && sales.payments.is_empty() && sales.voids.is_empty());
+        assert_eq!((next.number, next.opened_at), (2, Some(report.closed_at)));
+        let numbers: Vec<i64> = db.get_z_reports().await.unwrap().iter().map(|report| report.number).collect();
+        assert_eq!(numbers, vec![2, 1]);
+        assert_eq!(db.get_z_report(1).await.unwrap().totals.paid, report.totals.paid);
+        assert!(matches!(db.get_z_report(3).await, Err(sqlx::Error::RowNotFound)));
+
+        make_ready(&db, new_table.id, &[item_id]).await;
+        db.update_items(
+            vec![UpdateItemRequest {
+                id: item_id,
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Set(2)),
+            }],
+            TEST_ACTOR,
+        ).await.unwrap();
+        assert_eq!(db.delete_table(new_table.id).await.unwrap(), DeleteTableOutcome::HasSales);
+    }
+
+    /// Tries to restore a void while the close is totalling it.
+    struct RestoringTotals {
+        pool: PgPool,
+        tables_id: Uuid,
+        item_id: Uuid,
+        restore: std::sync::Mutex<Option<tokio::task::JoinHandle<bool>>>,
+    }
+
+    #[async_trait]
+    impl DayTotals for RestoringTotals {
+        async fn totals(&self, sales: &UnreportedSales) -> Result<ZReportTotals, AppError> {
+            let db = Database { pool: self.pool.clone() };
+            let (tables_id, item_id) = (self.tables_id, self.item_id);
+            let restore = tokio::spawn(async move { db.restore_item(tables_id, item_id, TEST_ACTOR).await.unwrap() });
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            assert!(!restore.is_finished());
+            *self.restore.lock().unwrap() = Some(restore);
+            Ok(z_report_totals(&[], &sales.payments, &sales.voids))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_closing_the_day_holds_back_changes_to_what_it_reports() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool: pool.clone() };
+
+        let new_table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Dish".to_string(), Decimal::new(1500, 2), 15, None).await.expect("Failed to add menu item");
+        let created = db.create_item(new_table.id, NewItemRequest { quantity: 1, menu_id: menu.id }, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        assert!(db.delete_item(new_table.id, created.id, TEST_ACTOR).await.unwrap());
+
+        let totals = RestoringTotals {
+            pool,
+            tables_id: new_table.id,
+            item_id: created.id,
+            restore: std::sync::Mutex::new(None),
+        };
+        let report = db.close_day(&totals, TEST_ACTOR).await.unwrap();
+        assert_eq!(report.totals.voids.items, 1);
+
+        let restore = totals.restore.lock().unwrap().take().unwrap();
+        assert!(!restore.await.unwrap());
+        assert!(db.get_item(new_table.id, created.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_kitchen_queue_spans_tables() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let first_table = db.add_table("First Table".to_string()).await.expect("Failed to add table");
+        let second_table = db.add_table("Second Table".to_string()).await.expect("Failed to add table");
+        let slow = db.add_menu("Slow Dish".to_string(), Decimal::new(1500, 2), 30, None).await.expect("Failed to add menu item");
+        let quick = db.add_menu("Quick Dish".to_string(), Decimal::new(500, 2), 5, None).await.expect("Failed to add menu item");
+        db.create_items(first_table.id, vec![NewItemRequest { quantity: 1, menu_id: slow.id }], Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let created = db
+            .create_items(
+                second_table.id,
+                vec![NewItemRequest { quantity: 2, menu_id: quick.id }, NewItemRequest { quantity: 1, menu_id: slow.id }],
+                Utc::now().naive_utc(),
+                TEST_ACTOR,
+            )
+            .await
+            .unwrap();
+        assert!(db.delete_item(second_table.id, created[1].id, TEST_ACTOR).await.unwrap());
+
+        let queue = db.get_kitchen_queue(&FilterParams { menu_id: None }).await.unwrap();
+        let entries: Vec<(&str, &str)> = queue.iter().map(|item| (item.table_name.as_str(), item.menu_name.as_str())).collect();
+        assert_eq!(entries, vec![("Second Table", "Quick Dish"), ("First Table", "Slow Dish")]);
+        assert_eq!(queue[1].due_at - queue[1].created_at, chrono::Duration::minutes(30));
+
+        let queue = db.get_kitchen_queue(&FilterParams { menu_id: Some(slow.id) }).await.unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].tables_id, first_table.id);
+
+        // A table waits on what is cooking and on what is due before its own
+        // items, not on what comes after them.
+        let third_table = db.add_table("Third Table".to_string()).await.expect("Failed to add table");
+        let cooking = db.create_item(third_table.id, NewItemRequest { quantity: 1, menu_id: slow.id }, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        let started = db.update_item_status(third_table.id, cooking.id, ItemStatus::InPreparation, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap();
+        assert_eq!(started, ItemStatusOutcome::Updated(None));
+        let ahead = |queue: Vec<KitchenQueueItem>| queue.into_iter().map(|item| item.table_name).collect::<Vec<_>>();
+        assert_eq!(ahead(db.get_kitchen_queue_ahead_of(second_table.id).await.unwrap()), vec!["Second Table", "Third Table"]);
+        assert_eq!(ahead(db.get_kitchen_queue_ahead_of(first_table.id).await.unwrap()), vec!["Second Table", "First Table", "Third Table"]);
+    }
+
+    #[tokio::test]
+    async fn test_item_status_moves_forward_one_step_at_a_time() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let menu = db.add_menu("Test Dish".to_string(), Decimal::new(1000, 2), 10, None).await.expect("Failed to add menu item");
+        let created = db
+            .create_items(table.id, vec![NewItemRequest { quantity: 3, menu_id: menu.id }], Utc::now().naive_utc(), TEST_ACTOR)
+            .await
+            .unwrap();
+        let item_id = created[0].id;
+
+        let item = db.get_item(table.id, item_id).await.unwrap();
+        assert_eq!(item.status, ItemStatus::Ordered);
+        assert_eq!(
+            db.update_item_status(table.id, item_id, ItemStatus::Served, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap(),
+            ItemStatusOutcome::Illegal(ItemStatus::Ordered)
+        );
+        assert_eq!(
+            db.update_item_status(Uuid::new_v4(), item_id, ItemStatus::InPreparation, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap(),
+            ItemStatusOutcome::NotFound
+        );
+        let delivery = || vec![UpdateItemRequest { id: item_id, quantity: None, delivered_quantity: Some(FieldUpdate::Set(1)) }];
+        assert!(matches!(db.update_items(delivery(), TEST_ACTOR).await, Err(AppError::Conflict(_))));
+        assert_eq!(db.get_item(table.id, item_id).await.unwrap().delivered_quantity, 0);
+        assert!(db.get_item_deliveries(table.id, item_id).await.unwrap().is_empty());
+
+        for status in [ItemStatus::InPreparation, ItemStatus::Ready] {
+            assert_eq!(db.update_item_status(table.id, item_id, status, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap(), ItemStatusOutcome::Updated(None));
+        }
+        let item = db.get_item(table.id, item_id).await.unwrap();
+        assert_eq!(item.status, ItemStatus::Ready);
+        assert!(item.started_at.is_some() && item.ready_at.is_some() && item.served_at.is_none());
+        assert_eq!(
+            db.update_item_status(table.id, item_id, ItemStatus::InPreparation, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap(),
+            ItemStatusOutcome::Illegal(ItemStatus::Ready)
+        );
+
+        db.update_items(delivery(), TEST_ACTOR).await.unwrap();
+        assert_eq!(db.update_item_status(table.id, item_id, ItemStatus::Served, Utc::now().naive_utc(), TEST_ACTOR).await.unwrap(), ItemStatusOutcome::Updated(None));
+        let item = db.get_item(table.id, item_id).await.unwrap();
+        assert_eq!(item.status, ItemStatus::Served);
+        assert_eq!(item.delivered_quantity, 3);
+        assert!(item.served_at.is_some() && item.delivered_at.is_some());
+        let deliveries = db.get_item_deliveries(table.id, item_id).await.unwrap();
+        assert_eq!(deliveries.iter().map(|delivery| delivery.quantity).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(matches!(db.update_items(delivery(), TEST_ACTOR).await, Err(AppError::Conflict(_))));
+        assert_eq!(db.get_item(table.id, item_id).await.unwrap().status, ItemStatus::Served);
+    }
+
+    #[tokio::test]
+    async fn test_overdue_alerts_are_raised_once_and_resolved_on_delivery() {
+        let (_guard, pool) = setup_test_db().await;
+        let db = Database { pool };
+
+        let table = db.add_table("Test Table".to_string()).await.expect("Failed to add table");
+        let quick = db.add_menu("Quick Dish".to_string(), Decimal::new(500, 2), 5, None).await.expect("Failed to add menu item");
+        let slow = db.add_menu("Slow Dish".to_string(), Decimal::new(1500, 2), 5, None).await.expect("Failed to add menu item");
+        assert_eq!(db.set_alert_threshold(slow.id, 10).await.unwrap().grace_minutes, 10);
+        assert_eq!(db.set_alert_threshold(slow.id, 30).await.unwrap().grace_minutes, 30);
+        assert_eq!(db.get_alert_threshold(slow.id).await.unwrap().unwrap().grace_minutes, 30);
+
+        // Items are overdue by the time they were ordered at, not by when the database wrote them.
+        let now = NaiveDate::from_ymd_opt(2024, 9, 14).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        let created = db
+            .create_items(
+                table.id,
+                vec![NewItemRequest { quantity: 2, menu_id: quick.id }, NewItemRequest { quantity: 1, menu_id: slow.id }],
+                now,
+                TEST_ACTOR,
+            )
+            .await
+            .unwrap();
+
+        assert!(db.raise_overdue_alerts(now + chrono::Duration::minutes(6), 2).await.unwrap().raised.is_empty());
+        let raised = db.raise_overdue_alerts(now + chrono::Duration::minutes(10), 2).await.unwrap().raised;
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].items_id, created[0].id);
+        assert_eq!(raised[0].overdue_at, now + chrono::Duration::minutes(7));
+        assert_eq!(raised[0].table_name, "Test Table");
+        assert!(db.raise_overdue_alerts(now + chrono::Duration::minutes(11), 2).await.unwrap().raised.is_empty());
+
+        make_ready(&db, table.id, &[created[0].id]).await;
+        let updated = db
+            .update_items(vec![UpdateItemRequest { id: created[0].id, quantity: None, delivered_quantity: Some(FieldUpdate::Set(2)) }], TEST_ACTOR)
+            .await
+            .unwrap();
+        assert_eq!(updated[0].resolved_alert, Some(raised[0].id));
+        assert!(db.get_alerts(false).await.unwrap().is_empty());
+        let alerts = db.get_alerts(true).await.unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].resolved_at.is_some());
+        assert!(db.get_alerts_by_id(&[raised[0].id]).await.unwrap()[0].resolved_at.is_some());
+
+        assert!(db.delete_alert_threshold(slow.id).await.unwrap());
+        assert!(!db.delete_alert_threshold(slow.id).await.unwrap());
+        let raised = db.raise_overdue_alerts(now + chrono::Duration::minutes(10), 2).await.unwrap().raised;
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].items_id, created[1].id);
+        assert!(db.delete_item(table.id, created[1].id, TEST_ACTOR).await.unwrap());
+        let scan = db.raise_overdue_alerts(now + chrono::Duration::minutes(12), 2).await.unwrap();
+        assert!(scan.raised.is_empty());
+        assert_eq!(scan.resolved.iter().map(|alert| alert.id).collect::<Vec<_>>(), vec![raised[0].id]);
+        assert!(db.get_alerts(false).await.unwrap().is_empty());
+    }
+
 }
\ No newline at end of file
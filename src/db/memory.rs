@@ -0,0 +1,1579 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::Error;
+use uuid::Uuid;
+
+use crate::billing::pricing::price_at;
+use crate::db::connection::{delivery_refused, AddDiscountOutcome, AddPaymentOutcome, AddRefundOutcome, DeleteTableOutcome, ItemStatusOutcome, NewCheckRequest, NewDiscount, NewItemRequest, NewPayment, NewPriceRuleRequest, NewPromoCodeRequest, NewRefund, NewServiceChargeRuleRequest, NewTaxCategoryRequest, OverdueAlerts, RetireMenuOutcome, Settlement, UnreportedSales, UpdateItemRequest, UpdateMenuRequest, UpdateTableRequest, UpdateTaxCategoryRequest, UpdatedItem};
+use crate::db::repository::{AlertRepository, BillingRepository, CheckRepository, DayTotals, DeviceRepository, DiscountRepository, ItemRepository, KitchenRepository, MenuRepository, PaymentRepository, PriceRuleRepository, ReportRepository, TableRepository, TaxRepository};
+use crate::error::AppError;
+use crate::models::{restaurant_models::{AlertThreshold, BillItem, ChargedTax, Check, CheckItem, DeletedItem, Device, Discount, ItemAlert, ItemDelivery, ItemStatus, Items, KitchenQueueItem, PartialItem, PartialItemReturn, Payment, PriceRule, PromoCode, RefundableItem, ServiceChargeRule, Table, TableSession, TaxCategory, Menu, ZReport}, route_models::{FilterParams, Pagination, MAX_ITEMS_LIMIT}};
+use crate::validation::items::check_item_updates;
+
+/// An `item_alerts` row.
+struct AlertRow {
+    id: Uuid,
+    items_id: Uuid,
+    overdue_at: NaiveDateTime,
+    raised_at: NaiveDateTime,
+    resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    devices: Vec<Device>,
+    tables: Vec<Table>,
+    menu: Vec<Menu>,
+    items: Vec<Items>,
+    deliveries: Vec<ItemDelivery>,
+    checks: Vec<Check>,
+    check_items: Vec<CheckItem>,
+    sessions: Vec<TableSession>,
+    payments: Vec<Payment>,
+    tax_categories: Vec<TaxCategory>,
+    service_charges: Vec<ServiceChargeRule>,
+    discounts: Vec<Discount>,
+    promo_codes: Vec<PromoCode>,
+    price_rules: Vec<PriceRule>,
+    receipt_numbers: i64,
+    reported_payments: HashSet<Uuid>,
+    z_reports: Vec<ZReport>,
+    alerts: Vec<AlertRow>,
+    alert_thresholds: Vec<AlertThreshold>,
+}
+
+impl MemoryState {
+    fn prep_time(&self, menu_id: Uuid) -> i32 {
+        self.menu.iter().find(|menu| menu.id == menu_id).map_or(0, |menu| menu.prep_time)
+    }
+
+    /// Resolves the open alerts of the given items, or of any item, delivered
+    /// or deleted since. Returns the alerts resolved, by item id.
+    fn resolve_alerts(&mut self, item_ids: Option<&[Uuid]>, now: NaiveDateTime) -> HashMap<Uuid, Uuid> {
+        let items = &self.items;
+        let mut resolved = HashMap::new();
+        for alert in self.alerts.iter_mut().filter(|alert| alert.resolved_at.is_none()) {
+            if item_ids.is_some_and(|item_ids| !item_ids.contains(&alert.items_id)) {
+                continue;
+            }
+            let settled = items
+                .iter()
+                .find(|item| item.id == alert.items_id)
+                .is_none_or(|item| item.deleted_at.is_some() || item.delivered_quantity >= item.quantity);
+            if settled {
+                alert.resolved_at = Some(now);
+                resolved.insert(alert.items_id, alert.id);
+            }
+        }
+        resolved
+    }
+
+    fn item_alert(&self, alert: &AlertRow) -> Option<ItemAlert> {
+        let item = self.items.iter().find(|item| item.id == alert.items_id)?;
+        let table = self.tables.iter().find(|table| table.id == item.tables_id)?;
+        Some(ItemAlert {
+            id: alert.id,
+            items_id: item.id,
+            tables_id: item.tables_id,
+            table_name: table.name.clone(),
+            menu_id: item.menu_id,
+            menu_name: item.menu_name.clone(),
+            quantity: item.quantity,
+            delivered_quantity: item.delivered_quantity,
+            overdue_at: alert.overdue_at,
+            raised_at: alert.raised_at,
+            resolved_at: alert.resolved_at,
+        })
+    }
+
+    fn alerts_by_id(&self, alert_ids: &[Uuid]) -> Vec<ItemAlert> {
+        let mut alerts: Vec<ItemAlert> = self
+            .alerts
+            .iter()
+            .filter(|alert| alert_ids.contains(&alert.id))
+            .filter_map(|alert| self.item_alert(alert))
+            .collect();
+        alerts.sort_by_key(|alert| (alert.overdue_at, alert.id));
+        alerts
+    }
+
+    fn tax_category_id(&self, menu_id: Uuid) -> Option<Uuid> {
+        self.menu.iter().find(|menu| menu.id == menu_id).and_then(|menu| menu.tax_category_id)
+    }
+
+    fn price_at(&self, menu: &Menu, ordered_at: NaiveDateTime) -> Decimal {
+        let rules: Vec<PriceRule> = self.price_rules.iter().filter(|rule| rule.menu_id == menu.id).cloned().collect();
+        price_at(menu.price, &rules, ordered_at)
+    }
+
+    /// The matching items as they are charged, in the order they were created.
+    /// Paid items stay in the tax category they were settled in.
+    fn bill_items(&self, include: impl Fn(&Items) -> bool) -> Vec<BillItem> {
+        let mut items: Vec<&Items> = self.items.iter().filter(|item| include(item)).collect();
+        items.sort_by_key(|item| (item.created_at, item.id));
+
+        items
+            .into_iter()
+            .map(|item| BillItem {
+                item_id: item.id,
+                menu_id: item.menu_id,
+                name: item.menu_name.clone(),
+                unit_price: item.unit_price,
+                quantity: item.quantity,
+                tax_category_id: match item.paid_at {
+                    Some(_) => item.tax_category_id,
+                    None => self.tax_category_id(item.menu_id),
+                },
+            })
+            .collect()
+    }
+
+    fn item_return(&self, item: &Items) -> PartialItemReturn {
+        PartialItemReturn {
+            id: item.id,
+            tables_id: item.tables_id,
+            menu_id: item.menu_id,
+            menu_name: item.menu_name.clone(),
+            unit_price: item.unit_price,
+            quantity: item.quantity,
+            delivered_quantity: item.delivered_quantity,
+            delivered_at: item.delivered_at,
+            paid_at: item.paid_at,
+            refunded_quantity: item.refunded_quantity,
+            created_at: item.created_at,
+            prep_time: self.prep_time(item.menu_id),
+            status: item.status,
+            started_at: item.started_at,
+            ready_at: item.ready_at,
+            served_at: item.served_at,
+        }
+    }
+
+    fn open_checks(&self, tables_id: Uuid) -> impl Iterator<Item = &Check> + '_ {
+        self.checks
+            .iter()
+            .filter(move |check| check.tables_id == tables_id && check.session_id.is_none())
+    }
+
+    /// Removes the checks of the current session, detaching any payments made on them.
+    fn remove_checks(&mut self, tables_id: Uuid) -> u64 {
+        let check_ids: HashSet<Uuid> = self.open_checks(tables_id).map(|check| check.id).collect();
+        self.check_items.retain(|share| !check_ids.contains(&share.check_id));
+        self.checks.retain(|check| !check_ids.contains(&check.id));
+        for payment in self.payments.iter_mut() {
+            if payment.check_id.is_some_and(|check_id| check_ids.contains(&check_id)) {
+                payment.check_id = None;
+            }
+        }
+        check_ids.len() as u64
+    }
+
+    fn remaining_items<'a>(&'a self, tables_id: Uuid, filters: &'a FilterParams) -> impl Iterator<Item = &'a Items> + 'a {
+        self.items.iter().filter(move |item| {
+            item.tables_id == tables_id
+                && filters.menu_id.is_none_or(|menu_id| item.menu_id == menu_id)
+                && item.quantity > item.delivered_quantity
+                && item.deleted_at.is_none()
+        })
+    }
+}
+
+/// Repository kept entirely in memory, so handlers and business rules can be
+/// tested without a database.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    state: Mutex<MemoryState>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_device(&self, name: &str) -> Device {
+        let device = Device {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+        };
+        self.state().devices.push(device.clone());
+        device
+    }
+
+    fn state(&self) -> MutexGuard<'_, MemoryState> {
+        self.state.lock().expect("In-memory repository lock poisoned")
+    }
+
+    fn now() -> NaiveDateTime {
+        Utc::now().naive_utc()
+    }
+}
+
+#[async_trait]
+impl DeviceRepository for InMemoryRepository {
+    async fn get_device(&self, device_id: Uuid) -> Result<Device, Error> {
+        self.state()
+            .devices
+            .iter()
+            .find(|device| device.id == device_id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+}
+
+#[async_trait]
+impl TableRepository for InMemoryRepository {
+    async fn get_tables(&self) -> Result<Vec<Table>, Error> {
+        Ok(self.state().tables.clone())
+    }
+
+    async fn add_table(&self, name: String) -> Result<Table, Error> {
+        let table = Table {
+            id: Uuid::new_v4(),
+            name,
+            covers: None,
+        };
+        self.state().tables.push(table.clone());
+        Ok(table)
+    }
+
+    async fn get_table(&self, tables_id: Uuid) -> Result<Table, Error> {
+        self.state()
+            .tables
+            .iter()
+            .find(|table| table.id == tables_id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn update_table(&self, tables_id: Uuid, updated_table: UpdateTableRequest) -> Result<Option<Table>, Error> {
+        let mut state = self.state();
+        let table = state.tables.iter_mut().find(|table| table.id == tables_id);
+        Ok(table.map(|table| {
+            if let Some(name) = updated_table.name {
+                table.name = name;
+            }
+            table.covers = updated_table.covers.or(table.covers);
+            table.clone()
+        }))
+    }
+
+    async fn delete_table(&self, tables_id: Uuid) -> Result<DeleteTableOutcome, Error> {
+        let mut state = self.state();
+        if !state.tables.iter().any(|table| table.id == tables_id) {
+            return Ok(DeleteTableOutcome::NotFound);
+        }
+
+        let pending = state
+            .remaining_items(tables_id, &FilterParams { menu_id: None })
+            .count() as i64;
+        if pending > 0 {
+            return Ok(DeleteTableOutcome::PendingItems(pending));
+        }
+
+        let has_sales = state.sessions.iter().any(|session| session.tables_id == tables_id)
+            || state.items.iter().any(|item| {
+                item.tables_id == tables_id && (item.session_id.is_some() || item.deleted_at.is_some())
+            });
+        if has_sales {
+            return Ok(DeleteTableOutcome::HasSales);
+        }
+
+        let item_ids: HashSet<Uuid> = state
+            .items
+            .iter()
+            .filter(|item| item.tables_id == tables_id)
+            .map(|item| item.id)
+            .collect();
+        state.deliveries.retain(|delivery| !item_ids.contains(&delivery.items_id));
+        let check_ids: HashSet<Uuid> = state
+            .checks
+            .iter()
+            .filter(|check| check.tables_id == tables_id)
+            .map(|check| check.id)
+            .collect();
+        state.check_items.retain(|share| !check_ids.contains(&share.check_id));
+        state.checks.retain(|check| check.tables_id != tables_id);
+        // Promo codes redeemed on the open bill can be used again.
+        let redeemed: Vec<Uuid> = state
+            .discounts
+            .iter()
+            .filter(|discount| discount.tables_id == tables_id)
+            .filter_map(|discount| discount.promo_code_id)
+            .collect();
+        for promo_code_id in redeemed {
+            if let Some(promo_code) = state.promo_codes.iter_mut().find(|promo_code| promo_code.id == promo_code_id) {
+                promo_code.uses = (promo_code.uses - 1).max(0);
+            }
+        }
+        state.discounts.retain(|discount| discount.tables_id != tables_id);
+        let items: Vec<PartialItemReturn> = state
+            .items
+            .iter()
+            .filter(|item| item.tables_id == tables_id)
+            .map(|item| state.item_return(item))
+            .collect();
+        state.items.retain(|item| item.tables_id != tables_id);
+        state.tables.retain(|table| table.id != tables_id);
+
+        Ok(DeleteTableOutcome::Deleted(items))
+    }
+}
+
+#[async_trait]
+impl MenuRepository for InMemoryRepository {
+    async fn get_menu(&self, include_retired: bool) -> Result<Vec<Menu>, Error> {
+        let mut menu: Vec<Menu> = self
+            .state()
+            .menu
+            .iter()
+            .filter(|menu| include_retired || menu.retired_at.is_none())
+            .cloned()
+            .collect();
+        menu.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(menu)
+    }
+
+    async fn get_menu_item(&self, menu_id: Uuid) -> Result<Menu, Error> {
+        self.state()
+            .menu
+            .iter()
+            .find(|menu| menu.id == menu_id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn add_menu(&self, name: String, price: Decimal, prep_time: i32, tax_category_id: Option<Uuid>) -> Result<Menu, Error> {
+        let menu = Menu {
+            id: Uuid::new_v4(),
+            name,
+            price,
+            prep_time,
+            retired_at: None,
+            tax_category_id,
+        };
+        self.state().menu.push(menu.clone());
+        Ok(menu)
+    }
+
+    async fn update_menu(&self, menu_id: Uuid, updated_menu: UpdateMenuRequest) -> Result<Option<Menu>, Error> {
+        let mut state = self.state();
+        let menu = state
+            .menu
+            .iter_mut()
+            .find(|menu| menu.id == menu_id && menu.retired_at.is_none());
+        Ok(menu.map(|menu| {
+            menu.price = updated_menu.price.unwrap_or(menu.price);
+            menu.prep_time = updated_menu.prep_time.unwrap_or(menu.prep_time);
+            if let Some(update) = updated_menu.tax_category_id {
+                menu.tax_category_id = update.apply();
+            }
+            menu.clone()
+        }))
+    }
+
+    async fn retire_menu(&self, menu_id: Uuid) -> Result<RetireMenuOutcome, Error> {
+        let mut state = self.state();
+        if !state.menu.iter().any(|menu| menu.id == menu_id) {
+            return Ok(RetireMenuOutcome::NotFound);
+        }
+
+        if state.items.iter().any(|item| item.menu_id == menu_id) {
+            let now = Self::now();
+            for menu in state.menu.iter_mut().filter(|menu| menu.id == menu_id) {
+                menu.retired_at.get_or_insert(now);
+            }
+            Ok(RetireMenuOutcome::Retired)
+        } else {
+            state.menu.retain(|menu| menu.id != menu_id);
+            state.price_rules.retain(|rule| rule.menu_id != menu_id);
+            Ok(RetireMenuOutcome::Deleted)
+        }
+    }
+
+    async fn get_orderable_menu_ids(&self, menu_ids: &[Uuid]) -> Result<HashSet<Uuid>, Error> {
+        Ok(self
+            .state()
+            .menu
+            .iter()
+            .filter(|menu| menu.retired_at.is_none() && menu_ids.contains(&menu.id))
+            .map(|menu| menu.id)
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ItemRepository for InMemoryRepository {
+    async fn get_all_remaining_items_from_table(
+        &self,
+        tables_id: Uuid,
+        pagination: Pagination,
+        filters: FilterParams,
+    ) -> Result<Vec<PartialItemReturn>, Error> {
+        let state = self.state();
+        let mut items: Vec<&Items> = state.remaining_items(tables_id, &filters).collect();
+        items.sort_by_key(|item| (item.created_at, item.id));
+
+        Ok(items
+            .into_iter()
+            .skip(pagination.offset())
+            .take(pagination.limit())
+            .map(|item| state.item_return(item))
+            .collect())
+    }
+
+    async fn count_remaining_items_from_table(&self, tables_id: Uuid, filters: &FilterParams) -> Result<i64, Error> {
+        Ok(self.state().remaining_items(tables_id, filters).count() as i64)
+    }
+
+    async fn get_paid_item_ids(&self, item_ids: &[Uuid]) -> Result<HashSet<Uuid>, Error> {
+        Ok(self
+            .state()
+            .items
+            .iter()
+            .filter(|item| item.paid_at.is_some() && item_ids.contains(&item.id))
+            .map(|item| item.id)
+            .collect())
+    }
+
+    async fn get_item_quantities(&self, item_ids: &[Uuid]) -> Result<HashMap<Uuid, (i32, i32)>, Error> {
+        Ok(self
+            .state()
+            .items
+            .iter()
+            .filter(|item| item.deleted_at.is_none() && item_ids.contains(&item.id))
+            .map(|item| (item.id, (item.quantity, item.delivered_quantity)))
+            .collect())
+    }
+
+    async fn create_items(
+        &self,
+        tables_id: Uuid,
+        new_items: Vec<NewItemRequest>,
+        ordered_at: NaiveDateTime,
+        actor: &str,
+    ) -> Result<Vec<PartialItem>, AppError> {
+        if new_items.len() > MAX_ITEMS_LIMIT {
+            return Err(AppError::LimitExceeded { limit: MAX_ITEMS_LIMIT });
+        }
+
+        let mut state = self.state();
+        if !state.tables.iter().any(|table| table.id == tables_id) {
+            return Err(AppError::Conflict(format!("Table with id {} does not exist", tables_id)));
+        }
+
+        let menus = new_items
+            .iter()
+            .map(|new_item| {
+                state
+                    .menu
+                    .iter()
+                    .find(|menu| menu.id == new_item.menu_id)
+                    .cloned()
+                    .ok_or_else(|| AppError::Conflict(format!("Menu item {} does not exist", new_item.menu_id)))
+            })
+            .collect::<Result<Vec<Menu>, AppError>>()?;
+
+        let mut created_items = Vec::with_capacity(new_items.len());
+        for (new_item, menu) in new_items.into_iter().zip(menus) {
+            let unit_price = state.price_at(&menu, ordered_at);
+            let item = Items {
+                id: Uuid::new_v4(),
+                tables_id,
+                menu_id: new_item.menu_id,
+                menu_name: menu.name,
+                unit_price,
+                quantity: new_item.quantity,
+                delivered_quantity: 0,
+                delivered_at: None,
+                created_at: ordered_at,
+                created_by: Some(actor.to_string()),
+                updated_at: ordered_at,
+                updated_by: Some(actor.to_string()),
+                deleted_at: None,
+                deleted_by: None,
+                session_id: None,
+                paid_at: None,
+                refunded_quantity: 0,
+                refunded_at: None,
+                refunded_by: None,
+                z_report_id: None,
+                status: ItemStatus::Ordered,
+                started_at: None,
+                ready_at: None,
+                served_at: None,
+                tax_category_id: None,
+                tax_name: None,
+                tax_rate: None,
+                tax_inclusive: None,
+            };
+            created_items.push(PartialItem {
+                id: item.id,
+                tables_id,
+                menu_id: item.menu_id,
+                menu_name: item.menu_name.clone(),
+                unit_price: item.unit_price,
+                quantity: item.quantity,
+                delivered_quantity: 0,
+            });
+            state.items.push(item);
+        }
+
+        Ok(created_items)
+    }
+
+    async fn create_item(
+        &self,
+        tables_id: Uuid,
+        new_item: NewItemRequest,
+        ordered_at: NaiveDateTime,
+        actor: &str,
+    ) -> Result<PartialItem, AppError> {
+        let mut created = self.create_items(tables_id, vec![new_item], ordered_at, actor).await?;
+        Ok(created.remove(0))
+    }
+
+    async fn get_item(&self, tables_id: Uuid, item_id: Uuid) -> Result<PartialItemReturn, Error> {
+        let state = self.state();
+        state
+            .items
+            .iter()
+            .find(|item| item.tables_id == tables_id && item.id == item_id && item.deleted_at.is_none())
+            .map(|item| state.item_return(item))
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_items(&self, item_ids: &[Uuid]) -> Result<Vec<PartialItemReturn>, Error> {
+        let state = self.state();
+        let mut items: Vec<&Items> = state
+            .items
+            .iter()
+            .filter(|item| item_ids.contains(&item.id) && item.deleted_at.is_none())
+            .collect();
+        items.sort_by_key(|item| (item.created_at, item.id));
+        Ok(items.into_iter().map(|item| state.item_return(item)).collect())
+    }
+
+    async fn delete_item(&self, tables_id: Uuid, item_id: Uuid, actor: &str) -> Result<bool, Error> {
+        let now = Self::now();
+        let mut state = self.state();
+        let item = state
+            .items
+            .iter_mut()
+            .find(|item| item.tables_id == tables_id && item.id == item_id && item.deleted_at.is_none() && item.paid_at.is_none());
+        Ok(item.is_some_and(|item| {
+            item.deleted_at = Some(now);
+            item.deleted_by = Some(actor.to_string());
+            item.updated_at = now;
+            item.updated_by = Some(actor.to_string());
+            true
+        }))
+    }
+
+    async fn restore_item(&self, tables_id: Uuid, item_id: Uuid, actor: &str) -> Result<bool, Error> {
+        let now = Self::now();
+        let mut state = self.state();
+        let item = state
+            .items
+            .iter_mut()
+            .find(|item| {
+                item.tables_id == tables_id
+                    && item.id == item_id
+                    && item.deleted_at.is_some()
+                    && item.session_id.is_none()
+                    && item.z_report_id.is_none()
+            });
+        Ok(item.is_some_and(|item| {
+            item.deleted_at = None;
+            item.deleted_by = None;
+            item.updated_at = now;
+            item.updated_by = Some(actor.to_string());
+            true
+        }))
+    }
+
+    async fn get_deleted_items(&self, from: Option<NaiveDateTime>, to: Option<NaiveDateTime>) -> Result<Vec<DeletedItem>, Error> {
+        let now = Self::now();
+        let from = from.unwrap_or(now - Duration::days(1));
+        let to = to.unwrap_or(now);
+
+        let mut items: Vec<DeletedItem> = self
+            .state()
+            .items
+            .iter()
+            .filter_map(|item| {
+                let deleted_at = item.deleted_at.filter(|deleted_at| *deleted_at >= from && *deleted_at <= to)?;
+                Some(DeletedItem {
+                    id: item.id,
+                    tables_id: item.tables_id,
+                    menu_id: item.menu_id,
+                    quantity: item.quantity,
+                    delivered_quantity: item.delivered_quantity,
+                    deleted_at,
+                    deleted_by: item.deleted_by.clone(),
+                })
+            })
+            .collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+        Ok(items)
+    }
+
+    async fn update_item(&self, item_id: Uuid, updated_item: UpdateItemRequest, actor: &str) -> Result<bool, AppError> {
+        let updated_item = UpdateItemRequest {
+            id: item_id,
+            ..updated_item
+        };
+        let updated = self.update_items(vec![updated_item], actor).await?;
+
+        Ok(!updated.is_empty())
+    }
+
+    async fn update_items(&self, items: Vec<UpdateItemRequest>, actor: &str) -> Result<Vec<UpdatedItem>, AppError> {
+        if items.is_empty() {
+            return Ok(vec![]);
+        } else if items.len() > MAX_ITEMS_LIMIT {
+            return Err(AppError::LimitExceeded { limit: MAX_ITEMS_LIMIT });
+        }
+
+        let now = Self::now();
+        let mut state = self.state();
+        let current: HashMap<Uuid, (i32, i32)> = state
+            .items
+            .iter()
+            .filter(|item| item.deleted_at.is_none() && items.iter().any(|update| update.id == item.id))
+            .map(|item| (item.id, (item.quantity, item.delivered_quantity)))
+            .collect();
+        check_item_updates(&items, &current)?;
+
+        let mut seen = HashSet::new();
+        let mut changes = vec![];
+
+        for update in items {
+            // Like the SQL `CASE`, only the first update for an item applies.
+            if !seen.insert(update.id) {
+                continue;
+            }
+            let Some(index) = state
+                .items
+                .iter()
+                .position(|item| item.id == update.id && item.deleted_at.is_none())
+            else {
+                continue;
+            };
+
+            // Every update is checked before any is applied, like the rolled back transaction.
+            let item = &state.items[index];
+            let quantity = update.quantity.map_or(item.quantity, |field| field.apply(item.quantity));
+            let delivered_quantity = update
+                .delivered_quantity
+                .map_or(item.delivered_quantity, |field| field.apply(item.delivered_quantity));
+            let status = item
+                .status
+                .after_delivery((item.quantity, item.delivered_quantity), (quantity, delivered_quantity))
+                .map_err(|status| delivery_refused(item.id, status))?;
+            changes.push((index, quantity, delivered_quantity, status));
+        }
+
+        let mut updated = vec![];
+        let mut deliveries = vec![];
+        for (index, quantity, delivered_quantity, status) in changes {
+            let item = &mut state.items[index];
+            let previous_delivered_quantity = item.delivered_quantity;
+            item.quantity = quantity;
+            item.delivered_quantity = delivered_quantity;
+            item.updated_at = now;
+            item.updated_by = Some(actor.to_string());
+            item.delivered_at = if delivered_quantity >= quantity { item.delivered_at.or(Some(now)) } else { None };
+            if status != item.status {
+                item.status = status;
+                item.served_at = Some(now);
+            }
+
+            let delivered = item.delivered_quantity - previous_delivered_quantity;
+            updated.push(UpdatedItem {
+                id: item.id,
+                delivered: delivered != 0,
+                resolved_alert: None,
+            });
+            if delivered != 0 {
+                deliveries.push(ItemDelivery {
+                    id: Uuid::new_v4(),
+                    items_id: item.id,
+                    quantity: delivered,
+                    delivered_at: now,
+                    delivered_by: Some(actor.to_string()),
+                });
+            }
+        }
+
+        state.deliveries.extend(deliveries);
+        let item_ids: Vec<Uuid> = updated.iter().map(|updated| updated.id).collect();
+        let resolved_alerts = state.resolve_alerts(Some(&item_ids), now);
+        for updated in &mut updated {
+            updated.resolved_alert = resolved_alerts.get(&updated.id).copied();
+        }
+
+        Ok(updated)
+    }
+
+    async fn get_item_deliveries(&self, tables_id: Uuid, item_id: Uuid) -> Result<Vec<ItemDelivery>, Error> {
+        let state = self.state();
+        let belongs_to_table = state
+            .items
+            .iter()
+            .any(|item| item.id == item_id && item.tables_id == tables_id);
+        if !belongs_to_table {
+            return Ok(vec![]);
+        }
+
+        Ok(state
+            .deliveries
+            .iter()
+            .filter(|delivery| delivery.items_id == item_id)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl BillingRepository for InMemoryRepository {
+    async fn get_bill_items(&self, tables_id: Uuid) -> Result<Vec<BillItem>, Error> {
+        Ok(self.state().bill_items(|item| {
+            item.tables_id == tables_id && item.deleted_at.is_none() && item.session_id.is_none()
+        }))
+    }
+
+    async fn get_settled_bill_items(&self, session_id: Uuid) -> Result<Vec<BillItem>, Error> {
+        Ok(self
+            .state()
+            .bill_items(|item| item.session_id == Some(session_id) && item.paid_at.is_some()))
+    }
+
+    async fn get_settled_taxes(&self, session_id: Uuid) -> Result<Vec<ChargedTax>, Error> {
+        let state = self.state();
+        let mut taxes: Vec<ChargedTax> = Vec::new();
+        for item in state.items.iter().filter(|item| item.session_id == Some(session_id) && item.paid_at.is_some()) {
+            let (Some(id), Some(name), Some(rate), Some(inclusive)) =
+                (item.tax_category_id, &item.tax_name, item.tax_rate, item.tax_inclusive)
+            else {
+                continue;
+            };
+            if !taxes.iter().any(|tax| tax.id == id) {
+                taxes.push(ChargedTax {
+                    id,
+                    name: name.clone(),
+                    rate,
+                    inclusive,
+                });
+            }
+        }
+        taxes.sort_by_key(|tax| tax.id);
+        Ok(taxes)
+    }
+}
+
+#[async_trait]
+impl CheckRepository for InMemoryRepository {
+    async fn get_checks(&self, tables_id: Uuid) -> Result<Vec<Check>, Error> {
+        let mut checks: Vec<Check> = self.state().open_checks(tables_id).cloned().collect();
+        checks.sort_by_key(|check| check.position);
+        Ok(checks)
+    }
+
+    async fn get_check_items(&self, tables_id: Uuid) -> Result<Vec<CheckItem>, Error> {
+        let state = self.state();
+        let check_ids: HashSet<Uuid> = state.open_checks(tables_id).map(|check| check.id).collect();
+        Ok(state
+            .check_items
+            .iter()
+            .filter(|share| check_ids.contains(&share.check_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn replace_checks(&self, tables_id: Uuid, checks: Vec<NewCheckRequest>, actor: &str) -> Result<Vec<Check>, Error> {
+        let mut state = self.state();
+        if !state.tables.iter().any(|table| table.id == tables_id) {
+            return Err(Error::RowNotFound);
+        }
+
+        state.remove_checks(tables_id);
+
+        let now = Self::now();
+        let mut created = Vec::with_capacity(checks.len());
+        for (position, new_check) in checks.into_iter().enumerate() {
+            let check = Check {
+                id: Uuid::new_v4(),
+                tables_id,
+                name: new_check.name.trim().to_string(),
+                position: position as i32,
+                created_at: now,
+                created_by: Some(actor.to_string()),
+                session_id: None,
+            };
+            for share in new_check.items {
+                state.check_items.push(CheckItem {
+                    check_id: check.id,
+                    items_id: share.item_id,
+                    share_numerator: share.numerator,
+                    share_denominator: share.denominator,
+                });
+            }
+            state.checks.push(check.clone());
+            created.push(check);
+        }
+
+        Ok(created)
+    }
+
+    async fn delete_checks(&self, tables_id: Uuid) -> Result<u64, Error> {
+        Ok(self.state().remove_checks(tables_id))
+    }
+}
+
+#[async_trait]
+impl PaymentRepository for InMemoryRepository {
+    async fn get_open_session(&self, tables_id: Uuid) -> Result<Option<TableSession>, Error> {
+        Ok(self
+            .state()
+            .sessions
+            .iter()
+            .find(|session| session.tables_id == tables_id && session.closed_at.is_none())
+            .cloned())
+    }
+
+    async fn get_payments(&self, session_id: Uuid) -> Result<Vec<Payment>, Error> {
+        Ok(self
+            .state()
+            .payments
+            .iter()
+            .filter(|payment| payment.session_id == session_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn add_payment(
+        &self,
+        tables_id: Uuid,
+        payment: NewPayment,
+        paid_before: Decimal,
+        settlement: Option<Settlement>,
+        actor: &str,
+    ) -> Result<AddPaymentOutcome, Error> {
+        let now = Self::now();
+        let mut state = self.state();
+        if !state.tables.iter().any(|table| table.id == tables_id) {
+            return Err(Error::RowNotFound);
+        }
+
+        let open_session = state
+            .sessions
+            .iter()
+            .find(|session| session.tables_id == tables_id && session.closed_at.is_none())
+            .map(|session| session.id);
+        let paid: Decimal = state
+            .payments
+            .iter()
+            .filter(|payment| Some(payment.session_id) == open_session)
+            .map(|payment| payment.amount)
+            .sum();
+        if paid != paid_before {
+            return Ok(AddPaymentOutcome::Stale);
+        }
+
+        let session_id = open_session.unwrap_or_else(|| {
+            state.receipt_numbers += 1;
+            let session = TableSession {
+                id: Uuid::new_v4(),
+                tables_id,
+                opened_at: now,
+                closed_at: None,
+                closed_by: None,
+                receipt_number: state.receipt_numbers,
+                covers: None,
+                z_report_id: None,
+                tax_rate: None,
+                service_charge_rule_id: None,
+                service_charge_name: None,
+                service_charge_rate: None,
+            };
+            state.sessions.push(session.clone());
+            session.id
+        });
+
+        let recorded = Payment {
+            id: Uuid::new_v4(),
+            session_id,
+            check_id: payment.check_id,
+            tender: payment.tender,
+            amount: payment.amount,
+            tip: payment.tip,
+            tendered: payment.tendered,
+            change: payment.change,
+            refund_of: None,
+            reason: None,
+            approved_by: None,
+            created_at: now,
+            created_by: Some(actor.to_string()),
+        };
+        state.payments.push(recorded.clone());
+
+        let closed = settlement.is_some();
+        if let Some(settlement) = settlement {
+            for item in state.items.iter_mut() {
+                if item.tables_id != tables_id || item.session_id.is_some() {
+                    continue;
+                }
+                let settled = settlement.items.iter().find(|settled| settled.item_id == item.id);
+                if settled.is_none() && item.deleted_at.is_none() {
+                    continue;
+                }
+                item.session_id = Some(session_id);
+                item.paid_at = item.deleted_at.is_none().then_some(now);
+                if let Some(category) = settled.and_then(|settled| settled.tax_category.as_ref()) {
+                    item.tax_category_id = Some(category.id);
+                    item.tax_name = Some(category.name.clone());
+                    item.tax_rate = Some(category.rate);
+                    item.tax_inclusive = Some(category.inclusive);
+                }
+            }
+            for check in state.checks.iter_mut() {
+                if check.tables_id == tables_id && check.session_id.is_none() {
+                    check.session_id = Some(session_id);
+                }
+            }
+            for discount in state.discounts.iter_mut() {
+                if discount.tables_id == tables_id && discount.session_id.is_none() {
+                    discount.session_id = Some(session_id);
+                }
+            }
+            let covers = state.tables.iter().find(|table| table.id == tables_id).and_then(|table| table.covers);
+            if let Some(session) = state.sessions.iter_mut().find(|session| session.id == session_id) {
+                session.closed_at = Some(now);
+                session.closed_by = Some(actor.to_string());
+                session.covers = covers;
+                session.tax_rate = Some(settlement.tax_rate);
+                session.service_charge_rule_id = settlement.service_charge.as_ref().map(|rule| rule.id);
+                session.service_charge_name = settlement.service_charge.as_ref().map(|rule| rule.name.clone());
+                session.service_charge_rate = settlement.service_charge.as_ref().map(|rule| rule.rate);
+            }
+        }
+
+        Ok(AddPaymentOutcome::Recorded { payment: recorded, closed })
+    }
+
+    async fn get_payment(&self, payment_id: Uuid) -> Result<Payment, Error> {
+        self.state()
+            .payments
+            .iter()
+            .find(|payment| payment.id == payment_id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_session(&self, session_id: Uuid) -> Result<TableSession, Error> {
+        self.state()
+            .sessions
+            .iter()
+            .find(|session| session.id == session_id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_last_session(&self, tables_id: Uuid) -> Result<Option<TableSession>, Error> {
+        Ok(self
+            .state()
+            .sessions
+            .iter()
+            .filter(|session| session.tables_id == tables_id)
+            .max_by_key(|session| session.receipt_number)
+            .cloned())
+    }
+
+    async fn get_refundable_items(&self, session_id: Uuid) -> Result<Vec<RefundableItem>, Error> {
+        Ok(self
+            .state()
+            .items
+            .iter()
+            .filter(|item| item.session_id == Some(session_id) && item.paid_at.is_some())
+            .map(|item| RefundableItem {
+                item_id: item.id,
+                quantity: item.quantity,
+                refunded_quantity: item.refunded_quantity,
+            })
+            .collect())
+    }
+
+    async fn add_refund(
+        &self,
+        payment_id: Uuid,
+        refund: NewRefund,
+        refunded_before: Decimal,
+        actor: &str,
+    ) -> Result<AddRefundOutcome, Error> {
+        let now = Self::now();
+        let mut state = self.state();
+        let original = state
+            .payments
+            .iter()
+            .find(|payment| payment.id == payment_id)
+            .cloned()
+            .ok_or(Error::RowNotFound)?;
+
+        let refunded: Decimal = state
+            .payments
+            .iter()
+            .filter(|payment| payment.refund_of == Some(payment_id))
+            .map(|payment| -(payment.amount + payment.tip))
+            .sum();
+        if refunded != refunded_before {
+            return Ok(AddRefundOutcome::Stale);
+        }
+
+        let refundable = refund.items.iter().all(|refunded_item| {
+            state.items.iter().any(|item| {
+                item.id == refunded_item.item_id
+                    && item.session_id == Some(original.session_id)
+                    && item.paid_at.is_some()
+                    && item.refunded_quantity + refunded_item.quantity <= item.quantity
+            })
+        });
+        if !refundable {
+            return Ok(AddRefundOutcome::Stale);
+        }
+        for refunded_item in &refund.items {
+            if let Some(item) = state.items.iter_mut().find(|item| item.id == refunded_item.item_id) {
+                item.refunded_quantity += refunded_item.quantity;
+                item.refunded_at = Some(now);
+                item.refunded_by = Some(actor.to_string());
+            }
+        }
+
+        let recorded = Payment {
+            id: Uuid::new_v4(),
+            session_id: original.session_id,
+            check_id: original.check_id,
+            tender: original.tender,
+            amount: -refund.amount,
+            tip: -refund.tip,
+            tendered: -(refund.amount + refund.tip),
+            change: Decimal::ZERO,
+            refund_of: Some(payment_id),
+            reason: Some(refund.reason),
+            approved_by: Some(refund.approved_by),
+            created_at: now,
+            created_by: Some(actor.to_string()),
+        };
+        state.payments.push(recorded.clone());
+
+        Ok(AddRefundOutcome::Recorded(recorded))
+    }
+}
+
+#[async_trait]
+impl ReportRepository for InMemoryRepository {
+    async fn get_payments_between(&self, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<Payment>, Error> {
+        Ok(self
+            .state()
+            .payments
+            .iter()
+            .filter(|payment| payment.created_at >= from && payment.created_at < to)
+            .cloned()
+            .collect())
+    }
+    async fn close_day(&self, totals: &dyn DayTotals, actor: &str) -> Result<ZReport, AppError> {
+        let sales = {
+            let state = self.state();
+            let mut sessions: Vec<TableSession> = state
+                .sessions
+                .iter()
+                .filter(|session| session.closed_at.is_some() && session.z_report_id.is_none())
+                .cloned()
+                .collect();
+            sessions.sort_by_key(|session| (session.closed_at, session.receipt_number));
+            UnreportedSales {
+                sessions,
+                payments: state
+                    .payments
+                    .iter()
+                    .filter(|payment| !state.reported_payments.contains(&payment.id))
+                    .cloned()
+                    .collect(),
+                voids: state.bill_items(|item| item.deleted_at.is_some() && item.z_report_id.is_none()),
+            }
+        };
+        let totals = totals.totals(&sales).await?;
+
+        let now = Self::now();
+        let mut state = self.state();
+        let report = ZReport {
+            id: Uuid::new_v4(),
+            number: state.z_reports.len() as i64 + 1,
+            opened_at: state.z_reports.last().map(|report| report.closed_at),
+            closed_at: now,
+            closed_by: Some(actor.to_string()),
+            totals,
+        };
+        for session in state.sessions.iter_mut() {
+            if sales.sessions.iter().any(|reported| reported.id == session.id) {
+                session.z_report_id = Some(report.id);
+            }
+        }
+        for item in state.items.iter_mut() {
+            if sales.voids.iter().any(|void| void.item_id == item.id) {
+                item.z_report_id = Some(report.id);
+            }
+        }
+        state.reported_payments.extend(sales.payments.iter().map(|payment| payment.id));
+        state.z_reports.push(report.clone());
+
+        Ok(report)
+    }
+
+    async fn get_z_reports(&self) -> Result<Vec<ZReport>, Error> {
+        Ok(self.state().z_reports.iter().rev().cloned().collect())
+    }
+
+    async fn get_z_report(&self, number: i64) -> Result<ZReport, Error> {
+        self.state()
+            .z_reports
+            .iter()
+            .find(|report| report.number == number)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+}
+
+#[async_trait]
+impl TaxRepository for InMemoryRepository {
+    async fn get_tax_categories(&self) -> Result<Vec<TaxCategory>, Error> {
+        let mut categories = self.state().tax_categories.clone();
+        categories.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(categories)
+    }
+
+    async fn get_tax_category(&self, tax_category_id: Uuid) -> Result<TaxCategory, Error> {
+        self.state()
+            .tax_categories
+            .iter()
+            .find(|category| category.id == tax_category_id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn add_tax_category(&self, new_category: NewTaxCategoryRequest) -> Result<TaxCategory, Error> {
+        let category = TaxCategory {
+            id: Uuid::new_v4(),
+            name: new_category.name,
+            rate: new_category.rate,
+            inclusive: new_category.inclusive,
+            created_at: Self::now(),
+        };
+        self.state().tax_categories.push(category.clone());
+        Ok(category)
+    }
+
+    async fn update_tax_category(
+        &self,
+        tax_category_id: Uuid,
+        updated_category: UpdateTaxCategoryRequest,
+    ) -> Result<Option<TaxCategory>, Error> {
+        let mut state = self.state();
+        let category = state.tax_categories.iter_mut().find(|category| category.id == tax_category_id);
+        Ok(category.map(|category| {
+            if let Some(name) = updated_category.name {
+                category.name = name;
+            }
+            category.rate = updated_category.rate.unwrap_or(category.rate);
+            category.inclusive = updated_category.inclusive.unwrap_or(category.inclusive);
+            category.clone()
+        }))
+    }
+
+    async fn delete_tax_category(&self, tax_category_id: Uuid) -> Result<bool, Error> {
+        let mut state = self.state();
+        let before = state.tax_categories.len();
+        state.tax_categories.retain(|category| category.id != tax_category_id);
+        for menu in state.menu.iter_mut().filter(|menu| menu.tax_category_id == Some(tax_category_id)) {
+            menu.tax_category_id = None;
+        }
+        Ok(state.tax_categories.len() < before)
+    }
+
+    async fn get_service_charge_rules(&self) -> Result<Vec<ServiceChargeRule>, Error> {
+        let mut rules = self.state().service_charges.clone();
+        rules.sort_by_key(|rule| (rule.min_covers, rule.created_at));
+        Ok(rules)
+    }
+
+    async fn add_service_charge_rule(&self, new_rule: NewServiceChargeRuleRequest) -> Result<ServiceChargeRule, Error> {
+        let rule = ServiceChargeRule {
+            id: Uuid::new_v4(),
+            name: new_rule.name,
+            rate: new_rule.rate,
+            min_covers: new_rule.min_covers,
+            created_at: Self::now(),
+        };
+        self.state().service_charges.push(rule.clone());
+        Ok(rule)
+    }
+
+    async fn delete_service_charge_rule(&self, rule_id: Uuid) -> Result<bool, Error> {
+        let mut state = self.state();
+        let before = state.service_charges.len();
+        state.service_charges.retain(|rule| rule.id != rule_id);
+        Ok(state.service_charges.len() < before)
+    }
+}
+
+#[async_trait]
+impl DiscountRepository for InMemoryRepository {
+    async fn get_discounts(&self, tables_id: Uuid) -> Result<Vec<Discount>, Error> {
+        let mut discounts: Vec<Discount> = self
+            .state()
+            .discounts
+            .iter()
+            .filter(|discount| discount.tables_id == tables_id && discount.session_id.is_none())
+            .cloned()
+            .collect();
+        discounts.sort_by_key(|discount| discount.created_at);
+        Ok(discounts)
+    }
+
+    async fn get_settled_discounts(&self, session_id: Uuid) -> Result<Vec<Discount>, Error> {
+        let mut discounts: Vec<Discount> = self
+            .state()
+            .discounts
+            .iter()
+            .filter(|discount| discount.session_id == Some(session_id))
+            .cloned()
+            .collect();
+        discounts.sort_by_key(|discount| discount.created_at);
+        Ok(discounts)
+    }
+
+    async fn add_discount(&self, tables_id: Uuid, discount: NewDiscount, actor: &str) -> Result<AddDiscountOutcome, Error> {
+        let mut state = self.state();
+        if !state.tables.iter().any(|table| table.id == tables_id) {
+            return Err(Error::RowNotFound);
+        }
+
+        if let Some(promo_code_id) = discount.promo_code_id {
+            let promo_code = state
+                .promo_codes
+                .iter_mut()
+                .find(|promo_code| promo_code.id == promo_code_id)
+                .ok_or(Error::RowNotFound)?;
+            if promo_code.max_uses.is_some_and(|max_uses| promo_code.uses >= max_uses) {
+                return Ok(AddDiscountOutcome::PromoCodeUsedUp);
+            }
+            promo_code.uses += 1;
+        }
+
+        let added = Discount {
+            id: Uuid::new_v4(),
+            tables_id,
+            items_id: discount.items_id,
+            kind: discount.kind,
+            value: discount.value,
+            reason: discount.reason,
+            promo_code_id: discount.promo_code_id,
+            applied_by: discount.applied_by,
+            session_id: None,
+            created_at: Self::now(),
+            created_by: Some(actor.to_string()),
+        };
+        state.discounts.push(added.clone());
+        Ok(AddDiscountOutcome::Added(added))
+    }
+
+    async fn delete_discount(&self, tables_id: Uuid, discount_id: Uuid) -> Result<bool, Error> {
+        let mut state = self.state();
+        let Some(position) = state.discounts.iter().position(|discount| {
+            discount.id == discount_id && discount.tables_id == tables_id && discount.session_id.is_none()
+        }) else {
+            return Ok(false);
+        };
+
+        let removed = state.discounts.remove(position);
+        if let Some(promo_code) = state
+            .promo_codes
+            .iter_mut()
+            .find(|promo_code| Some(promo_code.id) == removed.promo_code_id)
+        {
+            promo_code.uses = (promo_code.uses - 1).max(0);
+        }
+        Ok(true)
+    }
+
+    async fn get_promo_codes(&self) -> Result<Vec<PromoCode>, Error> {
+        let mut promo_codes = self.state().promo_codes.clone();
+        promo_codes.sort_by(|a, b| a.code.cmp(&b.code));
+        Ok(promo_codes)
+    }
+
+    async fn get_promo_code_by_code(&self, code: &str) -> Result<Option<PromoCode>, Error> {
+        Ok(self
+            .state()
+            .promo_codes
+            .iter()
+            .find(|promo_code| promo_code.code == code)
+            .cloned())
+    }
+
+    async fn add_promo_code(&self, new_promo_code: NewPromoCodeRequest) -> Result<PromoCode, Error> {
+        let promo_code = PromoCode {
+            id: Uuid::new_v4(),
+            code: new_promo_code.code,
+            kind: new_promo_code.kind,
+            value: new_promo_code.value,
+            valid_from: new_promo_code.valid_from,
+            valid_until: new_promo_code.valid_until,
+            max_uses: new_promo_code.max_uses,
+            uses: 0,
+            created_at: Self::now(),
+        };
+        self.state().promo_codes.push(promo_code.clone());
+        Ok(promo_code)
+    }
+}
+
+#[async_trait]
+impl PriceRuleRepository for InMemoryRepository {
+    async fn get_price_rules(&self, menu_ids: &[Uuid]) -> Result<Vec<PriceRule>, Error> {
+        let mut rules: Vec<PriceRule> = self
+            .state()
+            .price_rules
+            .iter()
+            .filter(|rule| menu_ids.contains(&rule.menu_id))
+            .cloned()
+            .collect();
+        rules.sort_by_key(|rule| (rule.start_time, rule.created_at));
+        Ok(rules)
+    }
+
+    async fn add_price_rule(&self, menu_id: Uuid, new_rule: NewPriceRuleRequest) -> Result<PriceRule, Error> {
+        let mut state = self.state();
+        if !state.menu.iter().any(|menu| menu.id == menu_id) {
+            return Err(Error::RowNotFound);
+        }
+
+        let rule = PriceRule {
+            id: Uuid::new_v4(),
+            menu_id,
+            name: new_rule.name,
+            price: new_rule.price,
+            days: new_rule.days,
+            start_time: new_rule.start_time,
+            end_time: new_rule.end_time,
+            created_at: Self::now(),
+        };
+        state.price_rules.push(rule.clone());
+        Ok(rule)
+    }
+
+    async fn update_price_rule(&self, menu_id: Uuid, rule_id: Uuid, updated_rule: NewPriceRuleRequest) -> Result<Option<PriceRule>, Error> {
+        let mut state = self.state();
+        let rule = state
+            .price_rules
+            .iter_mut()
+            .find(|rule| rule.id == rule_id && rule.menu_id == menu_id);
+        Ok(rule.map(|rule| {
+            rule.name = updated_rule.name;
+            rule.price = updated_rule.price;
+            rule.days = updated_rule.days;
+            rule.start_time = updated_rule.start_time;
+            rule.end_time = updated_rule.end_time;
+            rule.clone()
+        }))
+    }
+
+    async fn delete_price_rule(&self, menu_id: Uuid, rule_id: Uuid) -> Result<bool, Error> {
+        let mut state = self.state();
+        let before = state.price_rules.len();
+        state.price_rules.retain(|rule| !(rule.id == rule_id && rule.menu_id == menu_id));
+        Ok(state.price_rules.len() < before)
+    }
+}
+
+#[async_trait]
+impl KitchenRepository for InMemoryRepository {
+    async fn get_kitchen_queue(&self, filters: &FilterParams) -> Result<Vec<KitchenQueueItem>, Error> {
+        let state = self.state();
+        let mut items: Vec<KitchenQueueItem> = state
+            .items
+            .iter()
+            .filter(|item| {
+                filters.menu_id.is_none_or(|menu_id| item.menu_id == menu_id)
+                    && item.quantity > item.delivered_quantity
+                    && item.deleted_at.is_none()
+            })
+            .filter_map(|item| {
+                let table = state.tables.iter().find(|table| table.id == item.tables_id)?;
+                let prep_time = state.prep_time(item.menu_id);
+                Some(KitchenQueueItem {
+                    id: item.id,
+                    tables_id: item.tables_id,
+                    table_name: table.name.clone(),
+                    menu_id: item.menu_id,
+                    menu_name: item.menu_name.clone(),
+                    quantity: item.quantity,
+                    delivered_quantity: item.delivered_quantity,
+                    status: item.status,
+                    created_at: item.created_at,
+                    started_at: item.started_at,
+                    ready_at: item.ready_at,
+                    prep_time,
+                    due_at: item.created_at + Duration::minutes(i64::from(prep_time)),
+                })
+            })
+            .collect();
+        items.sort_by_key(|item| (item.due_at, item.created_at, item.id));
+        Ok(items)
+    }
+
+    async fn get_kitchen_queue_ahead_of(&self, tables_id: Uuid) -> Result<Vec<KitchenQueueItem>, Error> {
+        let queue = self.get_kitchen_queue(&FilterParams { menu_id: None }).await?;
+        let last = queue.iter().rposition(|item| item.tables_id == tables_id && item.status == ItemStatus::Ordered);
+        Ok(queue
+            .into_iter()
+            .enumerate()
+            .filter(|(position, item)| {
+                item.tables_id == tables_id
+                    || item.status == ItemStatus::InPreparation
+                    || (item.status == ItemStatus::Ordered && last.is_some_and(|last| *position <= last))
+            })
+            .map(|(_, item)| item)
+            .collect())
+    }
+
+    async fn update_item_status(
+        &self,
+        tables_id: Uuid,
+        item_id: Uuid,
+        status: ItemStatus,
+        now: NaiveDateTime,
+        actor: &str,
+    ) -> Result<ItemStatusOutcome, Error> {
+        let mut state = self.state();
+        let Some(item) = state
+            .items
+            .iter_mut()
+            .find(|item| item.tables_id == tables_id && item.id == item_id && item.deleted_at.is_none())
+        else {
+            return Ok(ItemStatusOutcome::NotFound);
+        };
+        if item.status.next() != Some(status) {
+            return Ok(ItemStatusOutcome::Illegal(item.status));
+        }
+
+        item.status = status;
+        item.updated_at = now;
+        item.updated_by = Some(actor.to_string());
+        let resolved_alert = match status {
+            ItemStatus::Ordered => None,
+            ItemStatus::InPreparation => {
+                item.started_at = Some(now);
+                None
+            }
+            ItemStatus::Ready => {
+                item.ready_at = Some(now);
+                None
+            }
+            ItemStatus::Served => {
+                let delivered = item.quantity - item.delivered_quantity;
+                item.delivered_quantity = item.quantity;
+                item.delivered_at = item.delivered_at.or(Some(now));
+                item.served_at = Some(now);
+                if delivered != 0 {
+                    state.deliveries.push(ItemDelivery {
+                        id: Uuid::new_v4(),
+                        items_id: item_id,
+                        quantity: delivered,
+                        delivered_at: now,
+                        delivered_by: Some(actor.to_string()),
+                    });
+                }
+                state.resolve_alerts(Some(&[item_id]), now).get(&item_id).copied()
+            }
+        };
+
+        Ok(ItemStatusOutcome::Updated(resolved_alert))
+    }
+}
+
+#[async_trait]
+impl AlertRepository for InMemoryRepository {
+    async fn raise_overdue_alerts(&self, now: NaiveDateTime, grace_minutes: i32) -> Result<OverdueAlerts, Error> {
+        let mut state = self.state();
+        let resolved_ids: Vec<Uuid> = state.resolve_alerts(None, now).into_values().collect();
+
+        let mut raised = vec![];
+        for item in state.items.iter().filter(|item| item.deleted_at.is_none() && item.quantity > item.delivered_quantity) {
+            if state.alerts.iter().any(|alert| alert.items_id == item.id && alert.resolved_at.is_none()) {
+                continue;
+            }
+            let grace = state
+                .alert_thresholds
+                .iter()
+                .find(|threshold| threshold.menu_id == item.menu_id)
+                .map_or(grace_minutes, |threshold| threshold.grace_minutes);
+            let overdue_at = item.created_at + Duration::minutes(i64::from(state.prep_time(item.menu_id) + grace));
+            if overdue_at <= now {
+                raised.push(AlertRow {
+                    id: Uuid::new_v4(),
+                    items_id: item.id,
+                    overdue_at,
+                    raised_at: now,
+                    resolved_at: None,
+                });
+            }
+        }
+        let raised_ids: Vec<Uuid> = raised.iter().map(|alert| alert.id).collect();
+        state.alerts.extend(raised);
+
+        Ok(OverdueAlerts {
+            raised: state.alerts_by_id(&raised_ids),
+            resolved: state.alerts_by_id(&resolved_ids),
+        })
+    }
+
+    async fn get_alerts(&self, include_resolved: bool) -> Result<Vec<ItemAlert>, Error> {
+        let state = self.state();
+        let mut alerts: Vec<ItemAlert> = state
+            .alerts
+            .iter()
+            .filter(|alert| include_resolved || alert.resolved_at.is_none())
+            .filter_map(|alert| state.item_alert(alert))
+            .collect();
+        alerts.sort_by_key(|alert| (alert.resolved_at.is_some(), alert.overdue_at, alert.id));
+        Ok(alerts)
+    }
+
+    async fn get_alerts_by_id(&self, alert_ids: &[Uuid]) -> Result<Vec<ItemAlert>, Error> {
+        Ok(self.state().alerts_by_id(alert_ids))
+    }
+
+    async fn get_alert_threshold(&self, menu_id: Uuid) -> Result<Option<AlertThreshold>, Error> {
+        let state = self.state();
+        Ok(state.alert_thresholds.iter().find(|threshold| threshold.menu_id == menu_id).cloned())
+    }
+
+    async fn set_alert_threshold(&self, menu_id: Uuid, grace_minutes: i32) -> Result<AlertThreshold, Error> {
+        let mut state = self.state();
+        let threshold = AlertThreshold { menu_id, grace_minutes };
+        state.alert_thresholds.retain(|existing| existing.menu_id != menu_id);
+        state.alert_thresholds.push(threshold.clone());
+        Ok(threshold)
+    }
+
+    async fn delete_alert_threshold(&self, menu_id: Uuid) -> Result<bool, Error> {
+        let mut state = self.state();
+        let before = state.alert_thresholds.len();
+        state.alert_thresholds.retain(|threshold| threshold.menu_id != menu_id);
+        Ok(state.alert_thresholds.len() < before)
+    }
+}
@@ -1,2 +1,6 @@
 pub mod connection;
+#[cfg(test)]
+pub mod memory;
+pub mod publishing;
+pub mod repository;
 mod connection_test;
\ No newline at end of file
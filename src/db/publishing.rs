@@ -0,0 +1,495 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use log::error;
+use rust_decimal::Decimal;
+use sqlx::Error;
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::db::connection::{AddDiscountOutcome, AddPaymentOutcome, AddRefundOutcome, DeleteTableOutcome, ItemStatusOutcome, NewCheckRequest, NewDiscount, NewItemRequest, NewPayment, NewPriceRuleRequest, NewPromoCodeRequest, NewRefund, NewServiceChargeRuleRequest, NewTaxCategoryRequest, OverdueAlerts, RetireMenuOutcome, Settlement, UpdateItemRequest, UpdateMenuRequest, UpdateTableRequest, UpdateTaxCategoryRequest, UpdatedItem};
+use crate::db::repository::{AlertRepository, BillingRepository, CheckRepository, DayTotals, DeviceRepository, DiscountRepository, ItemRepository, KitchenRepository, MenuRepository, PaymentRepository, PriceRuleRepository, ReportRepository, Repository, TableRepository, TaxRepository};
+use crate::error::AppError;
+use crate::events::items::{ItemEventKind, ItemEvents};
+use crate::models::{restaurant_models::{AlertThreshold, BillItem, ChargedTax, Check, CheckItem, DeletedItem, Device, Discount, ItemAlert, ItemDelivery, ItemStatus, KitchenQueueItem, Menu, PartialItem, PartialItemReturn, Payment, PriceRule, PromoCode, RefundableItem, ServiceChargeRule, Table, TableSession, TaxCategory, ZReport}, route_models::{FilterParams, Pagination}};
+
+/// Wraps the repository the routes use and publishes an item event for every
+/// item it changes, and for every overdue alert raised or resolved, so no
+/// write can leave connected screens out of date. Everything else is passed
+/// straight through.
+pub struct PublishingRepository {
+    repo: Arc<dyn Repository>,
+    events: Arc<ItemEvents>,
+    clock: Arc<dyn Clock>,
+}
+
+impl PublishingRepository {
+    pub fn new(repo: Arc<dyn Repository>, events: Arc<ItemEvents>, clock: Arc<dyn Clock>) -> Self {
+        PublishingRepository { repo, events, clock }
+    }
+
+    /// Publishes an event for each of the items as they now are. The change has
+    /// already been made by then, so failing to read the items back is only logged.
+    async fn publish_changes(&self, item_ids: &[Uuid], kind_of: impl Fn(&PartialItemReturn) -> ItemEventKind + Send + Sync) {
+        if item_ids.is_empty() {
+            return;
+        }
+        match self.repo.get_items(item_ids).await {
+            Ok(items) => {
+                for item in items {
+                    self.events.publish(kind_of(&item), item, self.clock.now());
+                }
+            }
+            Err(e) => error!("Failed to publish changes of items {:?}: {}", item_ids, e),
+        }
+    }
+
+    /// Publishes the alerts as they now are, logging a failure to read them
+    /// back like `publish_changes`.
+    async fn publish_alerts(&self, alert_ids: &[Uuid]) {
+        if alert_ids.is_empty() {
+            return;
+        }
+        match self.repo.get_alerts_by_id(alert_ids).await {
+            Ok(alerts) => {
+                for alert in alerts {
+                    self.events.publish_alert(alert, self.clock.now());
+                }
+            }
+            Err(e) => error!("Failed to publish alerts {:?}: {}", alert_ids, e),
+        }
+    }
+}
+
+/// An update that changed how much of the item was delivered is a delivery.
+fn delivery_kind(updated: &[UpdatedItem], item: &PartialItemReturn) -> ItemEventKind {
+    if updated.iter().any(|updated| updated.id == item.id && updated.delivered) {
+        ItemEventKind::Delivered
+    } else {
+        ItemEventKind::Updated
+    }
+}
+
+#[async_trait]
+impl DeviceRepository for PublishingRepository {
+    async fn get_device(&self, device_id: Uuid) -> Result<Device, Error> {
+        self.repo.get_device(device_id).await
+    }
+}
+
+#[async_trait]
+impl TableRepository for PublishingRepository {
+    async fn get_tables(&self) -> Result<Vec<Table>, Error> {
+        self.repo.get_tables().await
+    }
+
+    async fn add_table(&self, name: String) -> Result<Table, Error> {
+        self.repo.add_table(name).await
+    }
+
+    async fn get_table(&self, tables_id: Uuid) -> Result<Table, Error> {
+        self.repo.get_table(tables_id).await
+    }
+
+    async fn update_table(&self, tables_id: Uuid, updated_table: UpdateTableRequest) -> Result<Option<Table>, Error> {
+        self.repo.update_table(tables_id, updated_table).await
+    }
+
+    async fn delete_table(&self, tables_id: Uuid) -> Result<DeleteTableOutcome, Error> {
+        let outcome = self.repo.delete_table(tables_id).await?;
+        if let DeleteTableOutcome::Deleted(items) = &outcome {
+            for item in items {
+                self.events.publish(ItemEventKind::Deleted, item.clone(), self.clock.now());
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+#[async_trait]
+impl MenuRepository for PublishingRepository {
+    async fn get_menu(&self, include_retired: bool) -> Result<Vec<Menu>, Error> {
+        self.repo.get_menu(include_retired).await
+    }
+
+    async fn get_menu_item(&self, menu_id: Uuid) -> Result<Menu, Error> {
+        self.repo.get_menu_item(menu_id).await
+    }
+
+    async fn add_menu(&self, name: String, price: Decimal, prep_time: i32, tax_category_id: Option<Uuid>) -> Result<Menu, Error> {
+        self.repo.add_menu(name, price, prep_time, tax_category_id).await
+    }
+
+    async fn update_menu(&self, menu_id: Uuid, updated_menu: UpdateMenuRequest) -> Result<Option<Menu>, Error> {
+        self.repo.update_menu(menu_id, updated_menu).await
+    }
+
+    async fn retire_menu(&self, menu_id: Uuid) -> Result<RetireMenuOutcome, Error> {
+        self.repo.retire_menu(menu_id).await
+    }
+
+    async fn get_orderable_menu_ids(&self, menu_ids: &[Uuid]) -> Result<HashSet<Uuid>, Error> {
+        self.repo.get_orderable_menu_ids(menu_ids).await
+    }
+}
+
+#[async_trait]
+impl ItemRepository for PublishingRepository {
+    async fn get_all_remaining_items_from_table(
+        &self,
+        tables_id: Uuid,
+        pagination: Pagination,
+        filters: FilterParams,
+    ) -> Result<Vec<PartialItemReturn>, Error> {
+        self.repo.get_all_remaining_items_from_table(tables_id, pagination, filters).await
+    }
+
+    async fn count_remaining_items_from_table(&self, tables_id: Uuid, filters: &FilterParams) -> Result<i64, Error> {
+        self.repo.count_remaining_items_from_table(tables_id, filters).await
+    }
+
+    async fn get_item_quantities(&self, item_ids: &[Uuid]) -> Result<HashMap<Uuid, (i32, i32)>, Error> {
+        self.repo.get_item_quantities(item_ids).await
+    }
+
+    async fn get_paid_item_ids(&self, item_ids: &[Uuid]) -> Result<HashSet<Uuid>, Error> {
+        self.repo.get_paid_item_ids(item_ids).await
+    }
+
+    async fn create_items(
+        &self,
+        tables_id: Uuid,
+        new_items: Vec<NewItemRequest>,
+        ordered_at: NaiveDateTime,
+        actor: &str,
+    ) -> Result<Vec<PartialItem>, AppError> {
+        let created = self.repo.create_items(tables_id, new_items, ordered_at, actor).await?;
+        let item_ids: Vec<Uuid> = created.iter().map(|item| item.id).collect();
+        self.publish_changes(&item_ids, |_| ItemEventKind::Created).await;
+        Ok(created)
+    }
+
+    async fn create_item(
+        &self,
+        tables_id: Uuid,
+        new_item: NewItemRequest,
+        ordered_at: NaiveDateTime,
+        actor: &str,
+    ) -> Result<PartialItem, AppError> {
+        let created = self.repo.create_item(tables_id, new_item, ordered_at, actor).await?;
+        self.publish_changes(&[created.id], |_| ItemEventKind::Created).await;
+        Ok(created)
+    }
+
+    async fn get_item(&self, tables_id: Uuid, item_id: Uuid) -> Result<PartialItemReturn, Error> {
+        self.repo.get_item(tables_id, item_id).await
+    }
+
+    async fn get_items(&self, item_ids: &[Uuid]) -> Result<Vec<PartialItemReturn>, Error> {
+        self.repo.get_items(item_ids).await
+    }
+
+    async fn delete_item(&self, tables_id: Uuid, item_id: Uuid, actor: &str) -> Result<bool, Error> {
+        // A deleted item can no longer be read back, so it is published as it was.
+        let item = self.repo.get_item(tables_id, item_id).await.ok();
+        let deleted = self.repo.delete_item(tables_id, item_id, actor).await?;
+        if let Some(item) = item.filter(|_| deleted) {
+            self.events.publish(ItemEventKind::Deleted, item, self.clock.now());
+        }
+        Ok(deleted)
+    }
+
+    async fn restore_item(&self, tables_id: Uuid, item_id: Uuid, actor: &str) -> Result<bool, Error> {
+        let restored = self.repo.restore_item(tables_id, item_id, actor).await?;
+        if restored {
+            self.publish_changes(&[item_id], |_| ItemEventKind::Updated).await;
+        }
+        Ok(restored)
+    }
+
+    async fn get_deleted_items(&self, from: Option<NaiveDateTime>, to: Option<NaiveDateTime>) -> Result<Vec<DeletedItem>, Error> {
+        self.repo.get_deleted_items(from, to).await
+    }
+
+    async fn update_item(&self, item_id: Uuid, updated_item: UpdateItemRequest, actor: &str) -> Result<bool, AppError> {
+        let updated_item = UpdateItemRequest {
+            id: item_id,
+            ..updated_item
+        };
+        let updated = self.update_items(vec![updated_item], actor).await?;
+        Ok(!updated.is_empty())
+    }
+
+    async fn update_items(&self, items: Vec<UpdateItemRequest>, actor: &str) -> Result<Vec<UpdatedItem>, AppError> {
+        let updated = self.repo.update_items(items, actor).await?;
+        let item_ids: Vec<Uuid> = updated.iter().map(|item| item.id).collect();
+        self.publish_changes(&item_ids, |item| delivery_kind(&updated, item)).await;
+        let resolved_alerts: Vec<Uuid> = updated.iter().filter_map(|item| item.resolved_alert).collect();
+        self.publish_alerts(&resolved_alerts).await;
+        Ok(updated)
+    }
+
+    async fn get_item_deliveries(&self, tables_id: Uuid, item_id: Uuid) -> Result<Vec<ItemDelivery>, Error> {
+        self.repo.get_item_deliveries(tables_id, item_id).await
+    }
+}
+
+#[async_trait]
+impl BillingRepository for PublishingRepository {
+    async fn get_bill_items(&self, tables_id: Uuid) -> Result<Vec<BillItem>, Error> {
+        self.repo.get_bill_items(tables_id).await
+    }
+
+    async fn get_settled_bill_items(&self, session_id: Uuid) -> Result<Vec<BillItem>, Error> {
+        self.repo.get_settled_bill_items(session_id).await
+    }
+
+    async fn get_settled_taxes(&self, session_id: Uuid) -> Result<Vec<ChargedTax>, Error> {
+        self.repo.get_settled_taxes(session_id).await
+    }
+}
+
+#[async_trait]
+impl CheckRepository for PublishingRepository {
+    async fn get_checks(&self, tables_id: Uuid) -> Result<Vec<Check>, Error> {
+        self.repo.get_checks(tables_id).await
+    }
+
+    async fn get_check_items(&self, tables_id: Uuid) -> Result<Vec<CheckItem>, Error> {
+        self.repo.get_check_items(tables_id).await
+    }
+
+    async fn replace_checks(&self, tables_id: Uuid, checks: Vec<NewCheckRequest>, actor: &str) -> Result<Vec<Check>, Error> {
+        self.repo.replace_checks(tables_id, checks, actor).await
+    }
+
+    async fn delete_checks(&self, tables_id: Uuid) -> Result<u64, Error> {
+        self.repo.delete_checks(tables_id).await
+    }
+}
+
+#[async_trait]
+impl PaymentRepository for PublishingRepository {
+    async fn get_open_session(&self, tables_id: Uuid) -> Result<Option<TableSession>, Error> {
+        self.repo.get_open_session(tables_id).await
+    }
+
+    async fn get_payments(&self, session_id: Uuid) -> Result<Vec<Payment>, Error> {
+        self.repo.get_payments(session_id).await
+    }
+
+    async fn add_payment(
+        &self,
+        tables_id: Uuid,
+        payment: NewPayment,
+        paid_before: Decimal,
+        settlement: Option<Settlement>,
+        actor: &str,
+    ) -> Result<AddPaymentOutcome, Error> {
+        let settled: Vec<Uuid> = settlement
+            .iter()
+            .flat_map(|settlement| settlement.items.iter().map(|item| item.item_id))
+            .collect();
+        let outcome = self.repo.add_payment(tables_id, payment, paid_before, settlement, actor).await?;
+        if matches!(outcome, AddPaymentOutcome::Recorded { .. }) {
+            self.publish_changes(&settled, |_| ItemEventKind::Updated).await;
+        }
+        Ok(outcome)
+    }
+
+    async fn get_payment(&self, payment_id: Uuid) -> Result<Payment, Error> {
+        self.repo.get_payment(payment_id).await
+    }
+
+    async fn get_session(&self, session_id: Uuid) -> Result<TableSession, Error> {
+        self.repo.get_session(session_id).await
+    }
+
+    async fn get_last_session(&self, tables_id: Uuid) -> Result<Option<TableSession>, Error> {
+        self.repo.get_last_session(tables_id).await
+    }
+
+    async fn get_refundable_items(&self, session_id: Uuid) -> Result<Vec<RefundableItem>, Error> {
+        self.repo.get_refundable_items(session_id).await
+    }
+
+    async fn add_refund(
+        &self,
+        payment_id: Uuid,
+        refund: NewRefund,
+        refunded_before: Decimal,
+        actor: &str,
+    ) -> Result<AddRefundOutcome, Error> {
+        let item_ids: Vec<Uuid> = refund.items.iter().map(|item| item.item_id).collect();
+        let outcome = self.repo.add_refund(payment_id, refund, refunded_before, actor).await?;
+        if matches!(outcome, AddRefundOutcome::Recorded(_)) {
+            self.publish_changes(&item_ids, |_| ItemEventKind::Updated).await;
+        }
+        Ok(outcome)
+    }
+}
+
+#[async_trait]
+impl ReportRepository for PublishingRepository {
+    async fn get_payments_between(&self, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<Payment>, Error> {
+        self.repo.get_payments_between(from, to).await
+    }
+
+    async fn close_day(&self, totals: &dyn DayTotals, actor: &str) -> Result<ZReport, AppError> {
+        self.repo.close_day(totals, actor).await
+    }
+
+    async fn get_z_reports(&self) -> Result<Vec<ZReport>, Error> {
+        self.repo.get_z_reports().await
+    }
+
+    async fn get_z_report(&self, number: i64) -> Result<ZReport, Error> {
+        self.repo.get_z_report(number).await
+    }
+}
+
+#[async_trait]
+impl TaxRepository for PublishingRepository {
+    async fn get_tax_categories(&self) -> Result<Vec<TaxCategory>, Error> {
+        self.repo.get_tax_categories().await
+    }
+
+    async fn get_tax_category(&self, tax_category_id: Uuid) -> Result<TaxCategory, Error> {
+        self.repo.get_tax_category(tax_category_id).await
+    }
+
+    async fn add_tax_category(&self, new_category: NewTaxCategoryRequest) -> Result<TaxCategory, Error> {
+        self.repo.add_tax_category(new_category).await
+    }
+
+    async fn update_tax_category(
+        &self,
+        tax_category_id: Uuid,
+        updated_category: UpdateTaxCategoryRequest,
+    ) -> Result<Option<TaxCategory>, Error> {
+        self.repo.update_tax_category(tax_category_id, updated_category).await
+    }
+
+    async fn delete_tax_category(&self, tax_category_id: Uuid) -> Result<bool, Error> {
+        self.repo.delete_tax_category(tax_category_id).await
+    }
+
+    async fn get_service_charge_rules(&self) -> Result<Vec<ServiceChargeRule>, Error> {
+        self.repo.get_service_charge_rules().await
+    }
+
+    async fn add_service_charge_rule(&self, new_rule: NewServiceChargeRuleRequest) -> Result<ServiceChargeRule, Error> {
+        self.repo.add_service_charge_rule(new_rule).await
+    }
+
+    async fn delete_service_charge_rule(&self, rule_id: Uuid) -> Result<bool, Error> {
+        self.repo.delete_service_charge_rule(rule_id).await
+    }
+}
+
+#[async_trait]
+impl DiscountRepository for PublishingRepository {
+    async fn get_discounts(&self, tables_id: Uuid) -> Result<Vec<Discount>, Error> {
+        self.repo.get_discounts(tables_id).await
+    }
+
+    async fn get_settled_discounts(&self, session_id: Uuid) -> Result<Vec<Discount>, Error> {
+        self.repo.get_settled_discounts(session_id).await
+    }
+
+    async fn add_discount(&self, tables_id: Uuid, discount: NewDiscount, actor: &str) -> Result<AddDiscountOutcome, Error> {
+        self.repo.add_discount(tables_id, discount, actor).await
+    }
+
+    async fn delete_discount(&self, tables_id: Uuid, discount_id: Uuid) -> Result<bool, Error> {
+        self.repo.delete_discount(tables_id, discount_id).await
+    }
+
+    async fn get_promo_codes(&self) -> Result<Vec<PromoCode>, Error> {
+        self.repo.get_promo_codes().await
+    }
+
+    async fn get_promo_code_by_code(&self, code: &str) -> Result<Option<PromoCode>, Error> {
+        self.repo.get_promo_code_by_code(code).await
+    }
+
+    async fn add_promo_code(&self, new_promo_code: NewPromoCodeRequest) -> Result<PromoCode, Error> {
+        self.repo.add_promo_code(new_promo_code).await
+    }
+}
+
+#[async_trait]
+impl PriceRuleRepository for PublishingRepository {
+    async fn get_price_rules(&self, menu_ids: &[Uuid]) -> Result<Vec<PriceRule>, Error> {
+        self.repo.get_price_rules(menu_ids).await
+    }
+
+    async fn add_price_rule(&self, menu_id: Uuid, new_rule: NewPriceRuleRequest) -> Result<PriceRule, Error> {
+        self.repo.add_price_rule(menu_id, new_rule).await
+    }
+
+    async fn update_price_rule(&self, menu_id: Uuid, rule_id: Uuid, updated_rule: NewPriceRuleRequest) -> Result<Option<PriceRule>, Error> {
+        self.repo.update_price_rule(menu_id, rule_id, updated_rule).await
+    }
+
+    async fn delete_price_rule(&self, menu_id: Uuid, rule_id: Uuid) -> Result<bool, Error> {
+        self.repo.delete_price_rule(menu_id, rule_id).await
+    }
+}
+
+#[async_trait]
+impl KitchenRepository for PublishingRepository {
+    async fn get_kitchen_queue(&self, filters: &FilterParams) -> Result<Vec<KitchenQueueItem>, Error> {
+        self.repo.get_kitchen_queue(filters).await
+    }
+
+    async fn get_kitchen_queue_ahead_of(&self, tables_id: Uuid) -> Result<Vec<KitchenQueueItem>, Error> {
+        self.repo.get_kitchen_queue_ahead_of(tables_id).await
+    }
+
+    async fn update_item_status(&self, tables_id: Uuid, item_id: Uuid, status: ItemStatus, now: NaiveDateTime, actor: &str) -> Result<ItemStatusOutcome, Error> {
+        let outcome = self.repo.update_item_status(tables_id, item_id, status, now, actor).await?;
+        if let ItemStatusOutcome::Updated(resolved_alert) = outcome {
+            // Serving delivers whatever was left of the item.
+            let kind = if status == ItemStatus::Served { ItemEventKind::Delivered } else { ItemEventKind::Updated };
+            self.publish_changes(&[item_id], |_| kind).await;
+            if let Some(alert_id) = resolved_alert {
+                self.publish_alerts(&[alert_id]).await;
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+#[async_trait]
+impl AlertRepository for PublishingRepository {
+    async fn raise_overdue_alerts(&self, now: NaiveDateTime, grace_minutes: i32) -> Result<OverdueAlerts, Error> {
+        let alerts = self.repo.raise_overdue_alerts(now, grace_minutes).await?;
+        for alert in alerts.resolved.iter().chain(&alerts.raised) {
+            self.events.publish_alert(alert.clone(), self.clock.now());
+        }
+        Ok(alerts)
+    }
+
+    async fn get_alerts(&self, include_resolved: bool) -> Result<Vec<ItemAlert>, Error> {
+        self.repo.get_alerts(include_resolved).await
+    }
+
+    async fn get_alerts_by_id(&self, alert_ids: &[Uuid]) -> Result<Vec<ItemAlert>, Error> {
+        self.repo.get_alerts_by_id(alert_ids).await
+    }
+
+    async fn get_alert_threshold(&self, menu_id: Uuid) -> Result<Option<AlertThreshold>, Error> {
+        self.repo.get_alert_threshold(menu_id).await
+    }
+
+    async fn set_alert_threshold(&self, menu_id: Uuid, grace_minutes: i32) -> Result<AlertThreshold, Error> {
+        self.repo.set_alert_threshold(menu_id, grace_minutes).await
+    }
+
+    async fn delete_alert_threshold(&self, menu_id: Uuid) -> Result<bool, Error> {
+        self.repo.delete_alert_threshold(menu_id).await
+    }
+}
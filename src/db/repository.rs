@@ -0,0 +1,346 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use sqlx::Error;
+use uuid::Uuid;
+
+use crate::db::connection::{AddDiscountOutcome, AddPaymentOutcome, AddRefundOutcome, DeleteTableOutcome, ItemStatusOutcome, NewCheckRequest, NewDiscount, NewPayment, NewItemRequest, NewPriceRuleRequest, NewPromoCodeRequest, NewRefund, NewServiceChargeRuleRequest, NewTaxCategoryRequest, OverdueAlerts, RetireMenuOutcome, Settlement, UnreportedSales, UpdateItemRequest, UpdateMenuRequest, UpdateTableRequest, UpdateTaxCategoryRequest, UpdatedItem};
+use crate::error::AppError;
+use crate::models::{restaurant_models::{AlertThreshold, BillItem, ChargedTax, Check, CheckItem, DeletedItem, Device, Discount, ItemAlert, ItemDelivery, ItemStatus, KitchenQueueItem, PartialItem, PartialItemReturn, Payment, PriceRule, PromoCode, RefundableItem, ServiceChargeRule, Table, TableSession, TaxCategory, Menu, ZReport}, route_models::{FilterParams, Pagination, ZReportTotals}};
+
+/// Everything the routes need from storage. `Database` is the Postgres
+/// implementation used in production.
+pub trait Repository:
+    DeviceRepository
+    + TableRepository
+    + MenuRepository
+    + ItemRepository
+    + BillingRepository
+    + CheckRepository
+    + PaymentRepository
+    + ReportRepository
+    + TaxRepository
+    + DiscountRepository
+    + PriceRuleRepository
+    + KitchenRepository
+    + AlertRepository
+{
+}
+
+impl<T> Repository for T where
+    T: DeviceRepository
+        + TableRepository
+        + MenuRepository
+        + ItemRepository
+        + BillingRepository
+        + CheckRepository
+        + PaymentRepository
+        + ReportRepository
+        + TaxRepository
+    + DiscountRepository
+    + PriceRuleRepository
+    + KitchenRepository
+    + AlertRepository
+{
+}
+
+#[async_trait]
+pub trait DeviceRepository: Send + Sync {
+    async fn get_device(&self, device_id: Uuid) -> Result<Device, Error>;
+}
+
+#[async_trait]
+pub trait TableRepository: Send + Sync {
+    async fn get_tables(&self) -> Result<Vec<Table>, Error>;
+
+    async fn add_table(&self, name: String) -> Result<Table, Error>;
+
+    async fn get_table(&self, tables_id: Uuid) -> Result<Table, Error>;
+
+    /// Renames the table and records how many guests are seated. Fields left
+    /// out are kept as they are.
+    async fn update_table(&self, tables_id: Uuid, updated_table: UpdateTableRequest) -> Result<Option<Table>, Error>;
+
+    /// Deletes a table together with its delivered items and open bill. The
+    /// table is kept if any of its items are still waiting to be delivered, or
+    /// if it has taken payments or had items settled or voided, so no sale is
+    /// ever lost from the reports.
+    async fn delete_table(&self, tables_id: Uuid) -> Result<DeleteTableOutcome, Error>;
+}
+
+#[async_trait]
+pub trait MenuRepository: Send + Sync {
+    async fn get_menu(&self, include_retired: bool) -> Result<Vec<Menu>, Error>;
+
+    async fn get_menu_item(&self, menu_id: Uuid) -> Result<Menu, Error>;
+
+    async fn add_menu(&self, name: String, price: Decimal, prep_time: i32, tax_category_id: Option<Uuid>) -> Result<Menu, Error>;
+
+    async fn update_menu(&self, menu_id: Uuid, updated_menu: UpdateMenuRequest) -> Result<Option<Menu>, Error>;
+
+    /// Removes a dish from the menu. Dishes that were already ordered are kept
+    /// for the existing items and only marked as retired.
+    async fn retire_menu(&self, menu_id: Uuid) -> Result<RetireMenuOutcome, Error>;
+
+    /// Returns which of the given menu ids exist and can still be ordered.
+    async fn get_orderable_menu_ids(&self, menu_ids: &[Uuid]) -> Result<HashSet<Uuid>, Error>;
+}
+
+#[async_trait]
+pub trait ItemRepository: Send + Sync {
+    async fn get_all_remaining_items_from_table(
+        &self,
+        tables_id: Uuid,
+        pagination: Pagination,
+        filters: FilterParams,
+    ) -> Result<Vec<PartialItemReturn>, Error>;
+
+    async fn count_remaining_items_from_table(&self, tables_id: Uuid, filters: &FilterParams) -> Result<i64, Error>;
+
+    /// Returns the current `(quantity, delivered_quantity)` of the given items, skipping deleted ones.
+    async fn get_item_quantities(&self, item_ids: &[Uuid]) -> Result<HashMap<Uuid, (i32, i32)>, Error>;
+
+    /// Returns which of the given items have already been paid for.
+    async fn get_paid_item_ids(&self, item_ids: &[Uuid]) -> Result<HashSet<Uuid>, Error>;
+
+    /// Creates the items as ordered at `ordered_at`, copying the current name of
+    /// each dish onto them with the price then in effect, so price rules are honoured.
+    async fn create_items(
+        &self,
+        tables_id: Uuid,
+        new_items: Vec<NewItemRequest>,
+        ordered_at: NaiveDateTime,
+        actor: &str,
+    ) -> Result<Vec<PartialItem>, AppError>;
+
+    /// Creates a single item, priced like `create_items` and failing the same way.
+    async fn create_item(
+        &self,
+        tables_id: Uuid,
+        new_item: NewItemRequest,
+        ordered_at: NaiveDateTime,
+        actor: &str,
+    ) -> Result<PartialItem, AppError>;
+
+    async fn get_item(&self, tables_id: Uuid, item_id: Uuid) -> Result<PartialItemReturn, Error>;
+
+    /// Returns the given items in the order they were created, skipping deleted ones.
+    async fn get_items(&self, item_ids: &[Uuid]) -> Result<Vec<PartialItemReturn>, Error>;
+
+    /// Soft deletes an item, keeping the row so the deletion can be audited and undone.
+    /// Paid items are left alone.
+    async fn delete_item(&self, tables_id: Uuid, item_id: Uuid, actor: &str) -> Result<bool, Error>;
+
+    /// Undoes a soft delete, unless the item was paid or already reported as a void.
+    async fn restore_item(&self, tables_id: Uuid, item_id: Uuid, actor: &str) -> Result<bool, Error>;
+
+    /// Lists soft deleted items, defaulting to the last 24 hours when no window is given.
+    async fn get_deleted_items(&self, from: Option<NaiveDateTime>, to: Option<NaiveDateTime>) -> Result<Vec<DeletedItem>, Error>;
+
+    async fn update_item(&self, item_id: Uuid, updated_item: UpdateItemRequest, actor: &str) -> Result<bool, AppError>;
+
+    /// Applies the updates in one go. Every change in `delivered_quantity` is
+    /// logged as a delivery and `delivered_at` is stamped once an item is fully delivered.
+    /// Paid items can still be delivered but their quantity no longer changes.
+    /// Returns the items updated.
+    async fn update_items(&self, items: Vec<UpdateItemRequest>, actor: &str) -> Result<Vec<UpdatedItem>, AppError>;
+
+    async fn get_item_deliveries(&self, tables_id: Uuid, item_id: Uuid) -> Result<Vec<ItemDelivery>, Error>;
+}
+
+#[async_trait]
+pub trait BillingRepository: Send + Sync {
+    /// Returns the items charged to a table at the price they were ordered at,
+    /// in the order they were created. Deleted items and items paid in an
+    /// earlier session are not charged.
+    async fn get_bill_items(&self, tables_id: Uuid) -> Result<Vec<BillItem>, Error>;
+
+    /// Returns the items a closed session paid for, as they were charged, in
+    /// the tax category they were settled in.
+    async fn get_settled_bill_items(&self, session_id: Uuid) -> Result<Vec<BillItem>, Error>;
+
+    /// Returns the taxes a closed session's items were charged, with the name,
+    /// rate and inclusiveness their categories had when it was settled.
+    async fn get_settled_taxes(&self, session_id: Uuid) -> Result<Vec<ChargedTax>, Error>;
+}
+
+#[async_trait]
+pub trait CheckRepository: Send + Sync {
+    /// Returns the checks of the table's current session, in the order they were given.
+    async fn get_checks(&self, tables_id: Uuid) -> Result<Vec<Check>, Error>;
+
+    async fn get_check_items(&self, tables_id: Uuid) -> Result<Vec<CheckItem>, Error>;
+
+    /// Replaces the way the current session is split with the given checks.
+    async fn replace_checks(&self, tables_id: Uuid, checks: Vec<NewCheckRequest>, actor: &str) -> Result<Vec<Check>, Error>;
+
+    /// Removes the checks of the current session, returning how many there were.
+    async fn delete_checks(&self, tables_id: Uuid) -> Result<u64, Error>;
+}
+
+#[async_trait]
+pub trait PaymentRepository: Send + Sync {
+    /// Returns the session the table is in, once something has been paid.
+    async fn get_open_session(&self, tables_id: Uuid) -> Result<Option<TableSession>, Error>;
+
+    async fn get_payments(&self, session_id: Uuid) -> Result<Vec<Payment>, Error>;
+
+    /// Records a payment in the table's open session, opening one if needed.
+    /// The payment is refused as stale unless the session still has exactly
+    /// `paid_before` paid. With a `settlement` the session is closed, its items
+    /// are marked as paid and the rates they were charged at are kept.
+    async fn add_payment(
+        &self,
+        tables_id: Uuid,
+        payment: NewPayment,
+        paid_before: Decimal,
+        settlement: Option<Settlement>,
+        actor: &str,
+    ) -> Result<AddPaymentOutcome, Error>;
+
+    async fn get_payment(&self, payment_id: Uuid) -> Result<Payment, Error>;
+
+    async fn get_session(&self, session_id: Uuid) -> Result<TableSession, Error>;
+
+    /// Returns the table's most recent session, open or closed.
+    async fn get_last_session(&self, tables_id: Uuid) -> Result<Option<TableSession>, Error>;
+
+    /// Returns the items settled by a session, with what has been refunded of them.
+    async fn get_refundable_items(&self, session_id: Uuid) -> Result<Vec<RefundableItem>, Error>;
+
+    /// Records a refund of a payment as a negative payment in the same session
+    /// and marks the refunded items. The refund is refused as stale unless
+    /// exactly `refunded_before` (amount and tip) has been refunded of the
+    /// payment so far, or if an item no longer has the quantity left to refund.
+    async fn add_refund(
+        &self,
+        payment_id: Uuid,
+        refund: NewRefund,
+        refunded_before: Decimal,
+        actor: &str,
+    ) -> Result<AddRefundOutcome, Error>;
+}
+
+#[async_trait]
+pub trait ReportRepository: Send + Sync {
+    /// Returns payments and refunds taken in `[from, to)`.
+    async fn get_payments_between(&self, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<Payment>, Error>;
+
+    /// Closes the business day with the next report number: the sessions,
+    /// payments and voids not reported yet are read, totalled with `totals`
+    /// and marked as reported so they can no longer change. Settlements,
+    /// payments and restores wait for the close, so the report totals exactly
+    /// the sales it marks.
+    async fn close_day(&self, totals: &dyn DayTotals, actor: &str) -> Result<ZReport, AppError>;
+
+    /// Returns the Z reports, most recent first.
+    async fn get_z_reports(&self) -> Result<Vec<ZReport>, Error>;
+
+    async fn get_z_report(&self, number: i64) -> Result<ZReport, Error>;
+}
+
+/// Works out the totals of a Z report from the sales it covers, while
+/// `close_day` holds them.
+#[async_trait]
+pub trait DayTotals: Send + Sync {
+    async fn totals(&self, sales: &UnreportedSales) -> Result<ZReportTotals, AppError>;
+}
+
+#[async_trait]
+pub trait TaxRepository: Send + Sync {
+    async fn get_tax_categories(&self) -> Result<Vec<TaxCategory>, Error>;
+
+    async fn get_tax_category(&self, tax_category_id: Uuid) -> Result<TaxCategory, Error>;
+
+    async fn add_tax_category(&self, new_category: NewTaxCategoryRequest) -> Result<TaxCategory, Error>;
+
+    async fn update_tax_category(
+        &self,
+        tax_category_id: Uuid,
+        updated_category: UpdateTaxCategoryRequest,
+    ) -> Result<Option<TaxCategory>, Error>;
+
+    /// Deletes a tax category. Dishes in it fall back to the default rate.
+    async fn delete_tax_category(&self, tax_category_id: Uuid) -> Result<bool, Error>;
+
+    async fn get_service_charge_rules(&self) -> Result<Vec<ServiceChargeRule>, Error>;
+
+    async fn add_service_charge_rule(&self, new_rule: NewServiceChargeRuleRequest) -> Result<ServiceChargeRule, Error>;
+
+    async fn delete_service_charge_rule(&self, rule_id: Uuid) -> Result<bool, Error>;
+}
+
+#[async_trait]
+pub trait DiscountRepository: Send + Sync {
+    /// Returns the discounts on the table's current bill, in the order they were applied.
+    async fn get_discounts(&self, tables_id: Uuid) -> Result<Vec<Discount>, Error>;
+
+    /// Returns the discounts settled with a session.
+    async fn get_settled_discounts(&self, session_id: Uuid) -> Result<Vec<Discount>, Error>;
+
+    /// Applies a discount to the table's current bill. A promo code is redeemed
+    /// in the same transaction, unless it has reached its usage limit.
+    async fn add_discount(&self, tables_id: Uuid, discount: NewDiscount, actor: &str) -> Result<AddDiscountOutcome, Error>;
+
+    /// Removes a discount from the current bill, giving back its promo code use.
+    async fn delete_discount(&self, tables_id: Uuid, discount_id: Uuid) -> Result<bool, Error>;
+
+    async fn get_promo_codes(&self) -> Result<Vec<PromoCode>, Error>;
+
+    async fn get_promo_code_by_code(&self, code: &str) -> Result<Option<PromoCode>, Error>;
+
+    async fn add_promo_code(&self, new_promo_code: NewPromoCodeRequest) -> Result<PromoCode, Error>;
+}
+
+#[async_trait]
+pub trait PriceRuleRepository: Send + Sync {
+    /// Returns the price rules of the given dishes.
+    async fn get_price_rules(&self, menu_ids: &[Uuid]) -> Result<Vec<PriceRule>, Error>;
+
+    async fn add_price_rule(&self, menu_id: Uuid, new_rule: NewPriceRuleRequest) -> Result<PriceRule, Error>;
+
+    /// Replaces a rule of the dish. Items already ordered keep their price.
+    async fn update_price_rule(&self, menu_id: Uuid, rule_id: Uuid, updated_rule: NewPriceRuleRequest) -> Result<Option<PriceRule>, Error>;
+
+    async fn delete_price_rule(&self, menu_id: Uuid, rule_id: Uuid) -> Result<bool, Error>;
+}
+
+#[async_trait]
+pub trait KitchenRepository: Send + Sync {
+    /// Returns the items still to be delivered at every table, those due
+    /// first at the top.
+    async fn get_kitchen_queue(&self, filters: &FilterParams) -> Result<Vec<KitchenQueueItem>, Error>;
+
+    /// Returns the part of the kitchen queue deciding when the items of the
+    /// table will be ready, in queue order: its own items, every item in
+    /// preparation, and the ordered items due ahead of its last one.
+    async fn get_kitchen_queue_ahead_of(&self, tables_id: Uuid) -> Result<Vec<KitchenQueueItem>, Error>;
+
+    /// Moves an item on to the next preparation status, stamping it as started
+    /// or ready at `now`. Serving it also delivers what is left of it.
+    async fn update_item_status(&self, tables_id: Uuid, item_id: Uuid, status: ItemStatus, now: NaiveDateTime, actor: &str) -> Result<ItemStatusOutcome, Error>;
+}
+
+#[async_trait]
+pub trait AlertRepository: Send + Sync {
+    /// Resolves the alerts of items since delivered or deleted, then raises one
+    /// for each item still waiting at `now` past its prep time and grace period.
+    /// Dishes without a threshold of their own get `grace_minutes`. Returns the
+    /// alerts resolved and raised.
+    async fn raise_overdue_alerts(&self, now: NaiveDateTime, grace_minutes: i32) -> Result<OverdueAlerts, Error>;
+
+    /// Returns the open alerts, the longest overdue first, followed by the
+    /// resolved ones when asked for.
+    async fn get_alerts(&self, include_resolved: bool) -> Result<Vec<ItemAlert>, Error>;
+
+    /// Returns the given alerts, the longest overdue first.
+    async fn get_alerts_by_id(&self, alert_ids: &[Uuid]) -> Result<Vec<ItemAlert>, Error>;
+
+    async fn get_alert_threshold(&self, menu_id: Uuid) -> Result<Option<AlertThreshold>, Error>;
+
+    async fn set_alert_threshold(&self, menu_id: Uuid, grace_minutes: i32) -> Result<AlertThreshold, Error>;
+
+    async fn delete_alert_threshold(&self, menu_id: Uuid) -> Result<bool, Error>;
+}
\ No newline at end of file
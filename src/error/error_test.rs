@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+
+    use crate::error::AppError;
+    use crate::models::route_models::FieldError;
+
+    #[test]
+    fn test_status_codes_and_error_codes() {
+        let cases = vec![
+            (AppError::Database(sqlx::Error::RowNotFound), StatusCode::NOT_FOUND, "not_found"),
+            (AppError::Conflict("busy".to_string()), StatusCode::CONFLICT, "conflict"),
+            (AppError::LimitExceeded { limit: 100 }, StatusCode::PAYLOAD_TOO_LARGE, "limit_exceeded"),
+            (AppError::Database(sqlx::Error::PoolTimedOut), StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+            (
+                AppError::Serialization(serde_json::from_str::<i32>("not json").unwrap_err()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+            ),
+            (
+                AppError::Validation {
+                    message: "invalid".to_string(),
+                    errors: vec![FieldError {
+                        index: 0,
+                        field: "quantity".to_string(),
+                        message: "Quantity must be greater than zero".to_string(),
+                    }],
+                },
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "validation_failed",
+            ),
+        ];
+
+        for (error, status, code) in cases {
+            assert_eq!(error.status_code(), status);
+            assert_eq!(error.code(), code);
+        }
+    }
+}
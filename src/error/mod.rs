@@ -0,0 +1,141 @@
+mod error_test;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use log::error;
+use thiserror::Error;
+
+use crate::models::route_models::{ErrorResponse, FieldError};
+
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+const UNIQUE_VIOLATION: &str = "23505";
+
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{message}")]
+    Validation {
+        message: String,
+        errors: Vec<FieldError>,
+    },
+    #[error("The number of items exceeds the limit of {limit}")]
+    LimitExceeded { limit: usize },
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error("Could not serialize stored data: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+impl AppError {
+    /// A validation failure that is not tied to a specific field.
+    pub fn invalid(message: impl Into<String>) -> Self {
+        AppError::Validation {
+            message: message.into(),
+            errors: vec![],
+        }
+    }
+
+    fn database_code(error: &sqlx::Error) -> Option<String> {
+        match error {
+            sqlx::Error::Database(db_error) => db_error.code().map(|code| code.into_owned()),
+            _ => None,
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::LimitExceeded { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::Database(sqlx::Error::RowNotFound) => StatusCode::NOT_FOUND,
+            AppError::Database(e) => match Self::database_code(e).as_deref() {
+                Some(FOREIGN_KEY_VIOLATION) | Some(UNIQUE_VIOLATION) => StatusCode::CONFLICT,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            AppError::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Stable, machine readable code returned with every error body.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::Validation { .. } => "validation_failed",
+            AppError::LimitExceeded { .. } => "limit_exceeded",
+            AppError::Database(sqlx::Error::RowNotFound) => "not_found",
+            AppError::Database(e) => match Self::database_code(e).as_deref() {
+                Some(FOREIGN_KEY_VIOLATION) => "foreign_key_violation",
+                Some(UNIQUE_VIOLATION) => "unique_violation",
+                _ => "internal_error",
+            },
+            AppError::Serialization(_) => "internal_error",
+        }
+    }
+
+    /// Message shown to clients. Database details are only logged, never returned.
+    fn public_message(&self) -> String {
+        match self {
+            AppError::Database(sqlx::Error::RowNotFound) => "Resource not found".to_string(),
+            AppError::Database(e) => match Self::database_code(e).as_deref() {
+                Some(FOREIGN_KEY_VIOLATION) => "The request references a missing or still referenced resource".to_string(),
+                Some(UNIQUE_VIOLATION) => "The resource already exists".to_string(),
+                _ => "Internal server error".to_string(),
+            },
+            AppError::Serialization(_) => "Internal server error".to_string(),
+            e => e.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        if status.is_server_error() {
+            error!("{}", self);
+        }
+
+        let code = self.code();
+        let message = self.public_message();
+        let errors = match self {
+            AppError::Validation { errors, .. } => errors,
+            _ => vec![],
+        };
+        let error_response = ErrorResponse {
+            code,
+            message,
+            errors,
+        };
+
+        (status, Json(error_response)).into_response()
+    }
+}
+
+/// Turns `sqlx::Error::RowNotFound` into an `AppError::NotFound` with a specific message.
+pub trait OrNotFound<T> {
+    fn or_not_found(self, message: impl FnOnce() -> String) -> Result<T, AppError>;
+}
+
+impl<T> OrNotFound<T> for Result<T, sqlx::Error> {
+    fn or_not_found(self, message: impl FnOnce() -> String) -> Result<T, AppError> {
+        self.map_err(|e| match e {
+            sqlx::Error::RowNotFound => AppError::NotFound(message()),
+            e => AppError::Database(e),
+        })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::models::restaurant_models::{ItemAlert, PartialItemReturn};
+
+/// How many of the latest events are kept for clients resuming a stream.
+pub const REPLAY_CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemEventKind {
+    Created,
+    Updated,
+    Delivered,
+    Deleted,
+    AlertRaised,
+    AlertResolved,
+}
+
+impl ItemEventKind {
+    /// The SSE event name.
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemEventKind::Created => "item_created",
+            ItemEventKind::Updated => "item_updated",
+            ItemEventKind::Delivered => "item_delivered",
+            ItemEventKind::Deleted => "item_deleted",
+            ItemEventKind::AlertRaised => "alert_raised",
+            ItemEventKind::AlertResolved => "alert_resolved",
+        }
+    }
+}
+
+/// What an event carries, under its own key.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventSubject {
+    Item(PartialItemReturn),
+    Alert(ItemAlert),
+}
+
+/// A change to an item, with the item as it is after the change, or as it
+/// was just before being deleted. An overdue alert raised or resolved on an
+/// item comes with the alert instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemEvent {
+    pub id: u64,
+    pub kind: ItemEventKind,
+    pub tables_id: Uuid,
+    #[serde(flatten)]
+    pub subject: EventSubject,
+    pub at: NaiveDateTime,
+}
+
+/// What a client resuming a stream missed since the last event it saw.
+#[derive(Debug)]
+pub enum Missed {
+    /// The events published since, all still kept.
+    Events(Vec<ItemEvent>),
+    /// Some of the events published since are no longer kept, or the client
+    /// saw its last one before a restart. It has to refetch the items, and can
+    /// resume after the latest event, whose id is given.
+    Gap(u64),
+}
+
+struct Recent {
+    next_id: u64,
+    events: VecDeque<ItemEvent>,
+}
+
+/// Hands item changes out to every connected screen. Events are numbered in
+/// the order they are published, and the latest are kept so a client that
+/// reconnects can pick up from the last one it saw.
+pub struct ItemEvents {
+    sender: broadcast::Sender<ItemEvent>,
+    recent: Mutex<Recent>,
+}
+
+impl ItemEvents {
+    pub fn new(first_id: u64) -> Self {
+        let (sender, _) = broadcast::channel(REPLAY_CAPACITY);
+        ItemEvents {
+            sender,
+            recent: Mutex::new(Recent {
+                next_id: first_id,
+                events: VecDeque::with_capacity(REPLAY_CAPACITY),
+            }),
+        }
+    }
+
+    pub fn publish(&self, kind: ItemEventKind, item: PartialItemReturn, at: NaiveDateTime) -> ItemEvent {
+        self.push(kind, item.tables_id, EventSubject::Item(item), at)
+    }
+
+    /// Publishes that the alert was raised, or resolved if it has been.
+    pub fn publish_alert(&self, alert: ItemAlert, at: NaiveDateTime) -> ItemEvent {
+        let kind = if alert.resolved_at.is_some() { ItemEventKind::AlertResolved } else { ItemEventKind::AlertRaised };
+        self.push(kind, alert.tables_id, EventSubject::Alert(alert), at)
+    }
+
+    fn push(&self, kind: ItemEventKind, tables_id: Uuid, subject: EventSubject, at: NaiveDateTime) -> ItemEvent {
+        let mut recent = self.recent.lock().expect("Item events lock poisoned");
+        let event = ItemEvent {
+            id: recent.next_id,
+            kind,
+            tables_id,
+            subject,
+            at,
+        };
+        recent.next_id += 1;
+        if recent.events.len() == REPLAY_CAPACITY {
+            recent.events.pop_front();
+        }
+        recent.events.push_back(event.clone());
+        // Sending under the lock keeps subscribers in step with the replay.
+        // Nobody listening is not an error.
+        let _ = self.sender.send(event.clone());
+        event
+    }
+
+    /// Returns what was published after `last_event_id`, nothing without
+    /// one, and a receiver for every event published from then on.
+    pub fn subscribe(&self, last_event_id: Option<u64>) -> (Missed, broadcast::Receiver<ItemEvent>) {
+        let recent = self.recent.lock().expect("Item events lock poisoned");
+        let missed = match last_event_id {
+            None => Missed::Events(vec![]),
+            Some(last_event_id) => {
+                let oldest_kept = recent.events.front().map_or(recent.next_id, |event| event.id);
+                // Ids carry on from the time the process started, so an id past
+                // the latest one was handed out before a restart.
+                if last_event_id.saturating_add(1) < oldest_kept || last_event_id >= recent.next_id {
+                    Missed::Gap(recent.next_id.saturating_sub(1))
+                } else {
+                    Missed::Events(recent.events.iter().filter(|event| event.id > last_event_id).cloned().collect())
+                }
+            }
+        };
+        (missed, self.sender.subscribe())
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use crate::events::items::{ItemEvent, ItemEventKind, ItemEvents, Missed, REPLAY_CAPACITY};
+    use crate::models::restaurant_models::{ItemAlert, ItemStatus, PartialItemReturn};
+
+    fn at() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 9, 2).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn item() -> PartialItemReturn {
+        PartialItemReturn {
+            id: Uuid::new_v4(),
+            tables_id: Uuid::new_v4(),
+            menu_id: Uuid::new_v4(),
+            menu_name: "Soup".to_string(),
+            unit_price: Decimal::new(700, 2),
+            quantity: 1,
+            delivered_quantity: 0,
+            delivered_at: None,
+            paid_at: None,
+            refunded_quantity: 0,
+            created_at: at(),
+            prep_time: 10,
+            status: ItemStatus::Ordered,
+            started_at: None,
+            ready_at: None,
+            served_at: None,
+        }
+    }
+
+    fn alert(resolved_at: Option<NaiveDateTime>) -> ItemAlert {
+        ItemAlert {
+            id: Uuid::new_v4(),
+            items_id: Uuid::new_v4(),
+            tables_id: Uuid::new_v4(),
+            table_name: "Terrace".to_string(),
+            menu_id: Uuid::new_v4(),
+            menu_name: "Soup".to_string(),
+            quantity: 1,
+            delivered_quantity: 0,
+            overdue_at: at(),
+            raised_at: at(),
+            resolved_at,
+        }
+    }
+
+    fn missed_events(missed: Missed) -> Vec<ItemEvent> {
+        match missed {
+            Missed::Events(events) => events,
+            Missed::Gap(latest_id) => panic!("Events after {} were not kept", latest_id),
+        }
+    }
+
+    #[test]
+    fn test_events_resume_after_the_last_one_seen() {
+        let events = ItemEvents::new(41);
+        let (missed, mut receiver) = events.subscribe(None);
+        assert!(missed_events(missed).is_empty());
+
+        let created = events.publish(ItemEventKind::Created, item(), at());
+        let delivered = events.publish(ItemEventKind::Delivered, item(), at());
+        assert_eq!((created.id, delivered.id), (41, 42));
+        assert_eq!(receiver.try_recv().unwrap().id, 41);
+        assert_eq!(receiver.try_recv().unwrap().id, 42);
+
+        let (missed, mut receiver) = events.subscribe(Some(41));
+        let missed = missed_events(missed);
+        assert_eq!(missed.iter().map(|event| event.id).collect::<Vec<_>>(), vec![42]);
+        assert_eq!(missed[0].kind, ItemEventKind::Delivered);
+        assert!(receiver.try_recv().is_err());
+        events.publish(ItemEventKind::Deleted, item(), at());
+        assert_eq!(receiver.try_recv().unwrap().id, 43);
+    }
+
+    #[test]
+    fn test_only_the_latest_events_are_kept() {
+        let events = ItemEvents::new(1);
+        for _ in 0..REPLAY_CAPACITY + 5 {
+            events.publish(ItemEventKind::Updated, item(), at());
+        }
+
+        let (missed, _) = events.subscribe(Some(5));
+        let missed = missed_events(missed);
+        assert_eq!(missed.len(), REPLAY_CAPACITY);
+        assert_eq!(missed[0].id, 6);
+        assert_eq!(missed.last().unwrap().id, REPLAY_CAPACITY as u64 + 5);
+
+        let (missed, _) = events.subscribe(Some(4));
+        assert!(matches!(missed, Missed::Gap(latest_id) if latest_id == REPLAY_CAPACITY as u64 + 5));
+    }
+
+    #[test]
+    fn test_events_from_before_a_restart_are_a_gap() {
+        let events = ItemEvents::new(100);
+        assert!(matches!(events.subscribe(Some(150)).0, Missed::Gap(99)));
+        assert!(matches!(events.subscribe(Some(50)).0, Missed::Gap(99)));
+        assert!(missed_events(events.subscribe(Some(99)).0).is_empty());
+
+        events.publish(ItemEventKind::Created, item(), at());
+        assert!(matches!(events.subscribe(Some(101)).0, Missed::Gap(100)));
+        assert_eq!(missed_events(events.subscribe(Some(99)).0).len(), 1);
+    }
+
+    #[test]
+    fn test_alerts_are_published_as_raised_or_resolved() {
+        let events = ItemEvents::new(1);
+        let raised = alert(None);
+        let tables_id = raised.tables_id;
+
+        let event = events.publish_alert(raised, at());
+        assert_eq!((event.kind, event.tables_id), (ItemEventKind::AlertRaised, tables_id));
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["alert"]["menu_name"], "Soup");
+        assert!(json.get("item").is_none());
+
+        let event = events.publish_alert(alert(Some(at())), at());
+        assert_eq!(event.kind, ItemEventKind::AlertResolved);
+        assert_eq!(serde_json::to_value(&event).unwrap()["kind"], "alert_resolved");
+    }
+}
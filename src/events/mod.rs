@@ -0,0 +1,2 @@
+pub mod items;
+mod items_test;
\ No newline at end of file
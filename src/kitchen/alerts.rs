@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use log::{error, warn};
+use sqlx::Error;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, MissedTickBehavior};
+
+use crate::clock::Clock;
+use crate::config::AlertSettings;
+use crate::db::repository::Repository;
+use crate::models::restaurant_models::ItemAlert;
+
+/// Raises alerts for the items overdue by the clock, logging each new one.
+/// Returns the alerts raised.
+pub async fn scan_overdue_items(repo: &dyn Repository, clock: &dyn Clock, grace_minutes: i32) -> Result<Vec<ItemAlert>, Error> {
+    let alerts = repo.raise_overdue_alerts(clock.now(), grace_minutes).await?;
+    for alert in &alerts.raised {
+        warn!(
+            "Item {} ({} x {}) at table {} is overdue since {}",
+            alert.items_id, alert.quantity, alert.menu_name, alert.table_name, alert.overdue_at
+        );
+    }
+    Ok(alerts.raised)
+}
+
+/// Scans for overdue items every `scan_interval` for as long as the server runs.
+pub fn spawn_overdue_scan(repo: Arc<dyn Repository>, clock: Arc<dyn Clock>, settings: AlertSettings) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticks = interval(settings.scan_interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = scan_overdue_items(repo.as_ref(), clock.as_ref(), settings.grace_minutes).await {
+                error!("Failed to scan for overdue items: {}", e);
+            }
+        }
+    })
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime};
+use uuid::Uuid;
+
+use crate::models::restaurant_models::{ItemStatus, KitchenQueueItem};
+
+/// The cook who frees up first, cooks with nothing to do before any other.
+fn next_cook(free_at: &[Option<NaiveDateTime>]) -> usize {
+    (0..free_at.len()).min_by_key(|&cook| free_at[cook]).unwrap_or(0)
+}
+
+/// When each item of the kitchen queue should be ready, by item id.
+///
+/// Which cook started an item is not recorded, so the items already in
+/// preparation are spread over the `cooks` in the order they were started,
+/// each keeping its cook busy until it should be ready. The ordered ones are
+/// then handed, in queue order, to whichever cook frees up first. An item
+/// takes its dish's `prep_time` once, whatever its quantity, as portions of a
+/// dish are cooked together. Items running late are expected any moment, so
+/// no estimate is before `now`.
+pub fn estimate_ready_times(queue: &[KitchenQueueItem], cooks: usize, now: NaiveDateTime) -> HashMap<Uuid, NaiveDateTime> {
+    let prep_time = |item: &KitchenQueueItem| Duration::minutes(i64::from(item.prep_time));
+    let mut free_at: Vec<Option<NaiveDateTime>> = vec![None; cooks.max(1)];
+    let mut estimates = HashMap::with_capacity(queue.len());
+
+    let mut started: Vec<&KitchenQueueItem> = queue.iter().filter(|item| item.status == ItemStatus::InPreparation).collect();
+    started.sort_by_key(|item| (item.started_at, item.id));
+    for item in started {
+        let cook = next_cook(&free_at);
+        let ready_at = item.started_at.unwrap_or(item.created_at) + prep_time(item);
+        free_at[cook] = free_at[cook].max(Some(ready_at));
+        estimates.insert(item.id, ready_at.max(now));
+    }
+
+    for item in queue.iter().filter(|item| item.status == ItemStatus::Ordered) {
+        let cook = next_cook(&free_at);
+        let start = free_at[cook].map_or(item.created_at, |at| at.max(item.created_at));
+        let ready_at = start + prep_time(item);
+        free_at[cook] = Some(ready_at);
+        estimates.insert(item.id, ready_at.max(now));
+    }
+
+    for item in queue.iter().filter(|item| item.status == ItemStatus::Ready) {
+        estimates.insert(item.id, item.ready_at.unwrap_or(now));
+    }
+
+    estimates
+}
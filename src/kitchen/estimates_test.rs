@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+    use uuid::Uuid;
+
+    use crate::kitchen::estimates::estimate_ready_times;
+    use crate::models::restaurant_models::{ItemStatus, KitchenQueueItem};
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 9, 2).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn item(created_at: NaiveDateTime, prep_time: i32, status: ItemStatus) -> KitchenQueueItem {
+        KitchenQueueItem {
+            id: Uuid::new_v4(),
+            tables_id: Uuid::new_v4(),
+            table_name: "Terrace".to_string(),
+            menu_id: Uuid::new_v4(),
+            menu_name: "Dish".to_string(),
+            quantity: 2,
+            delivered_quantity: 0,
+            status,
+            created_at,
+            started_at: None,
+            ready_at: None,
+            prep_time,
+            due_at: created_at + Duration::minutes(i64::from(prep_time)),
+        }
+    }
+
+    #[test]
+    fn test_items_wait_for_a_free_cook() {
+        let queue = vec![
+            item(at(12, 0), 10, ItemStatus::Ordered),
+            item(at(12, 0), 20, ItemStatus::Ordered),
+            item(at(12, 5), 10, ItemStatus::Ordered),
+        ];
+
+        let estimates = estimate_ready_times(&queue, 1, at(12, 0));
+        assert_eq!(estimates[&queue[0].id], at(12, 10));
+        assert_eq!(estimates[&queue[1].id], at(12, 30));
+        assert_eq!(estimates[&queue[2].id], at(12, 40));
+
+        let estimates = estimate_ready_times(&queue, 2, at(12, 0));
+        assert_eq!(estimates[&queue[0].id], at(12, 10));
+        assert_eq!(estimates[&queue[1].id], at(12, 20));
+        assert_eq!(estimates[&queue[2].id], at(12, 20));
+
+        // An idle kitchen has each item ready its prep time after it was ordered.
+        let estimates = estimate_ready_times(&queue, 3, at(12, 0));
+        assert_eq!(estimates[&queue[2].id], at(12, 15));
+    }
+
+    #[test]
+    fn test_started_and_late_items() {
+        let mut started = item(at(12, 0), 15, ItemStatus::InPreparation);
+        started.started_at = Some(at(12, 10));
+        let mut ready = item(at(11, 50), 5, ItemStatus::Ready);
+        ready.ready_at = Some(at(12, 2));
+        let late = item(at(11, 40), 10, ItemStatus::Ordered);
+        let queue = vec![late.clone(), ready.clone(), started.clone()];
+
+        let estimates = estimate_ready_times(&queue, 1, at(12, 30));
+        assert_eq!(estimates[&started.id], at(12, 30));
+        assert_eq!(estimates[&ready.id], at(12, 2));
+        assert_eq!(estimates[&late.id], at(12, 35));
+
+        let estimates = estimate_ready_times(&queue, 2, at(12, 12));
+        assert_eq!(estimates[&started.id], at(12, 25));
+        assert_eq!(estimates[&late.id], at(12, 12));
+    }
+}
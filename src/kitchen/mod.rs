@@ -0,0 +1,3 @@
+pub mod alerts;
+pub mod estimates;
+mod estimates_test;
\ No newline at end of file
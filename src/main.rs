@@ -1,14 +1,26 @@
+mod clock;
 mod config;
 mod routes;
 mod models;
 mod db;
+mod billing;
+mod error;
+mod validation;
+mod receipts;
+mod reports;
+mod kitchen;
+mod events;
 
 use std::sync::Arc;
 
+use clock::{Clock, SystemClock};
 use config::Config;
-use db::connection::Database;
+use db::{connection::Database, publishing::PublishingRepository};
+use chrono::Utc;
 use dotenv::dotenv;
-use routes::create_router;
+use events::items::ItemEvents;
+use kitchen::alerts::spawn_overdue_scan;
+use routes::{create_router, state::AppState};
 use anyhow::{Context, Result};
 use log::error;
 
@@ -28,7 +40,21 @@ async fn main() -> Result<()> {
         .context("Failed to create database connection")?;
     let db = Arc::new(db);
 
-    let app = create_router(db.clone());
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    // Numbering events from the start time keeps them increasing across restarts.
+    let events = Arc::new(ItemEvents::new(Utc::now().timestamp_micros() as u64));
+
+    let state = AppState {
+        repo: Arc::new(PublishingRepository::new(db, events.clone(), clock.clone())),
+        tax_rate: config.tax_rate,
+        clock,
+        receipt: Arc::new(config.receipt),
+        kitchen_cooks: config.kitchen_cooks,
+        events,
+    };
+
+    spawn_overdue_scan(state.repo.clone(), state.clock.clone(), config.alerts);
+    let app = create_router(state);
 
     let listener = tokio::net::TcpListener::bind(format!("{}:{}", config.host, config.port))
         .await
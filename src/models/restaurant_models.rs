@@ -1,26 +1,33 @@
-use chrono::NaiveDateTime;
+use std::fmt;
+
+use chrono::{NaiveDateTime, NaiveTime};
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use rust_decimal::Decimal;
+use super::route_models::ZReportTotals;
 
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Device {
     pub id: Uuid,
     pub name: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Table {
     pub id: Uuid,
     pub name: String,
+    /// How many guests are seated, once known.
+    pub covers: Option<i32>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Items {
     pub id: Uuid,
     pub tables_id: Uuid,
     pub menu_id: Uuid,
+    pub menu_name: String,
+    pub unit_price: Decimal,
     pub quantity: i32,
     pub delivered_quantity: i32,
     pub delivered_at: Option<NaiveDateTime>,
@@ -30,6 +37,22 @@ pub struct Items {
     pub updated_by: Option<String>,
     pub deleted_at: Option<NaiveDateTime>,
     pub deleted_by: Option<String>,
+    pub session_id: Option<Uuid>,
+    pub paid_at: Option<NaiveDateTime>,
+    pub refunded_quantity: i32,
+    pub refunded_at: Option<NaiveDateTime>,
+    pub refunded_by: Option<String>,
+    pub z_report_id: Option<Uuid>,
+    pub status: ItemStatus,
+    pub started_at: Option<NaiveDateTime>,
+    pub ready_at: Option<NaiveDateTime>,
+    pub served_at: Option<NaiveDateTime>,
+    /// The tax category the item was charged in when its bill was settled,
+    /// with the category's name, rate and inclusiveness at the time.
+    pub tax_category_id: Option<Uuid>,
+    pub tax_name: Option<String>,
+    pub tax_rate: Option<Decimal>,
+    pub tax_inclusive: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -37,25 +60,370 @@ pub struct PartialItem {
     pub id: Uuid,
     pub tables_id: Uuid,
     pub menu_id: Uuid,
+    pub menu_name: String,
+    pub unit_price: Decimal,
     pub quantity: i32,
     pub delivered_quantity: i32
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct PartialItemReturn {
     pub id: Uuid,
     pub tables_id: Uuid,
     pub menu_id: Uuid,
+    pub menu_name: String,
+    pub unit_price: Decimal,
     pub quantity: i32,
     pub delivered_quantity: i32,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub paid_at: Option<NaiveDateTime>,
+    pub refunded_quantity: i32,
     pub created_at: NaiveDateTime,
-    pub prep_time: i32
+    pub prep_time: i32,
+    pub status: ItemStatus,
+    pub started_at: Option<NaiveDateTime>,
+    pub ready_at: Option<NaiveDateTime>,
+    pub served_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ItemDelivery {
+    pub id: Uuid,
+    pub items_id: Uuid,
+    pub quantity: i32,
+    pub delivered_at: NaiveDateTime,
+    pub delivered_by: Option<String>,
+}
+
+/// An item the kitchen still has to send out, across all tables. `due_at` is
+/// `prep_time` minutes after it was ordered.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KitchenQueueItem {
+    pub id: Uuid,
+    pub tables_id: Uuid,
+    pub table_name: String,
+    pub menu_id: Uuid,
+    pub menu_name: String,
+    pub quantity: i32,
+    pub delivered_quantity: i32,
+    pub status: ItemStatus,
+    pub created_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    pub ready_at: Option<NaiveDateTime>,
+    pub prep_time: i32,
+    pub due_at: NaiveDateTime,
+}
+
+/// Raised when an item is still waiting past its dish's `prep_time` plus the
+/// grace period, `overdue_at` being when that ran out. Delivering the item
+/// resolves it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ItemAlert {
+    pub id: Uuid,
+    pub items_id: Uuid,
+    pub tables_id: Uuid,
+    pub table_name: String,
+    pub menu_id: Uuid,
+    pub menu_name: String,
+    pub quantity: i32,
+    pub delivered_quantity: i32,
+    pub overdue_at: NaiveDateTime,
+    pub raised_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+/// Grace period of a dish, replacing the global one for its overdue alerts.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AlertThreshold {
+    pub menu_id: Uuid,
+    pub grace_minutes: i32,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
+pub struct DeletedItem {
+    pub id: Uuid,
+    pub tables_id: Uuid,
+    pub menu_id: Uuid,
+    pub quantity: i32,
+    pub delivered_quantity: i32,
+    pub deleted_at: NaiveDateTime,
+    pub deleted_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Menu {
     pub id: Uuid,
     pub name: String,
     pub price: Decimal,
     pub prep_time: i32,
+    pub retired_at: Option<NaiveDateTime>,
+    pub tax_category_id: Option<Uuid>,
+}
+
+/// Overrides a dish's price on the given days, `1` for Monday to `7` for
+/// Sunday, from `start_time` until `end_time`. A window that ends before it
+/// starts runs past midnight into the next day.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PriceRule {
+    pub id: Uuid,
+    pub menu_id: Uuid,
+    pub name: String,
+    pub price: Decimal,
+    pub days: Vec<i32>,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub created_at: NaiveDateTime,
+}
+
+/// An item as it is charged on the bill, at the price captured when it was ordered.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BillItem {
+    pub item_id: Uuid,
+    pub menu_id: Uuid,
+    pub name: String,
+    pub unit_price: Decimal,
+    pub quantity: i32,
+    pub tax_category_id: Option<Uuid>,
+}
+
+/// A tax rate dishes can be assigned to. With `inclusive` the rate is already
+/// part of the menu price, otherwise it is added on top.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaxCategory {
+    pub id: Uuid,
+    pub name: String,
+    pub rate: Decimal,
+    pub inclusive: bool,
+    pub created_at: NaiveDateTime,
+}
+
+/// A tax category as bills charge it: at its current rate, or at the one a
+/// settled item was paid at.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ChargedTax {
+    pub id: Uuid,
+    pub name: String,
+    pub rate: Decimal,
+    pub inclusive: bool,
+}
+
+impl From<TaxCategory> for ChargedTax {
+    fn from(category: TaxCategory) -> Self {
+        ChargedTax {
+            id: category.id,
+            name: category.name,
+            rate: category.rate,
+            inclusive: category.inclusive,
+        }
+    }
+}
+
+/// A service charge added to the bill of tables seating at least `min_covers` guests.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ServiceChargeRule {
+    pub id: Uuid,
+    pub name: String,
+    pub rate: Decimal,
+    pub min_covers: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum DiscountKind {
+    /// `value` is a percentage, `10` for 10% off.
+    Percent,
+    /// `value` is an amount of money taken off.
+    Fixed,
+}
+
+/// A discount on one item, or on the whole table when `items_id` is empty.
+/// Discounts are settled together with the table's session.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Discount {
+    pub id: Uuid,
+    pub tables_id: Uuid,
+    pub items_id: Option<Uuid>,
+    pub kind: DiscountKind,
+    pub value: Decimal,
+    pub reason: Option<String>,
+    pub promo_code_id: Option<Uuid>,
+    pub applied_by: String,
+    pub session_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Option<String>,
+}
+
+/// A named discount guests can redeem, optionally limited to a time window
+/// and a number of uses.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PromoCode {
+    pub id: Uuid,
+    pub code: String,
+    pub kind: DiscountKind,
+    pub value: Decimal,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub created_at: NaiveDateTime,
+}
+
+/// One of the checks a table's bill is split into.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Check {
+    pub id: Uuid,
+    pub tables_id: Uuid,
+    pub name: String,
+    pub position: i32,
+    pub created_at: NaiveDateTime,
+    pub created_by: Option<String>,
+    /// Set once the session the check belongs to is closed.
+    pub session_id: Option<Uuid>,
+}
+
+/// The share of an item charged to a check, as a fraction of the whole item.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CheckItem {
+    pub check_id: Uuid,
+    pub items_id: Uuid,
+    pub share_numerator: i32,
+    pub share_denominator: i32,
+}
+
+/// One seating at a table, from the first payment until the bill is settled.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TableSession {
+    pub id: Uuid,
+    pub tables_id: Uuid,
+    pub opened_at: NaiveDateTime,
+    pub closed_at: Option<NaiveDateTime>,
+    pub closed_by: Option<String>,
+    /// Sequential number printed on the session's receipt.
+    pub receipt_number: i64,
+    /// How many guests were seated when the session was settled.
+    pub covers: Option<i32>,
+    /// The Z report the session was settled before, once its day is closed.
+    pub z_report_id: Option<Uuid>,
+    /// The rate dishes outside any tax category were taxed at when the
+    /// session was settled.
+    pub tax_rate: Option<Decimal>,
+    /// The service charge rule the session was settled with, with its name
+    /// and rate at the time.
+    pub service_charge_rule_id: Option<Uuid>,
+    pub service_charge_name: Option<String>,
+    pub service_charge_rate: Option<Decimal>,
+}
+
+/// Where an item is between the kitchen and the table. Items go through the
+/// statuses in order, one step at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum ItemStatus {
+    Ordered,
+    InPreparation,
+    Ready,
+    Served,
+}
+
+impl ItemStatus {
+    /// The status an item can move on to from this one.
+    pub fn next(self) -> Option<ItemStatus> {
+        match self {
+            ItemStatus::Ordered => Some(ItemStatus::InPreparation),
+            ItemStatus::InPreparation => Some(ItemStatus::Ready),
+            ItemStatus::Ready => Some(ItemStatus::Served),
+            ItemStatus::Served => None,
+        }
+    }
+
+    /// The status an item moves to when its `(quantity, delivered_quantity)`
+    /// change from `before` to `after`. Only ready items can be delivered,
+    /// moving on to served once fully delivered, and served items can no
+    /// longer change. A change the status does not allow fails with it.
+    pub fn after_delivery(self, before: (i32, i32), after: (i32, i32)) -> Result<ItemStatus, ItemStatus> {
+        let (quantity, delivered_quantity) = after;
+        match self {
+            _ if before == after => Ok(self),
+            ItemStatus::Ready if delivered_quantity >= quantity => self.next().ok_or(self),
+            ItemStatus::Ready => Ok(self),
+            ItemStatus::Served => Err(self),
+            _ if delivered_quantity != before.1 => Err(self),
+            _ => Ok(self),
+        }
+    }
+}
+
+impl fmt::Display for ItemStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ItemStatus::Ordered => "ordered",
+            ItemStatus::InPreparation => "in preparation",
+            ItemStatus::Ready => "ready",
+            ItemStatus::Served => "served",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum Tender {
+    Cash,
+    Card,
+    Voucher,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+pub enum RefundReason {
+    Quality,
+    WrongItem,
+    Overcharge,
+    Goodwill,
+    Other,
+}
+
+/// A payment towards a table's bill. `amount` is what goes towards the bill,
+/// `tendered` what was handed over and `change` what was given back. Refunds
+/// are recorded as payments with negative amounts that point at the payment
+/// they refund.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Payment {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub check_id: Option<Uuid>,
+    pub tender: Tender,
+    pub amount: Decimal,
+    pub tip: Decimal,
+    pub tendered: Decimal,
+    pub change: Decimal,
+    pub refund_of: Option<Uuid>,
+    pub reason: Option<RefundReason>,
+    pub approved_by: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Option<String>,
+}
+
+/// A paid item and how much of it has been refunded so far.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RefundableItem {
+    pub item_id: Uuid,
+    pub quantity: i32,
+    pub refunded_quantity: i32,
+}
+
+/// A closed business day. `totals` are kept as they were worked out at the
+/// close, and `opened_at` is when the previous day was closed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ZReport {
+    pub id: Uuid,
+    pub number: i64,
+    pub opened_at: Option<NaiveDateTime>,
+    pub closed_at: NaiveDateTime,
+    pub closed_by: Option<String>,
+    pub totals: ZReportTotals,
 }
\ No newline at end of file
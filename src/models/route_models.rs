@@ -1,6 +1,11 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use super::restaurant_models::PartialItem;
+use super::restaurant_models::{DiscountKind, ItemStatus, PartialItem, PartialItemReturn, Payment, Tender};
+
+pub const DEFAULT_ITEMS_LIMIT: usize = 10;
+pub const MAX_ITEMS_LIMIT: usize = 100;
 
 #[derive(Debug, Deserialize)]
 pub struct Pagination {
@@ -8,22 +13,303 @@ pub struct Pagination {
     pub offset: Option<usize>,
 }
 
+impl Pagination {
+    /// Requested page size, defaulting to `DEFAULT_ITEMS_LIMIT` and capped at `MAX_ITEMS_LIMIT`.
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_ITEMS_LIMIT).clamp(1, MAX_ITEMS_LIMIT)
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset.unwrap_or(0)
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct FilterParams {
     pub menu_id: Option<Uuid>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct MenuListParams {
+    pub include_retired: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AlertListParams {
+    pub include_resolved: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeletedItemsParams {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
 #[derive(Serialize)]
 pub struct BulkNewItemResponse {
     pub items: Vec<PartialItem>,
 }
 
+/// An item with when the kitchen expects it to be ready, or `None` once
+/// nothing of it is left to send out.
+#[derive(Serialize)]
+pub struct EstimatedItem {
+    #[serde(flatten)]
+    pub item: PartialItemReturn,
+    pub estimated_ready_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct PaginatedItemsResponse {
+    pub items: Vec<EstimatedItem>,
+    pub total: i64,
+    pub limit: usize,
+    pub offset: usize,
+    pub next_offset: Option<usize>,
+}
+
 #[derive(Serialize)]
 pub struct ErrorResponse {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FieldError {
+    pub index: usize,
+    pub field: String,
     pub message: String,
 }
 
 #[derive(Serialize)]
 pub struct SuccessResponse {
     pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BillLine {
+    pub item_id: Uuid,
+    pub menu_id: Uuid,
+    pub name: String,
+    pub unit_price: Decimal,
+    pub quantity: i32,
+    pub line_total: Decimal,
+    /// What item discounts take off this line.
+    pub discount: Decimal,
+    pub tax_category_id: Option<Uuid>,
+}
+
+/// A discount as applied to the bill. `item_id` is empty for table discounts.
+#[derive(Debug, Serialize)]
+pub struct DiscountLine {
+    pub discount_id: Uuid,
+    pub item_id: Option<Uuid>,
+    pub reason: Option<String>,
+    pub kind: DiscountKind,
+    pub value: Decimal,
+    pub amount: Decimal,
+    pub applied_by: String,
+}
+
+/// Tax charged at one rate. Lines without a tax category are taxed at the
+/// default rate, reported without a `tax_category_id`. `taxable` is the amount
+/// the rate applies to, which for inclusive prices is the line totals less the
+/// tax they contain.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaxLine {
+    pub tax_category_id: Option<Uuid>,
+    pub name: String,
+    pub rate: Decimal,
+    pub inclusive: bool,
+    pub taxable: Decimal,
+    pub tax: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceChargeLine {
+    pub rule_id: Uuid,
+    pub name: String,
+    pub rate: Decimal,
+    pub amount: Decimal,
+}
+
+/// `subtotal` adds up the line totals as priced on the menu, and `discount`
+/// what the discounts take off it. `tax` covers both inclusive and exclusive
+/// taxes, but only exclusive taxes and the service charge are added on top of
+/// the discounted subtotal to make the total.
+#[derive(Debug, Serialize)]
+pub struct BillResponse {
+    pub tables_id: Uuid,
+    pub covers: Option<i32>,
+    pub lines: Vec<BillLine>,
+    pub subtotal: Decimal,
+    pub discounts: Vec<DiscountLine>,
+    pub discount: Decimal,
+    pub taxes: Vec<TaxLine>,
+    pub tax: Decimal,
+    pub service_charge: Option<ServiceChargeLine>,
+    pub total: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckLine {
+    pub item_id: Uuid,
+    pub name: String,
+    pub unit_price: Decimal,
+    pub quantity: i32,
+    pub share_numerator: i32,
+    pub share_denominator: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub lines: Vec<CheckLine>,
+    pub subtotal: Decimal,
+    pub tax: Decimal,
+    pub service_charge: Decimal,
+    pub total: Decimal,
+    pub paid: Decimal,
+    pub balance: Decimal,
+}
+
+/// Whatever part of the bill is not on any check yet.
+#[derive(Debug, Serialize)]
+pub struct UnassignedAmount {
+    pub subtotal: Decimal,
+    pub tax: Decimal,
+    pub service_charge: Decimal,
+    pub total: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChecksResponse {
+    pub tables_id: Uuid,
+    pub checks: Vec<CheckSummary>,
+    pub unassigned: UnassignedAmount,
+    pub subtotal: Decimal,
+    pub discount: Decimal,
+    pub tax: Decimal,
+    pub service_charge: Decimal,
+    pub total: Decimal,
+}
+
+/// Payments made in the table's current session. Once `balance` reaches zero the
+/// session is closed, and the response describes the session that was just closed.
+#[derive(Debug, Serialize)]
+pub struct PaymentsResponse {
+    pub tables_id: Uuid,
+    pub session_id: Option<Uuid>,
+    pub payments: Vec<Payment>,
+    pub total: Decimal,
+    pub paid: Decimal,
+    pub tips: Decimal,
+    pub balance: Decimal,
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiptFormat {
+    #[default]
+    Text,
+    Escpos,
+}
+
+/// Without a `session_id` the receipt is for the table's current bill, or for
+/// its last settled bill when nothing has been ordered since.
+#[derive(Debug, Deserialize)]
+pub struct ReceiptParams {
+    pub format: Option<ReceiptFormat>,
+    pub session_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DailyReportParams {
+    pub date: Option<NaiveDate>,
+}
+
+/// Takings for one tender. `refunds` counts refunded amounts and tips as a
+/// positive figure, and `net` is `sales + tips - refunds`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TenderTotals {
+    pub tender: Tender,
+    pub payments: usize,
+    pub sales: Decimal,
+    pub tips: Decimal,
+    pub refunds: usize,
+    pub refunded: Decimal,
+    pub net: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DailyTotals {
+    pub date: NaiveDate,
+    pub tenders: Vec<TenderTotals>,
+    pub sales: Decimal,
+    pub tips: Decimal,
+    pub refunded: Decimal,
+    pub net: Decimal,
+}
+
+/// What one dish sold, at the prices it was ordered at before discounts.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DishSales {
+    pub menu_id: Uuid,
+    pub name: String,
+    pub quantity: i64,
+    pub gross: Decimal,
+}
+
+/// Items deleted from bills, at the price they were ordered at.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VoidTotals {
+    pub items: usize,
+    pub quantity: i64,
+    pub value: Decimal,
+}
+
+/// Everything a business day sold and took. Sales come from the bills settled
+/// during the day, so `gross_sales - discounts + service_charges` plus the
+/// exclusive taxes make `total_sales`. Payments and refunds are every one taken
+/// during the day, per tender as in the daily report.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ZReportTotals {
+    pub sessions: usize,
+    pub covers: i64,
+    pub dishes: Vec<DishSales>,
+    pub gross_sales: Decimal,
+    pub discounts: Decimal,
+    pub service_charges: Decimal,
+    pub taxes: Vec<TaxLine>,
+    pub tax: Decimal,
+    pub total_sales: Decimal,
+    pub tenders: Vec<TenderTotals>,
+    pub paid: Decimal,
+    pub tips: Decimal,
+    pub refunded: Decimal,
+    pub net: Decimal,
+    pub voids: VoidTotals,
+}
+
+#[derive(Serialize)]
+pub struct PendingItemEstimate {
+    pub id: Uuid,
+    pub menu_name: String,
+    pub quantity: i32,
+    pub delivered_quantity: i32,
+    pub status: ItemStatus,
+    pub estimated_ready_at: NaiveDateTime,
+}
+
+/// When the food of a table should arrive: `estimated_ready_at` is when its
+/// last pending item should be ready, `None` when nothing is pending.
+#[derive(Serialize)]
+pub struct TableEstimate {
+    pub tables_id: Uuid,
+    pub estimated_ready_at: Option<NaiveDateTime>,
+    pub items: Vec<PendingItemEstimate>,
 }
\ No newline at end of file
@@ -0,0 +1,62 @@
+use crate::receipts::receipt::{align, truncate, ReceiptLine};
+
+const ESC: u8 = 0x1b;
+const GS: u8 = 0x1d;
+const LF: u8 = b'\n';
+
+const INITIALIZE: [u8; 2] = [ESC, b'@'];
+const ALIGN_LEFT: [u8; 3] = [ESC, b'a', 0];
+const ALIGN_CENTER: [u8; 3] = [ESC, b'a', 1];
+const BOLD_ON: [u8; 3] = [ESC, b'E', 1];
+const BOLD_OFF: [u8; 3] = [ESC, b'E', 0];
+const NORMAL_SIZE: [u8; 3] = [GS, b'!', 0x00];
+const DOUBLE_HEIGHT: [u8; 3] = [GS, b'!', 0x01];
+const DOUBLE_SIZE: [u8; 3] = [GS, b'!', 0x11];
+/// Feeds the paper past the cutter and makes a partial cut.
+const FEED_AND_CUT: [u8; 7] = [ESC, b'd', 3, GS, b'V', 66, 0];
+
+/// Printers are left in their default code page, so anything outside ASCII
+/// is printed as `?`.
+fn push_text(bytes: &mut Vec<u8>, text: &str) {
+    bytes.extend(text.chars().map(|c| if c.is_ascii() && !c.is_ascii_control() { c as u8 } else { b'?' }));
+    bytes.push(LF);
+}
+
+/// Renders the receipt as ESC/POS commands for thermal printers, `width`
+/// characters to a line in the normal font. The title is printed at double
+/// size and the total at double height.
+pub fn render_escpos(lines: &[ReceiptLine], width: usize) -> Vec<u8> {
+    let mut bytes = INITIALIZE.to_vec();
+    for line in lines {
+        match line {
+            ReceiptLine::Title(title) => {
+                bytes.extend(ALIGN_CENTER);
+                bytes.extend(BOLD_ON);
+                bytes.extend(DOUBLE_SIZE);
+                push_text(&mut bytes, &truncate(title, width / 2));
+                bytes.extend(NORMAL_SIZE);
+                bytes.extend(BOLD_OFF);
+                bytes.extend(ALIGN_LEFT);
+            }
+            ReceiptLine::Centered(text) => {
+                bytes.extend(ALIGN_CENTER);
+                push_text(&mut bytes, &truncate(text, width));
+                bytes.extend(ALIGN_LEFT);
+            }
+            ReceiptLine::Text(text) => push_text(&mut bytes, &truncate(text, width)),
+            ReceiptLine::Row(left, right) => push_text(&mut bytes, &align(left, right, width)),
+            ReceiptLine::Total(left, right) => {
+                bytes.extend(BOLD_ON);
+                bytes.extend(DOUBLE_HEIGHT);
+                push_text(&mut bytes, &align(left, right, width));
+                bytes.extend(NORMAL_SIZE);
+                bytes.extend(BOLD_OFF);
+            }
+            ReceiptLine::Rule => push_text(&mut bytes, &"-".repeat(width)),
+            ReceiptLine::DoubleRule => push_text(&mut bytes, &"=".repeat(width)),
+            ReceiptLine::Blank => bytes.push(LF),
+        }
+    }
+    bytes.extend(FEED_AND_CUT);
+    bytes
+}
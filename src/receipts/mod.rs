@@ -0,0 +1,3 @@
+pub mod escpos;
+pub mod receipt;
+mod receipt_test;
\ No newline at end of file
@@ -0,0 +1,173 @@
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+
+use crate::config::ReceiptSettings;
+use crate::models::{
+    restaurant_models::{Payment, Tender},
+    route_models::BillResponse,
+};
+
+/// Everything printed on a receipt. Receipts for bills that have not been paid
+/// towards yet have no number.
+pub struct Receipt {
+    pub number: Option<i64>,
+    pub table_name: String,
+    pub printed_at: NaiveDateTime,
+    pub bill: BillResponse,
+    pub payments: Vec<Payment>,
+    /// Whether the bill has been paid in full, so no balance is left to show.
+    pub settled: bool,
+}
+
+/// A receipt line, independent of how it ends up printed.
+#[derive(Debug, PartialEq)]
+pub enum ReceiptLine {
+    /// The restaurant name, printed large.
+    Title(String),
+    Centered(String),
+    Text(String),
+    /// Text on the left with an amount aligned right.
+    Row(String, String),
+    /// The grand total, printed emphasized.
+    Total(String, String),
+    Rule,
+    DoubleRule,
+    Blank,
+}
+
+fn tender_name(tender: Tender) -> &'static str {
+    match tender {
+        Tender::Cash => "Cash",
+        Tender::Card => "Card",
+        Tender::Voucher => "Voucher",
+    }
+}
+
+/// A rate as a percentage, `0.125` as `12.5%`.
+fn percent(rate: Decimal) -> String {
+    format!("{}%", (rate * Decimal::ONE_HUNDRED).normalize())
+}
+
+fn negative(amount: Decimal) -> String {
+    format!("-{}", amount)
+}
+
+fn row(left: impl Into<String>, right: impl ToString) -> ReceiptLine {
+    ReceiptLine::Row(left.into(), right.to_string())
+}
+
+/// Lays out the receipt: header, items with their discounts, totals, payments
+/// and footer.
+pub fn receipt_lines(receipt: &Receipt, settings: &ReceiptSettings) -> Vec<ReceiptLine> {
+    let bill = &receipt.bill;
+    let mut lines = vec![ReceiptLine::Title(settings.name.clone())];
+    lines.extend(settings.header.iter().cloned().map(ReceiptLine::Centered));
+    lines.push(ReceiptLine::Rule);
+
+    lines.push(match receipt.number {
+        Some(number) => row("Receipt", format!("#{:06}", number)),
+        None => ReceiptLine::Text("Provisional bill".to_string()),
+    });
+    lines.push(row(
+        receipt.table_name.as_str(),
+        bill.covers.map(|covers| format!("Covers {}", covers)).unwrap_or_default(),
+    ));
+    lines.push(ReceiptLine::Text(receipt.printed_at.format("%Y-%m-%d %H:%M").to_string()));
+    lines.push(ReceiptLine::Rule);
+
+    for line in &bill.lines {
+        lines.push(row(format!("{} x {}", line.quantity, line.name), line.line_total));
+        if line.quantity > 1 {
+            lines.push(ReceiptLine::Text(format!("    @ {}", line.unit_price)));
+        }
+        for discount in bill.discounts.iter().filter(|discount| discount.item_id == Some(line.item_id)) {
+            let reason = discount.reason.as_deref().unwrap_or("Discount");
+            lines.push(row(format!("    {}", reason), negative(discount.amount)));
+        }
+    }
+    lines.push(ReceiptLine::Rule);
+
+    lines.push(row("Subtotal", bill.subtotal));
+    for discount in bill.discounts.iter().filter(|discount| discount.item_id.is_none()) {
+        lines.push(row(discount.reason.as_deref().unwrap_or("Discount"), negative(discount.amount)));
+    }
+    for tax in &bill.taxes {
+        let included = if tax.inclusive { " incl." } else { "" };
+        lines.push(row(format!("{} {}{}", tax.name, percent(tax.rate), included), tax.tax));
+    }
+    if let Some(service_charge) = &bill.service_charge {
+        lines.push(row(format!("{} {}", service_charge.name, percent(service_charge.rate)), service_charge.amount));
+    }
+    lines.push(ReceiptLine::DoubleRule);
+    lines.push(ReceiptLine::Total("TOTAL".to_string(), bill.total.to_string()));
+
+    if !receipt.payments.is_empty() {
+        lines.push(ReceiptLine::Rule);
+        for payment in &receipt.payments {
+            let tender = tender_name(payment.tender);
+            match payment.refund_of {
+                Some(_) => lines.push(row(format!("Refund {}", tender), payment.amount)),
+                None => lines.push(row(tender, payment.amount)),
+            }
+            if !payment.tip.is_zero() {
+                lines.push(row("    Tip", payment.tip));
+            }
+            if !payment.change.is_zero() {
+                lines.push(row("    Change", payment.change));
+            }
+        }
+        if !receipt.settled {
+            let paid: Decimal = receipt.payments.iter().map(|payment| payment.amount).sum();
+            lines.push(row("Balance due", bill.total - paid));
+        }
+    }
+
+    if !settings.footer.is_empty() {
+        lines.push(ReceiptLine::Blank);
+        lines.extend(settings.footer.iter().cloned().map(ReceiptLine::Centered));
+    }
+    lines
+}
+
+/// Cuts `text` down to at most `width` characters.
+pub fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+pub fn center(text: &str, width: usize) -> String {
+    let text = truncate(text, width);
+    let padding = (width - text.chars().count()) / 2;
+    format!("{}{}", " ".repeat(padding), text).trim_end().to_string()
+}
+
+/// `left` and `right` on one line, with `left` shortened to keep at least one
+/// space before `right`.
+pub fn align(left: &str, right: &str, width: usize) -> String {
+    let right = truncate(right, width);
+    let right_width = right.chars().count();
+    if right_width == 0 {
+        return truncate(left, width);
+    }
+    let left = truncate(left, width.saturating_sub(right_width + 1));
+    let padding = width - left.chars().count() - right_width;
+    format!("{}{}{}", left, " ".repeat(padding), right)
+}
+
+/// Renders the receipt as fixed-width text, `width` characters to a line.
+pub fn render_text(lines: &[ReceiptLine], width: usize) -> String {
+    let mut text = String::new();
+    for line in lines {
+        let rendered = match line {
+            ReceiptLine::Title(title) => center(title, width),
+            ReceiptLine::Centered(line) => center(line, width),
+            ReceiptLine::Text(line) => truncate(line, width),
+            ReceiptLine::Row(left, right) | ReceiptLine::Total(left, right) => align(left, right, width),
+            ReceiptLine::Rule => "-".repeat(width),
+            ReceiptLine::DoubleRule => "=".repeat(width),
+            ReceiptLine::Blank => String::new(),
+        };
+        text.push_str(&rendered);
+        text.push('\n');
+    }
+    text
+}
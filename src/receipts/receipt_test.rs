@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use chrono::{NaiveDate, NaiveDateTime};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use crate::billing::bill::{build_bill, BillRules};
+    use crate::config::ReceiptSettings;
+    use crate::models::restaurant_models::{BillItem, ChargedTax, Discount, DiscountKind, Payment, ServiceChargeRule, Tender};
+    use crate::receipts::{
+        escpos::render_escpos,
+        receipt::{align, center, receipt_lines, render_text, Receipt},
+    };
+
+    const WIDTH: usize = 42;
+
+    fn printed_at() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 9, 6).unwrap().and_hms_opt(21, 15, 0).unwrap()
+    }
+
+    fn settings() -> ReceiptSettings {
+        ReceiptSettings {
+            name: "Chez Test".to_string(),
+            header: vec!["1 Harbour Street".to_string(), "VAT GB123456789".to_string()],
+            footer: vec!["Thank you for dining with us".to_string()],
+            width: WIDTH,
+        }
+    }
+
+    fn bill_item(name: &str, unit_price: Decimal, quantity: i32, tax_category_id: Option<Uuid>) -> BillItem {
+        BillItem {
+            item_id: Uuid::new_v4(),
+            menu_id: Uuid::new_v4(),
+            name: name.to_string(),
+            unit_price,
+            quantity,
+            tax_category_id,
+        }
+    }
+
+    fn discount(items_id: Option<Uuid>, kind: DiscountKind, value: Decimal, reason: &str) -> Discount {
+        Discount {
+            id: Uuid::new_v4(),
+            tables_id: Uuid::new_v4(),
+            items_id,
+            kind,
+            value,
+            reason: Some(reason.to_string()),
+            promo_code_id: None,
+            applied_by: "Alice".to_string(),
+            session_id: None,
+            created_at: printed_at(),
+            created_by: None,
+        }
+    }
+
+    fn payment(tender: Tender, amount: Decimal, tip: Decimal, change: Decimal) -> Payment {
+        Payment {
+            id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            check_id: None,
+            tender,
+            amount,
+            tip,
+            tendered: amount + tip + change,
+            change,
+            refund_of: None,
+            reason: None,
+            approved_by: None,
+            created_at: printed_at(),
+            created_by: None,
+        }
+    }
+
+    /// A settled table of four with a comped item, a table discount, two tax
+    /// rates and a service charge, paid by card and cash.
+    fn sample_receipt() -> Receipt {
+        let alcohol = ChargedTax {
+            id: Uuid::new_v4(),
+            name: "Alcohol".to_string(),
+            rate: Decimal::new(20, 2),
+            inclusive: true,
+        };
+        let rules = BillRules {
+            default_tax_rate: Decimal::new(10, 2),
+            tax_categories: vec![alcohol.clone()],
+            service_charges: vec![ServiceChargeRule {
+                id: Uuid::new_v4(),
+                name: "Service".to_string(),
+                rate: Decimal::new(125, 3),
+                min_covers: 4,
+                created_at: printed_at(),
+            }],
+        };
+        let steak = bill_item("Ribeye steak with peppercorn sauce and fries", Decimal::new(2450, 2), 2, None);
+        let dessert = bill_item("Crème brûlée", Decimal::new(750, 2), 1, None);
+        let wine = bill_item("House red", Decimal::new(2800, 2), 1, Some(alcohol.id));
+        let discounts = vec![
+            discount(Some(dessert.item_id), DiscountKind::Percent, Decimal::from(100), "Birthday"),
+            discount(None, DiscountKind::Percent, Decimal::from(10), "Promo code SUMMER"),
+        ];
+        let bill = build_bill(Uuid::new_v4(), Some(4), vec![steak, dessert, wine], &discounts, &rules);
+
+        Receipt {
+            number: Some(42),
+            table_name: "Terrace 3".to_string(),
+            printed_at: printed_at(),
+            payments: vec![
+                payment(Tender::Card, Decimal::new(4000, 2), Decimal::new(500, 2), Decimal::ZERO),
+                payment(Tender::Cash, bill.total - Decimal::new(4000, 2), Decimal::ZERO, Decimal::new(162, 2)),
+            ],
+            bill,
+            settled: true,
+        }
+    }
+
+    /// Compares against `src/receipts/golden/<name>`. Run the tests with
+    /// `UPDATE_GOLDEN=1` to rewrite the golden files after a deliberate change.
+    fn assert_golden(name: &str, actual: &[u8]) {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "src", "receipts", "golden", name].iter().collect();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = fs::read(&path).unwrap_or_else(|_| panic!("Golden file {} is missing", path.display()));
+        assert!(
+            expected == actual,
+            "{} differs from its golden file, rerun with UPDATE_GOLDEN=1 if the change is intended",
+            name
+        );
+    }
+
+    #[test]
+    fn test_rows_fit_the_paper() {
+        assert_eq!(align("Subtotal", "12.50", 20), "Subtotal       12.50");
+        assert_eq!(align("Ribeye steak with fries", "49.00", 20), "Ribeye steak w 49.00");
+        assert_eq!(align("Provisional", "", 8), "Provisio");
+        assert_eq!(center("Chez Test", 20), "     Chez Test");
+    }
+
+    #[test]
+    fn test_text_receipt_matches_golden_file() {
+        let receipt = sample_receipt();
+        let text = render_text(&receipt_lines(&receipt, &settings()), WIDTH);
+        assert!(text.lines().all(|line| line.chars().count() <= WIDTH));
+        assert_golden("receipt.txt", text.as_bytes());
+    }
+
+    #[test]
+    fn test_escpos_receipt_matches_golden_file() {
+        let receipt = sample_receipt();
+        let bytes = render_escpos(&receipt_lines(&receipt, &settings()), WIDTH);
+        assert!(bytes.starts_with(&[0x1b, b'@']));
+        assert!(bytes.iter().all(|byte| byte.is_ascii()));
+        assert_golden("receipt.escpos", &bytes);
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::NaiveDate;
+
+use crate::billing::bill::round_money;
+use crate::models::{
+    restaurant_models::{Payment, Tender},
+    route_models::{DailyTotals, TenderTotals},
+};
+
+const TENDERS: [Tender; 3] = [Tender::Cash, Tender::Card, Tender::Voucher];
+
+fn tender_totals(tender: Tender, payments: &[Payment]) -> TenderTotals {
+    let (refunds, payments): (Vec<&Payment>, Vec<&Payment>) = payments
+        .iter()
+        .filter(|payment| payment.tender == tender)
+        .partition(|payment| payment.refund_of.is_some());
+
+    let sales = round_money(payments.iter().map(|payment| payment.amount).sum());
+    let tips = round_money(payments.iter().map(|payment| payment.tip).sum());
+    let refunded = round_money(refunds.iter().map(|refund| -(refund.amount + refund.tip)).sum());
+
+    TenderTotals {
+        tender,
+        payments: payments.len(),
+        sales,
+        tips,
+        refunds: refunds.len(),
+        refunded,
+        net: round_money(sales + tips - refunded),
+    }
+}
+
+/// Totals payments and refunds per tender. Every tender is listed, even when
+/// it was not used.
+pub(super) fn totals_by_tender(payments: &[Payment]) -> Vec<TenderTotals> {
+    TENDERS.iter().map(|&tender| tender_totals(tender, payments)).collect()
+}
+
+/// Totals the payments and refunds taken on `date`, per tender.
+pub fn daily_totals(date: NaiveDate, payments: &[Payment]) -> DailyTotals {
+    let tenders = totals_by_tender(payments);
+
+    DailyTotals {
+        date,
+        sales: round_money(tenders.iter().map(|totals| totals.sales).sum()),
+        tips: round_money(tenders.iter().map(|totals| totals.tips).sum()),
+        refunded: round_money(tenders.iter().map(|totals| totals.refunded).sum()),
+        net: round_money(tenders.iter().map(|totals| totals.net).sum()),
+        tenders,
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use crate::models::restaurant_models::{Payment, RefundReason, Tender};
+    use crate::reports::daily::daily_totals;
+
+    fn payment(tender: Tender, amount: i64, tip: i64, refund_of: Option<Uuid>) -> Payment {
+        Payment {
+            id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            check_id: None,
+            tender,
+            amount: Decimal::new(amount, 2),
+            tip: Decimal::new(tip, 2),
+            tendered: Decimal::new(amount + tip, 2),
+            change: Decimal::ZERO,
+            refund_of,
+            reason: refund_of.map(|_| RefundReason::Overcharge),
+            approved_by: refund_of.map(|_| "manager".to_string()),
+            created_at: Utc::now().naive_utc(),
+            created_by: None,
+        }
+    }
+
+    #[test]
+    fn test_daily_totals_net_out_refunds_per_tender() {
+        let card = payment(Tender::Card, 4400, 300, None);
+        let payments = vec![
+            payment(Tender::Cash, 2000, 0, None),
+            payment(Tender::Card, -500, -100, Some(card.id)),
+            card,
+        ];
+
+        let totals = daily_totals(Utc::now().date_naive(), &payments);
+
+        let tenders: Vec<(Tender, usize, String, String, String)> = totals
+            .tenders
+            .iter()
+            .map(|tender| {
+                (
+                    tender.tender,
+                    tender.refunds,
+                    tender.sales.to_string(),
+                    tender.refunded.to_string(),
+                    tender.net.to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            tenders,
+            vec![
+                (Tender::Cash, 0, "20.00".to_string(), "0.00".to_string(), "20.00".to_string()),
+                (Tender::Card, 1, "44.00".to_string(), "6.00".to_string(), "41.00".to_string()),
+                (Tender::Voucher, 0, "0.00".to_string(), "0.00".to_string(), "0.00".to_string()),
+            ]
+        );
+        assert_eq!(totals.tips, Decimal::new(300, 2));
+        assert_eq!(totals.net.to_string(), "61.00");
+    }
+}
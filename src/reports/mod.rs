@@ -0,0 +1,4 @@
+pub mod daily;
+mod daily_test;
+pub mod z_report;
+mod z_report_test;
\ No newline at end of file
@@ -0,0 +1,89 @@
+use rust_decimal::Decimal;
+
+use crate::billing::bill::round_money;
+use crate::models::{
+    restaurant_models::{BillItem, Payment},
+    route_models::{BillResponse, DishSales, TaxLine, VoidTotals, ZReportTotals},
+};
+use crate::reports::daily::totals_by_tender;
+
+/// Quantity and gross sales per dish, best sellers first.
+fn dish_sales(bills: &[BillResponse]) -> Vec<DishSales> {
+    let mut dishes: Vec<DishSales> = Vec::new();
+    for line in bills.iter().flat_map(|bill| &bill.lines) {
+        match dishes.iter_mut().find(|dish| dish.menu_id == line.menu_id) {
+            Some(dish) => {
+                dish.quantity += i64::from(line.quantity);
+                dish.gross += line.line_total;
+            }
+            None => dishes.push(DishSales {
+                menu_id: line.menu_id,
+                name: line.name.clone(),
+                quantity: i64::from(line.quantity),
+                gross: line.line_total,
+            }),
+        }
+    }
+
+    for dish in dishes.iter_mut() {
+        dish.gross = round_money(dish.gross);
+    }
+    dishes.sort_by(|a, b| b.gross.cmp(&a.gross).then_with(|| a.name.cmp(&b.name)));
+    dishes
+}
+
+/// Tax charged per rate over all bills, in the order the rates were first charged.
+fn tax_totals(bills: &[BillResponse]) -> Vec<TaxLine> {
+    let mut taxes: Vec<TaxLine> = Vec::new();
+    for line in bills.iter().flat_map(|bill| &bill.taxes) {
+        let same_rate = |tax: &&mut TaxLine| {
+            tax.tax_category_id == line.tax_category_id && tax.rate == line.rate && tax.inclusive == line.inclusive
+        };
+        match taxes.iter_mut().find(same_rate) {
+            Some(tax) => {
+                tax.taxable = round_money(tax.taxable + line.taxable);
+                tax.tax = round_money(tax.tax + line.tax);
+            }
+            None => taxes.push(line.clone()),
+        }
+    }
+    taxes
+}
+
+fn void_totals(voids: &[BillItem]) -> VoidTotals {
+    VoidTotals {
+        items: voids.len(),
+        quantity: voids.iter().map(|item| i64::from(item.quantity)).sum(),
+        value: round_money(voids.iter().map(|item| item.unit_price * Decimal::from(item.quantity)).sum()),
+    }
+}
+
+/// Totals a business day from the bills settled, the payments and refunds
+/// taken and the items voided since the previous close.
+pub fn z_report_totals(bills: &[BillResponse], payments: &[Payment], voids: &[BillItem]) -> ZReportTotals {
+    let tenders = totals_by_tender(payments);
+
+    ZReportTotals {
+        sessions: bills.len(),
+        covers: bills.iter().filter_map(|bill| bill.covers).map(i64::from).sum(),
+        dishes: dish_sales(bills),
+        gross_sales: round_money(bills.iter().map(|bill| bill.subtotal).sum()),
+        discounts: round_money(bills.iter().map(|bill| bill.discount).sum()),
+        service_charges: round_money(
+            bills
+                .iter()
+                .filter_map(|bill| bill.service_charge.as_ref())
+                .map(|charge| charge.amount)
+                .sum(),
+        ),
+        taxes: tax_totals(bills),
+        tax: round_money(bills.iter().map(|bill| bill.tax).sum()),
+        total_sales: round_money(bills.iter().map(|bill| bill.total).sum()),
+        paid: round_money(tenders.iter().map(|totals| totals.sales).sum()),
+        tips: round_money(tenders.iter().map(|totals| totals.tips).sum()),
+        refunded: round_money(tenders.iter().map(|totals| totals.refunded).sum()),
+        net: round_money(tenders.iter().map(|totals| totals.net).sum()),
+        tenders,
+        voids: void_totals(voids),
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use crate::billing::bill::{build_bill, BillRules};
+    use crate::models::restaurant_models::{BillItem, ChargedTax, Discount, DiscountKind, Payment, RefundReason, ServiceChargeRule, Tender};
+    use crate::reports::z_report::z_report_totals;
+
+    fn bill_item(menu_id: Uuid, name: &str, unit_price: Decimal, quantity: i32, tax_category_id: Option<Uuid>) -> BillItem {
+        BillItem {
+            item_id: Uuid::new_v4(),
+            menu_id,
+            name: name.to_string(),
+            unit_price,
+            quantity,
+            tax_category_id,
+        }
+    }
+
+    fn payment(tender: Tender, amount: i64, tip: i64, refund_of: Option<Uuid>) -> Payment {
+        Payment {
+            id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            check_id: None,
+            tender,
+            amount: Decimal::new(amount, 2),
+            tip: Decimal::new(tip, 2),
+            tendered: Decimal::new(amount + tip, 2),
+            change: Decimal::ZERO,
+            refund_of,
+            reason: refund_of.map(|_| RefundReason::Quality),
+            approved_by: refund_of.map(|_| "manager".to_string()),
+            created_at: Utc::now().naive_utc(),
+            created_by: None,
+        }
+    }
+
+    #[test]
+    fn test_z_report_totals_sales_taxes_payments_and_voids() {
+        let alcohol = ChargedTax {
+            id: Uuid::new_v4(),
+            name: "Alcohol".to_string(),
+            rate: Decimal::new(20, 2),
+            inclusive: true,
+        };
+        let rules = BillRules {
+            default_tax_rate: Decimal::new(10, 2),
+            tax_categories: vec![alcohol.clone()],
+            service_charges: vec![ServiceChargeRule {
+                id: Uuid::new_v4(),
+                name: "Large party".to_string(),
+                rate: Decimal::new(10, 2),
+                min_covers: 4,
+                created_at: Utc::now().naive_utc(),
+            }],
+        };
+        let (steak, wine) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let couple = build_bill(
+            Uuid::new_v4(),
+            Some(2),
+            vec![
+                bill_item(steak, "Steak", Decimal::from(20), 2, None),
+                bill_item(wine, "Wine", Decimal::from(30), 1, Some(alcohol.id)),
+            ],
+            &[],
+            &rules,
+        );
+        let party_table = Uuid::new_v4();
+        let party = build_bill(
+            party_table,
+            Some(4),
+            vec![bill_item(steak, "Steak", Decimal::from(20), 1, None)],
+            &[Discount {
+                id: Uuid::new_v4(),
+                tables_id: party_table,
+                items_id: None,
+                kind: DiscountKind::Fixed,
+                value: Decimal::from(5),
+                reason: None,
+                promo_code_id: None,
+                applied_by: "Alice".to_string(),
+                session_id: None,
+                created_at: Utc::now().naive_utc(),
+                created_by: None,
+            }],
+            &rules,
+        );
+
+        let card = payment(Tender::Card, 7400, 500, None);
+        let payments = vec![payment(Tender::Cash, 2000, 0, None), payment(Tender::Card, -1000, -100, Some(card.id)), card];
+        let voids = vec![bill_item(Uuid::new_v4(), "Soup", Decimal::new(1250, 2), 2, None)];
+
+        let totals = z_report_totals(&[couple, party], &payments, &voids);
+        assert_eq!(totals.sessions, 2);
+        assert_eq!(totals.covers, 6);
+        let dishes: Vec<(&str, i64, String)> = totals
+            .dishes
+            .iter()
+            .map(|dish| (dish.name.as_str(), dish.quantity, dish.gross.to_string()))
+            .collect();
+        assert_eq!(dishes, vec![("Steak", 3, "60.00".to_string()), ("Wine", 1, "30.00".to_string())]);
+        assert_eq!(totals.gross_sales.to_string(), "90.00");
+        assert_eq!(totals.discounts.to_string(), "5.00");
+
+        let taxes: Vec<(&str, String)> = totals.taxes.iter().map(|tax| (tax.name.as_str(), tax.tax.to_string())).collect();
+        assert_eq!(taxes.len(), 2);
+        assert!(taxes.contains(&("Alcohol", "5.00".to_string())));
+        assert_eq!(totals.tax, totals.taxes.iter().map(|tax| tax.tax).sum::<Decimal>());
+        let exclusive_tax: Decimal = totals.taxes.iter().filter(|tax| !tax.inclusive).map(|tax| tax.tax).sum();
+        assert_eq!(
+            totals.total_sales,
+            totals.gross_sales - totals.discounts + totals.service_charges + exclusive_tax
+        );
+
+        assert_eq!(totals.paid.to_string(), "94.00");
+        assert_eq!(totals.tips.to_string(), "5.00");
+        assert_eq!(totals.refunded.to_string(), "11.00");
+        assert_eq!(totals.net.to_string(), "88.00");
+        assert_eq!(totals.tenders[1].refunds, 1);
+
+        assert_eq!(totals.voids.items, 1);
+        assert_eq!(totals.voids.quantity, 2);
+        assert_eq!(totals.voids.value.to_string(), "25.00");
+    }
+}
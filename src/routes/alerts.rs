@@ -0,0 +1,74 @@
+use std::sync::Arc;
+use crate::db::connection::AlertThresholdRequest;
+use crate::db::repository::Repository;
+use crate::error::{AppError, OrNotFound};
+use crate::models::route_models::AlertListParams;
+use crate::validation::field_error;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use uuid::Uuid;
+
+pub async fn alerts_list(
+    Query(params): Query<AlertListParams>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let alerts = repo.get_alerts(params.include_resolved.unwrap_or(false)).await?;
+    info!("{} alerts found", alerts.len());
+
+    Ok(Json(alerts))
+}
+
+async fn ensure_menu_exists(repo: &dyn Repository, menu_id: Uuid) -> Result<(), AppError> {
+    repo.get_menu_item(menu_id)
+        .await
+        .or_not_found(|| format!("Menu item with id {} not found", menu_id))?;
+    Ok(())
+}
+
+pub async fn alert_threshold_get(
+    Path(menu_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    ensure_menu_exists(repo.as_ref(), menu_id).await?;
+    let threshold = repo.get_alert_threshold(menu_id).await?.ok_or_else(|| {
+        AppError::NotFound(format!("Menu item with id {} uses the default alert threshold", menu_id))
+    })?;
+
+    Ok(Json(threshold))
+}
+
+pub async fn alert_threshold_set(
+    Path(menu_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+    Json(request): Json<AlertThresholdRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    if request.grace_minutes < 0 {
+        return Err(AppError::Validation {
+            message: "Alert threshold is invalid".to_string(),
+            errors: vec![field_error(0, "grace_minutes", "Grace period cannot be negative")],
+        });
+    }
+    ensure_menu_exists(repo.as_ref(), menu_id).await?;
+
+    info!("Setting alert threshold of dish {} to {} minutes", menu_id, request.grace_minutes);
+    let threshold = repo.set_alert_threshold(menu_id, request.grace_minutes).await?;
+
+    Ok(Json(threshold))
+}
+
+pub async fn alert_threshold_delete(
+    Path(menu_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Removing alert threshold of dish {}", menu_id);
+    if !repo.delete_alert_threshold(menu_id).await? {
+        return Err(AppError::NotFound(format!("Menu item with id {} has no alert threshold", menu_id)));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,82 @@
+use crate::billing::bill::{build_bill, BillRules};
+use crate::db::connection::{SettledItem, Settlement};
+use crate::error::{AppError, OrNotFound};
+use crate::models::{restaurant_models::{ChargedTax, TableSession}, route_models::BillResponse};
+use crate::routes::state::AppState;
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use uuid::Uuid;
+
+/// The tax categories and service charge rules bills are priced with.
+pub(super) async fn bill_rules(state: &AppState) -> Result<BillRules, AppError> {
+    Ok(BillRules {
+        default_tax_rate: state.tax_rate,
+        tax_categories: state.repo.get_tax_categories().await?.into_iter().map(ChargedTax::from).collect(),
+        service_charges: state.repo.get_service_charge_rules().await?,
+    })
+}
+
+/// Builds the bill for what the table has not paid yet. Fails with `404`
+/// unless the table exists.
+pub(super) async fn current_bill(state: &AppState, tables_id: Uuid) -> Result<BillResponse, AppError> {
+    let rules = bill_rules(state).await?;
+    priced_bill(state, tables_id, &rules).await
+}
+
+/// Builds the bill for what the table has not paid yet with the given rules.
+pub(super) async fn priced_bill(state: &AppState, tables_id: Uuid, rules: &BillRules) -> Result<BillResponse, AppError> {
+    let table = state
+        .repo
+        .get_table(tables_id)
+        .await
+        .or_not_found(|| format!("Table with id {} not found", tables_id))?;
+
+    let items = state.repo.get_bill_items(tables_id).await?;
+    let discounts = state.repo.get_discounts(tables_id).await?;
+    Ok(build_bill(tables_id, table.covers, items, &discounts, rules))
+}
+
+/// What paying off `bill` settles: its items with the tax category each was
+/// charged in, and the rate and service charge it was priced with.
+pub(super) fn settlement(bill: &BillResponse, rules: &BillRules) -> Settlement {
+    let tax_category = |tax_category_id: Option<Uuid>| {
+        rules.tax_categories.iter().find(|category| Some(category.id) == tax_category_id).cloned()
+    };
+    Settlement {
+        items: bill
+            .lines
+            .iter()
+            .map(|line| SettledItem {
+                item_id: line.item_id,
+                tax_category: tax_category(line.tax_category_id),
+            })
+            .collect(),
+        tax_rate: rules.default_tax_rate,
+        service_charge: rules.service_charge_for(bill.covers).cloned(),
+    }
+}
+
+/// Rebuilds the bill a closed session paid, from the items and discounts
+/// settled with it, the covers seated and the rates it was settled at.
+pub(super) async fn settled_bill(state: &AppState, session: &TableSession) -> Result<BillResponse, AppError> {
+    let items = state.repo.get_settled_bill_items(session.id).await?;
+    let discounts = state.repo.get_settled_discounts(session.id).await?;
+    let tax_categories = state.repo.get_settled_taxes(session.id).await?;
+    let rules = BillRules::settled(session, tax_categories, state.tax_rate);
+    Ok(build_bill(session.tables_id, session.covers, items, &discounts, &rules))
+}
+
+pub async fn table_bill(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Building bill for table {}", tables_id);
+    let bill = current_bill(&state, tables_id).await?;
+    info!("Bill for table {} has {} lines, total {}", tables_id, bill.lines.len(), bill.total);
+
+    Ok(Json(bill))
+}
@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+use crate::billing::checks::{even_split, split_bill};
+use crate::db::connection::SplitChecksRequest;
+use crate::error::AppError;
+use crate::models::route_models::{BillResponse, ChecksResponse};
+use crate::routes::{billing::current_bill, identity::Identity, payments::open_payments, state::AppState, tables::ensure_table};
+use crate::validation::checks::{validate_checks, MAX_CHECKS};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use uuid::Uuid;
+
+pub(super) async fn table_checks(state: &AppState, tables_id: Uuid, bill: &BillResponse) -> Result<ChecksResponse, AppError> {
+    let checks = state.repo.get_checks(tables_id).await?;
+    let check_items = state.repo.get_check_items(tables_id).await?;
+    let payments = open_payments(state.repo.as_ref(), tables_id).await?;
+
+    Ok(split_bill(bill, checks, check_items, &payments))
+}
+
+async fn ensure_no_check_payments(state: &AppState, tables_id: Uuid) -> Result<(), AppError> {
+    let payments = open_payments(state.repo.as_ref(), tables_id).await?;
+    if payments.iter().any(|payment| payment.check_id.is_some()) {
+        return Err(AppError::Conflict(format!(
+            "Checks of table with id {} have already been paid on and cannot be changed",
+            tables_id
+        )));
+    }
+    Ok(())
+}
+
+pub async fn checks_get(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Get checks for table {}", tables_id);
+    let bill = current_bill(&state, tables_id).await?;
+    Ok(Json(table_checks(&state, tables_id, &bill).await?))
+}
+
+pub async fn checks_split(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Identity,
+    Json(split): Json<SplitChecksRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Splitting bill of table {}", tables_id);
+    ensure_table(state.repo.as_ref(), tables_id).await?;
+    ensure_no_check_payments(&state, tables_id).await?;
+
+    let items = state.repo.get_bill_items(tables_id).await?;
+    let checks = match split {
+        SplitChecksRequest::Even(guests) => {
+            if !(1..=MAX_CHECKS).contains(&guests) {
+                return Err(AppError::invalid(format!(
+                    "A bill can be split evenly between 1 and {} guests",
+                    MAX_CHECKS
+                )));
+            }
+            even_split(guests, &items)
+        }
+        SplitChecksRequest::Checks(checks) => checks,
+    };
+
+    let item_ids: HashSet<Uuid> = items.iter().map(|item| item.item_id).collect();
+    let errors = validate_checks(&checks, &item_ids);
+    if !errors.is_empty() {
+        info!("Rejected {} invalid fields in split of table {}", errors.len(), tables_id);
+        return Err(AppError::Validation {
+            message: "Some checks are invalid".to_string(),
+            errors,
+        });
+    }
+
+    let created = state.repo.replace_checks(tables_id, checks, &identity.actor()).await?;
+    info!("Bill of table {} split into {} checks", tables_id, created.len());
+
+    let bill = current_bill(&state, tables_id).await?;
+    Ok(Json(table_checks(&state, tables_id, &bill).await?))
+}
+
+pub async fn checks_delete(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+    _identity: Identity,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Removing checks of table {}", tables_id);
+    ensure_table(state.repo.as_ref(), tables_id).await?;
+    ensure_no_check_payments(&state, tables_id).await?;
+
+    let removed = state.repo.delete_checks(tables_id).await?;
+    info!("{} checks removed from table {}", removed, tables_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
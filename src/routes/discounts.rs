@@ -0,0 +1,176 @@
+use std::sync::Arc;
+use crate::db::connection::{AddDiscountOutcome, NewDiscount, NewDiscountRequest, NewPromoCodeRequest};
+use crate::db::repository::Repository;
+use crate::error::AppError;
+use crate::models::route_models::FieldError;
+use crate::routes::{billing::current_bill, identity::Identity, payments::open_payments, state::AppState};
+use crate::validation::{
+    discounts::{promo_code_unusable, validate_new_discount, validate_promo_code},
+    field_error,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use uuid::Uuid;
+
+fn invalid_discount(errors: Vec<FieldError>) -> AppError {
+    AppError::Validation {
+        message: "Discount is invalid".to_string(),
+        errors,
+    }
+}
+
+/// Promo codes are matched without regard to case or surrounding spaces.
+fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// Discounts are fixed once payments have been taken against the bill.
+async fn ensure_not_being_paid(repo: &dyn Repository, tables_id: Uuid) -> Result<(), AppError> {
+    if !open_payments(repo, tables_id).await?.is_empty() {
+        return Err(AppError::Conflict(format!(
+            "Table with id {} is being paid, its discounts can no longer be changed",
+            tables_id
+        )));
+    }
+    Ok(())
+}
+
+pub async fn discounts_list(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Get discounts for table {}", tables_id);
+    let bill = current_bill(&state, tables_id).await?;
+
+    Ok(Json(bill.discounts))
+}
+
+pub async fn discount_create(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Identity,
+    Json(new_discount): Json<NewDiscountRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Applying discount to table {} by {}", tables_id, new_discount.applied_by);
+    let errors = validate_new_discount(&new_discount);
+    if !errors.is_empty() {
+        info!("Rejected {} invalid fields in discount for table {}", errors.len(), tables_id);
+        return Err(invalid_discount(errors));
+    }
+
+    let bill = current_bill(&state, tables_id).await?;
+    ensure_not_being_paid(state.repo.as_ref(), tables_id).await?;
+
+    if let Some(item_id) = new_discount.item_id {
+        if !bill.lines.iter().any(|line| line.item_id == item_id) {
+            if !state.repo.get_paid_item_ids(&[item_id]).await?.is_empty() {
+                return Err(AppError::Conflict(format!("Item with id {} has already been paid", item_id)));
+            }
+            return Err(invalid_discount(vec![field_error(
+                0,
+                "item_id",
+                format!("Item {} is not on the bill of this table", item_id),
+            )]));
+        }
+    }
+
+    let discount = match &new_discount.promo_code {
+        Some(code) => {
+            let code = normalize_code(code);
+            let promo_code_error = |message: String| {
+                invalid_discount(vec![field_error(0, "promo_code", message)])
+            };
+            let Some(promo_code) = state.repo.get_promo_code_by_code(&code).await? else {
+                return Err(promo_code_error(format!("Unknown promo code {}", code)));
+            };
+            if let Some(message) = promo_code_unusable(&promo_code, state.clock.now()) {
+                return Err(promo_code_error(message));
+            }
+            NewDiscount {
+                items_id: new_discount.item_id,
+                kind: promo_code.kind,
+                value: promo_code.value,
+                reason: new_discount.reason.clone().or_else(|| Some(format!("Promo code {}", promo_code.code))),
+                promo_code_id: Some(promo_code.id),
+                applied_by: new_discount.applied_by.trim().to_string(),
+            }
+        }
+        None => NewDiscount {
+            items_id: new_discount.item_id,
+            // Both are present, `validate_new_discount` checked them.
+            kind: new_discount.kind.expect("Validated discount kind"),
+            value: new_discount.value.expect("Validated discount value"),
+            reason: new_discount.reason.clone(),
+            promo_code_id: None,
+            applied_by: new_discount.applied_by.trim().to_string(),
+        },
+    };
+
+    match state.repo.add_discount(tables_id, discount, &identity.actor()).await? {
+        AddDiscountOutcome::PromoCodeUsedUp => Err(AppError::Conflict(format!(
+            "Promo code {} has been used up",
+            normalize_code(new_discount.promo_code.as_deref().unwrap_or_default())
+        ))),
+        AddDiscountOutcome::Added(discount) => {
+            info!("Discount {} applied to table {}", discount.id, tables_id);
+            let bill = current_bill(&state, tables_id).await?;
+            Ok((StatusCode::CREATED, Json(bill)))
+        }
+    }
+}
+
+pub async fn discount_delete(
+    Path((tables_id, discount_id)): Path<(Uuid, Uuid)>,
+    State(repo): State<Arc<dyn Repository>>,
+    _identity: Identity,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Removing discount {} from table {}", discount_id, tables_id);
+    ensure_not_being_paid(repo.as_ref(), tables_id).await?;
+    if !repo.delete_discount(tables_id, discount_id).await? {
+        return Err(AppError::NotFound(format!(
+            "Discount with id {} not found on the bill of table {}",
+            discount_id, tables_id
+        )));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn promo_codes_list(
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let promo_codes = repo.get_promo_codes().await?;
+    info!("{} promo codes found", promo_codes.len());
+
+    Ok(Json(promo_codes))
+}
+
+pub async fn promo_code_create(
+    State(repo): State<Arc<dyn Repository>>,
+    Json(new_promo_code): Json<NewPromoCodeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let errors = validate_promo_code(&new_promo_code);
+    if !errors.is_empty() {
+        return Err(AppError::Validation {
+            message: "Promo code is invalid".to_string(),
+            errors,
+        });
+    }
+
+    let code = normalize_code(&new_promo_code.code);
+    if repo.get_promo_code_by_code(&code).await?.is_some() {
+        return Err(AppError::Conflict(format!("Promo code {} already exists", code)));
+    }
+
+    info!("Adding promo code {}", code);
+    let promo_code = repo
+        .add_promo_code(NewPromoCodeRequest { code, ..new_promo_code })
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(promo_code)))
+}
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+use crate::error::{AppError, OrNotFound};
+use crate::events::items::{ItemEvent, ItemEvents, Missed};
+use crate::routes::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+};
+use futures_util::{future, stream, Stream, StreamExt};
+use log::info;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers.get("last-event-id")?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Sent instead of the events a client missed when they can no longer be
+/// replayed. The client has to refetch the items it shows.
+const RESYNC_EVENT: &str = "resync";
+
+/// What a stream sends: an item event, or a resync, with the id to resume
+/// after if there is one.
+enum Streamed {
+    Item(Box<ItemEvent>),
+    Resync(Option<u64>),
+}
+
+impl Streamed {
+    fn into_event(self) -> Result<Event, axum::Error> {
+        match self {
+            Streamed::Item(event) => Event::default().id(event.id.to_string()).event(event.kind.name()).json_data(&*event),
+            Streamed::Resync(id) => {
+                let resync = Event::default().event(RESYNC_EVENT).data("{}");
+                Ok(match id {
+                    Some(id) => resync.id(id.to_string()),
+                    None => resync,
+                })
+            }
+        }
+    }
+}
+
+/// Streams the item events of one table, or of all of them, starting with the
+/// kept events after `Last-Event-ID`. When some of those are no longer kept,
+/// or a client falls too far behind to be sent every event, it is sent a
+/// `resync` event instead and carries on with the events from then on.
+fn item_event_stream(
+    events: Arc<ItemEvents>,
+    last_event_id: Option<u64>,
+    tables_id: Option<Uuid>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let (missed, receiver) = events.subscribe(last_event_id);
+    let missed = match missed {
+        Missed::Events(events) => events.into_iter().map(|event| Streamed::Item(Box::new(event))).collect(),
+        Missed::Gap(latest_id) => vec![Streamed::Resync(Some(latest_id))],
+    };
+    let live = stream::unfold(receiver, |mut receiver| async move {
+        match receiver.recv().await {
+            Ok(event) => Some((Streamed::Item(Box::new(event)), receiver)),
+            Err(RecvError::Lagged(_)) => Some((Streamed::Resync(None), receiver)),
+            Err(RecvError::Closed) => None,
+        }
+    });
+
+    let stream = stream::iter(missed)
+        .chain(live)
+        .filter(move |streamed| {
+            future::ready(match (streamed, tables_id) {
+                (Streamed::Item(event), Some(tables_id)) => event.tables_id == tables_id,
+                _ => true,
+            })
+        })
+        .map(Streamed::into_event);
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+pub async fn item_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let last_event_id = last_event_id(&headers);
+    info!("Streaming item events after {:?}", last_event_id);
+
+    item_event_stream(state.events.clone(), last_event_id, None)
+}
+
+pub async fn table_item_events(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .repo
+        .get_table(tables_id)
+        .await
+        .or_not_found(|| format!("Table with id {} not found", tables_id))?;
+    let last_event_id = last_event_id(&headers);
+    info!("Streaming item events of table {} after {:?}", tables_id, last_event_id);
+
+    Ok(item_event_stream(state.events.clone(), last_event_id, Some(tables_id)))
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use crate::db::repository::Repository;
+use crate::error::AppError;
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use log::info;
+use uuid::Uuid;
+
+pub const DEVICE_ID_HEADER: &str = "x-device-id";
+
+/// The device a write request was made from, resolved against the `Device` table.
+#[derive(Debug)]
+pub struct Identity {
+    pub device_id: Uuid,
+}
+
+impl Identity {
+    /// Value recorded in the `created_by`, `updated_by` and `deleted_by` columns.
+    pub fn actor(&self) -> String {
+        self.device_id.to_string()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Identity
+where
+    Arc<dyn Repository>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let repo = Arc::<dyn Repository>::from_ref(state);
+        let header = parts
+            .headers
+            .get(DEVICE_ID_HEADER)
+            .ok_or_else(|| AppError::Unauthorized(format!("Missing {} header", DEVICE_ID_HEADER)))?;
+
+        let device_id = header
+            .to_str()
+            .ok()
+            .and_then(|value| Uuid::parse_str(value.trim()).ok())
+            .ok_or_else(|| AppError::BadRequest(format!("Invalid {} header", DEVICE_ID_HEADER)))?;
+
+        match repo.get_device(device_id).await {
+            Ok(device) => {
+                info!("Request made by device {} ({})", device.name, device.id);
+                Ok(Identity { device_id: device.id })
+            }
+            Err(sqlx::Error::RowNotFound) => Err(AppError::Unauthorized(format!("Unknown device {}", device_id))),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
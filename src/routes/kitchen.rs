@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::NaiveDateTime;
+use crate::db::connection::{ItemStatusOutcome, UpdateItemStatusRequest};
+use crate::db::repository::Repository;
+use crate::error::{AppError, OrNotFound};
+use crate::kitchen::estimates::estimate_ready_times;
+use crate::models::restaurant_models::KitchenQueueItem;
+use crate::models::route_models::{FilterParams, PendingItemEstimate, TableEstimate};
+use crate::routes::{identity::Identity, state::AppState};
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use uuid::Uuid;
+
+/// The kitchen queue ahead of the table's items with when each of its items
+/// should be ready. Only that part of the queue is simulated, as the items
+/// behind the table's cannot hold them up.
+pub(super) async fn ready_estimates(
+    state: &AppState,
+    tables_id: Uuid,
+) -> Result<(Vec<KitchenQueueItem>, HashMap<Uuid, NaiveDateTime>), AppError> {
+    let queue = state.repo.get_kitchen_queue_ahead_of(tables_id).await?;
+    let estimates = estimate_ready_times(&queue, state.kitchen_cooks, state.clock.now());
+    Ok((queue, estimates))
+}
+
+pub async fn kitchen_queue(
+    Query(filters): Query<FilterParams>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let queue = repo.get_kitchen_queue(&filters).await?;
+    info!("{} items waiting in the kitchen", queue.len());
+
+    Ok(Json(queue))
+}
+
+/// Moves an item on to its next preparation status, as kitchen devices
+/// work through the queue.
+pub async fn item_status_update(
+    Path((tables_id, item_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    identity: Identity,
+    Json(update): Json<UpdateItemStatusRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Marking item {} of table {} {}", item_id, tables_id, update.status);
+    match state.repo.update_item_status(tables_id, item_id, update.status, state.clock.now(), &identity.actor()).await? {
+        ItemStatusOutcome::NotFound => {
+            return Err(AppError::NotFound(format!("Item with id {} not found in table {}", item_id, tables_id)));
+        }
+        ItemStatusOutcome::Illegal(current) => {
+            return Err(AppError::Conflict(format!(
+                "Item with id {} is {} and cannot be marked {}",
+                item_id, current, update.status
+            )));
+        }
+        ItemStatusOutcome::Updated(_) => {}
+    }
+
+    let item = state
+        .repo
+        .get_item(tables_id, item_id)
+        .await
+        .or_not_found(|| format!("Item with id {} not found in table {}", item_id, tables_id))?;
+    Ok(Json(item))
+}
+
+pub async fn table_estimate(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Estimating when the food of table {} will be ready", tables_id);
+    state
+        .repo
+        .get_table(tables_id)
+        .await
+        .or_not_found(|| format!("Table with id {} not found", tables_id))?;
+
+    let (queue, estimates) = ready_estimates(&state, tables_id).await?;
+    let mut items: Vec<PendingItemEstimate> = queue
+        .into_iter()
+        .filter(|item| item.tables_id == tables_id)
+        .filter_map(|item| {
+            Some(PendingItemEstimate {
+                estimated_ready_at: *estimates.get(&item.id)?,
+                id: item.id,
+                menu_name: item.menu_name,
+                quantity: item.quantity,
+                delivered_quantity: item.delivered_quantity,
+                status: item.status,
+            })
+        })
+        .collect();
+    items.sort_by_key(|item| (item.estimated_ready_at, item.id));
+
+    Ok(Json(TableEstimate {
+        tables_id,
+        estimated_ready_at: items.last().map(|item| item.estimated_ready_at),
+        items,
+    }))
+}
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+use crate::db::connection::{NewMenuRequest, RetireMenuOutcome, TaxCategoryUpdate, UpdateMenuRequest};
+use crate::db::repository::Repository;
+use crate::error::{AppError, OrNotFound};
+use crate::models::route_models::MenuListParams;
+use crate::validation::validate_price;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use uuid::Uuid;
+
+fn validate_prep_time(prep_time: i32) -> Result<i32, AppError> {
+    if prep_time < 0 {
+        return Err(AppError::invalid("Preparation time must not be negative"));
+    }
+    Ok(prep_time)
+}
+
+async fn validate_tax_category(repo: &dyn Repository, tax_category_id: Option<Uuid>) -> Result<Option<Uuid>, AppError> {
+    if let Some(tax_category_id) = tax_category_id {
+        repo.get_tax_category(tax_category_id).await.map_err(|error| match error {
+            sqlx::Error::RowNotFound => AppError::invalid(format!("Unknown tax category {}", tax_category_id)),
+            error => AppError::Database(error),
+        })?;
+    }
+    Ok(tax_category_id)
+}
+
+pub async fn menu_list(
+    Query(params): Query<MenuListParams>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let menu = repo.get_menu(params.include_retired.unwrap_or(false)).await?;
+    info!("{} dishes found", menu.len());
+
+    Ok(Json(menu))
+}
+
+pub async fn menu_create(
+    State(repo): State<Arc<dyn Repository>>,
+    Json(new_menu): Json<NewMenuRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = new_menu.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::invalid("Dish name must not be empty"));
+    }
+    let price = validate_price(new_menu.price).map_err(AppError::invalid)?;
+    let prep_time = validate_prep_time(new_menu.prep_time)?;
+    let tax_category_id = validate_tax_category(repo.as_ref(), new_menu.tax_category_id).await?;
+
+    info!("Adding dish {} to the menu", name);
+    let menu = repo.add_menu(name, price, prep_time, tax_category_id).await?;
+
+    Ok((StatusCode::CREATED, Json(menu)))
+}
+
+pub async fn menu_get(
+    Path(menu_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let menu = repo
+        .get_menu_item(menu_id)
+        .await
+        .or_not_found(|| format!("Dish with id {} not found", menu_id))?;
+
+    Ok(Json(menu))
+}
+
+pub async fn menu_update(
+    Path(menu_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+    Json(updated_menu): Json<UpdateMenuRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let price = updated_menu
+        .price
+        .map(|price| validate_price(price).map_err(AppError::invalid))
+        .transpose()?;
+    let prep_time = updated_menu.prep_time.map(validate_prep_time).transpose()?;
+    if let Some(TaxCategoryUpdate::Set(tax_category_id)) = updated_menu.tax_category_id {
+        validate_tax_category(repo.as_ref(), Some(tax_category_id)).await?;
+    }
+
+    info!("Updating dish {}", menu_id);
+    let menu = repo
+        .update_menu(
+            menu_id,
+            UpdateMenuRequest {
+                price,
+                prep_time,
+                tax_category_id: updated_menu.tax_category_id,
+            },
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Dish with id {} not found", menu_id)))?;
+
+    Ok(Json(menu))
+}
+
+pub async fn menu_retire(
+    Path(menu_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Trying to retire dish {}", menu_id);
+    match repo.retire_menu(menu_id).await? {
+        RetireMenuOutcome::Deleted | RetireMenuOutcome::Retired => Ok(StatusCode::NO_CONTENT),
+        RetireMenuOutcome::NotFound => Err(AppError::NotFound(format!("Dish with id {} not found", menu_id))),
+    }
+}
@@ -1,168 +1,313 @@
 
+mod alerts;
+mod billing;
+mod checks;
+mod discounts;
+mod events;
+mod identity;
+mod kitchen;
+mod menu;
+mod payments;
+mod price_rules;
+mod receipts;
+mod reports;
+mod routes_test;
+pub mod state;
+mod tables;
+mod taxes;
+
 use std::sync::Arc;
-use crate::db::connection::{BulkNewItemRequest, BulkUpdateItemRequest, Database};
+use crate::db::connection::{BulkNewItemRequest, BulkUpdateItemRequest};
+use crate::db::repository::Repository;
 use crate::{
+    error::{AppError, OrNotFound},
     models::restaurant_models::PartialItem,
-    models::route_models::{Pagination, FilterParams,  BulkNewItemResponse, ErrorResponse, SuccessResponse}
+    models::route_models::{Pagination, FilterParams, DeletedItemsParams, PaginatedItemsResponse, BulkNewItemResponse, EstimatedItem, SuccessResponse},
+    validation::items::{check_item_updates, validate_new_items}
 };
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
-    routing::get,
+    routing::{delete, get, post, put},
     Json,
     Router,
 };
-use log::{info, error};
+use log::info;
 use tower::ServiceBuilder;
 use tower_http::trace::TraceLayer;
 use uuid::Uuid;
+use alerts::{alert_threshold_delete, alert_threshold_get, alert_threshold_set, alerts_list};
+use billing::table_bill;
+use checks::{checks_delete, checks_get, checks_split};
+use discounts::{discount_create, discount_delete, discounts_list, promo_code_create, promo_codes_list};
+use events::{item_events, table_item_events};
+use identity::Identity;
+use kitchen::{item_status_update, kitchen_queue, ready_estimates, table_estimate};
+use menu::{menu_create, menu_get, menu_list, menu_retire, menu_update};
+use payments::{open_payments, payment_create, payments_get, refund_create};
+use price_rules::{price_rule_create, price_rule_delete, price_rule_update, price_rules_list};
+use receipts::table_receipt;
+use reports::{daily_report, z_report_create, z_report_get, z_reports_list};
+use taxes::{
+    service_charge_create, service_charge_delete, service_charges_list, tax_categories_list, tax_category_create,
+    tax_category_delete, tax_category_update,
+};
+use state::AppState;
+use tables::{table_create, table_delete, table_get, table_update, tables_list};
 
 
-//TODO: do filter on remaining items => not delivered items to a table
-pub fn create_router(db: Arc<Database>) -> Router {
-    return Router::new()
+pub fn create_router(state: AppState) -> Router {
+    Router::new()
+    .route("/tables", get(tables_list).post(table_create))
+    .route("/tables/:tables_id", get(table_get).put(table_update).delete(table_delete))
+    .route("/tables/:tables_id/bill", get(table_bill))
+    .route("/tables/:tables_id/receipt", get(table_receipt))
+    .route("/tables/:tables_id/eta", get(table_estimate))
+    .route("/tables/:tables_id/checks", get(checks_get).put(checks_split).delete(checks_delete))
+    .route("/tables/:tables_id/discounts", get(discounts_list).post(discount_create))
+    .route("/tables/:tables_id/discounts/:discount_id", delete(discount_delete))
+    .route("/tables/:tables_id/payments", get(payments_get).post(payment_create))
+    .route("/payments/:payment_id/refunds", post(refund_create))
+    .route("/reports/daily", get(daily_report))
+    .route("/reports/z", get(z_reports_list).post(z_report_create))
+    .route("/reports/z/:number", get(z_report_get))
+    .route("/menu", get(menu_list).post(menu_create))
+    .route("/menu/:menu_id", get(menu_get).put(menu_update).delete(menu_retire))
+    .route("/menu/:menu_id/price-rules", get(price_rules_list).post(price_rule_create))
+    .route("/menu/:menu_id/price-rules/:rule_id", put(price_rule_update).delete(price_rule_delete))
+    .route("/menu/:menu_id/alert-threshold", get(alert_threshold_get).put(alert_threshold_set).delete(alert_threshold_delete))
+    .route("/promo-codes", get(promo_codes_list).post(promo_code_create))
+    .route("/tax-categories", get(tax_categories_list).post(tax_category_create))
+    .route("/tax-categories/:tax_category_id", put(tax_category_update).delete(tax_category_delete))
+    .route("/service-charges", get(service_charges_list).post(service_charge_create))
+    .route("/service-charges/:rule_id", delete(service_charge_delete))
     .route("/tables/:tables_id/items", get(items_list).post(items_create).put(item_update))
     .route("/tables/:tables_id/items/:item_id", get(item_get).delete(item_delete))
+    .route("/tables/:tables_id/items/:item_id/restore", post(item_restore))
+    .route("/tables/:tables_id/items/:item_id/deliveries", get(item_deliveries_list))
+    .route("/tables/:tables_id/items/:item_id/status", put(item_status_update))
+    .route("/admin/deleted-items", get(deleted_items_list))
+    .route("/kitchen/queue", get(kitchen_queue))
+    .route("/alerts", get(alerts_list))
+    .route("/events", get(item_events))
+    .route("/tables/:tables_id/events", get(table_item_events))
     .layer(ServiceBuilder::new().layer(TraceLayer::new_for_http()))
-    .with_state(db);
+    .with_state(state)
 }
 
 
 pub async fn items_list(
     Path(tables_id): Path<Uuid>,
-    State(db): State<Arc<Database>>,
-) -> impl IntoResponse {
-    let pagination = Pagination { limit: None, offset: None };
-    let filters = FilterParams { menu_id: None };
-
-    match db.get_all_remaining_items_from_table(tables_id, pagination, filters).await {
-        Ok(items) => {
-            if items.is_empty() {
-                info!("No items found for tables_id {}", tables_id);
-                let error_response = ErrorResponse {
-                    message: format!("No items found for table with id {}", tables_id),
-                };
-                (StatusCode::NOT_FOUND, Json(error_response)).into_response()
-            } else {
-                info!("Items found for tables_id {}", tables_id);
-                Json(items).into_response()
-            }
-        }
-        Err(e) => {
-            let error_response = ErrorResponse {
-                message: format!("Database error: {}", e),
-            };
-            error!("{}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)).into_response()
-        }
+    Query(pagination): Query<Pagination>,
+    Query(filters): Query<FilterParams>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let limit = pagination.limit();
+    let offset = pagination.offset();
+
+    let total = state.repo.count_remaining_items_from_table(tables_id, &filters).await?;
+    if total == 0 {
+        info!("No items found for tables_id {}", tables_id);
+        return Err(AppError::NotFound(format!("No items found for table with id {}", tables_id)));
     }
+
+    let items = state.repo.get_all_remaining_items_from_table(tables_id, pagination, filters).await?;
+    info!("Items found for tables_id {}", tables_id);
+    let (_, estimates) = ready_estimates(&state, tables_id).await?;
+    let items: Vec<EstimatedItem> = items
+        .into_iter()
+        .map(|item| EstimatedItem {
+            estimated_ready_at: estimates.get(&item.id).copied(),
+            item,
+        })
+        .collect();
+
+    let next_offset = offset + items.len();
+    let next_offset = (next_offset < total as usize).then_some(next_offset);
+    Ok(Json(PaginatedItemsResponse {
+        items,
+        total,
+        limit,
+        offset,
+        next_offset,
+    }))
 }
 
 pub async fn items_create(
     Path(tables_id): Path<Uuid>,
-    State(db): State<Arc<Database>>,
+    State(state): State<AppState>,
+    identity: Identity,
     Json(bulk_new_items): Json<BulkNewItemRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, AppError> {
     info!("Creating new items for table: {:?}", tables_id);
 
-    match db.create_items(tables_id, bulk_new_items.items).await {
-        Ok(created_items) => {
-            if created_items.is_empty() {
-                info!("No items found in request");
-                let error_response = ErrorResponse {
-                    message: format!("No items were created for table with id {}", tables_id),
-                };
-                (StatusCode::BAD_REQUEST, Json(error_response)).into_response()
-            } else {
-                info!("{} items added to table {}", created_items.len(), tables_id);
-                let response_items: Vec<PartialItem> = created_items.into_iter().map(|item| PartialItem {
-                    id: item.id,
-                    tables_id: item.tables_id,
-                    menu_id: item.menu_id,
-                    quantity: item.quantity,
-                    delivered_quantity: item.delivered_quantity,
-                }).collect();
-
-                (StatusCode::CREATED, Json(BulkNewItemResponse { items: response_items })).into_response()
-            }
-        }
-        Err(e) => {
-            error!("Failed to create items for table: {:?}. Error: {}", tables_id, e);
-            let error_response = ErrorResponse {
-                message: format!("Failed to create items: {}", e),
-            };
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)).into_response()
-        }
+    if bulk_new_items.items.is_empty() {
+        info!("No items found in request");
+        return Err(AppError::BadRequest(format!("No items were created for table with id {}", tables_id)));
     }
+
+    let menu_ids: Vec<Uuid> = bulk_new_items.items.iter().map(|item| item.menu_id).collect();
+    let orderable_menu_ids = state.repo.get_orderable_menu_ids(&menu_ids).await?;
+
+    let errors = validate_new_items(&bulk_new_items.items, &orderable_menu_ids);
+    if !errors.is_empty() {
+        info!("Rejected {} invalid fields for table {}", errors.len(), tables_id);
+        return Err(AppError::Validation {
+            message: "Some items are invalid".to_string(),
+            errors,
+        });
+    }
+
+    let ordered_at = state.clock.now();
+    let created_items = state
+        .repo
+        .create_items(tables_id, bulk_new_items.items, ordered_at, &identity.actor())
+        .await?;
+    info!("{} items added to table {}", created_items.len(), tables_id);
+    let response_items: Vec<PartialItem> = created_items.into_iter().map(|item| PartialItem {
+        id: item.id,
+        tables_id: item.tables_id,
+        menu_id: item.menu_id,
+        menu_name: item.menu_name,
+        unit_price: item.unit_price,
+        quantity: item.quantity,
+        delivered_quantity: item.delivered_quantity,
+    }).collect();
+
+    Ok((StatusCode::CREATED, Json(BulkNewItemResponse { items: response_items })))
 }
 
 pub async fn item_get(
     Path((tables_id, item_id)): Path<(Uuid, Uuid)>,
-    State(db): State<Arc<Database>>,
-) -> impl IntoResponse {
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
     info!("Get item for tables {} and items {}", tables_id, item_id);
-    match db.get_item(tables_id, item_id).await {
-        Ok(item) => Json(item).into_response(),
-        Err(e) => {
-            error!("Failed to retrieve item for tables_id {} and item_id {}", tables_id, item_id);
-            let error_response = ErrorResponse {
-                message: format!("Failed to retrieve item: {}", e),
-            };
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)).into_response()
-        },
+    let item = state
+        .repo
+        .get_item(tables_id, item_id)
+        .await
+        .or_not_found(|| format!("Item with id {} not found in table {}", item_id, tables_id))?;
+    let (_, estimates) = ready_estimates(&state, tables_id).await?;
+
+    Ok(Json(EstimatedItem {
+        estimated_ready_at: estimates.get(&item.id).copied(),
+        item,
+    }))
+}
+
+pub async fn item_deliveries_list(
+    Path((tables_id, item_id)): Path<(Uuid, Uuid)>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Get deliveries for tables {} and items {}", tables_id, item_id);
+    repo.get_item(tables_id, item_id)
+        .await
+        .or_not_found(|| format!("Item with id {} not found in table {}", item_id, tables_id))?;
+
+    let deliveries = repo.get_item_deliveries(tables_id, item_id).await?;
+    Ok(Json(deliveries))
+}
+
+/// Once payments have been taken the bill must not drop below what was paid,
+/// so items can no longer be deleted or have their quantity lowered.
+async fn ensure_not_being_paid(repo: &dyn Repository, tables_id: Uuid, item_id: Uuid) -> Result<(), AppError> {
+    if !open_payments(repo, tables_id).await?.is_empty() {
+        return Err(AppError::Conflict(format!(
+            "Table with id {} is being paid, item {} can no longer be deleted or reduced",
+            tables_id, item_id
+        )));
     }
+    Ok(())
 }
 
 pub async fn item_delete(
     Path((tables_id, item_id)): Path<(Uuid, Uuid)>,
-    State(db): State<Arc<Database>>,
-) -> impl IntoResponse {
+    State(state): State<AppState>,
+    identity: Identity,
+) -> Result<impl IntoResponse, AppError> {
     info!("Trying to delete item {} for table {}", item_id, tables_id);
-    match db.delete_item(tables_id, item_id).await {
-        Ok(true) => StatusCode::NO_CONTENT.into_response(),
-        Ok(false) => {
-            let error_response = ErrorResponse {
-                message: format!("Item with id {} not found in table {}", item_id, tables_id),
-            };
-            (StatusCode::NOT_FOUND, Json(error_response)).into_response()
-        },
-        Err(e) => {
-            let error_response = ErrorResponse {
-                message: format!("Failed to delete item: {}", e),
-            };
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)).into_response()
-        },
+    let item = state
+        .repo
+        .get_item(tables_id, item_id)
+        .await
+        .or_not_found(|| format!("Item with id {} not found in table {}", item_id, tables_id))?;
+    if item.paid_at.is_some() {
+        return Err(AppError::Conflict(format!("Item with id {} has already been paid", item_id)));
+    }
+    ensure_not_being_paid(state.repo.as_ref(), tables_id, item_id).await?;
+
+    if !state.repo.delete_item(tables_id, item_id, &identity.actor()).await? {
+        return Err(AppError::NotFound(format!("Item with id {} not found in table {}", item_id, tables_id)));
     }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn item_restore(
+    Path((tables_id, item_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+    identity: Identity,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Trying to restore item {} for table {}", item_id, tables_id);
+    if !state.repo.restore_item(tables_id, item_id, &identity.actor()).await? {
+        return Err(AppError::NotFound(format!("Deleted item with id {} not found in table {}", item_id, tables_id)));
+    }
+
+    let item = state.repo.get_item(tables_id, item_id).await?;
+    Ok(Json(item))
+}
+
+pub async fn deleted_items_list(
+    Query(params): Query<DeletedItemsParams>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let items = repo.get_deleted_items(params.from, params.to).await?;
+    info!("{} deleted items found", items.len());
+
+    Ok(Json(items))
 }
 
 pub async fn item_update(
-    State(db): State<Arc<Database>>,
+    State(state): State<AppState>,
+    identity: Identity,
     Json(bulk_updated_items): Json<BulkUpdateItemRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, AppError> {
     info!("Trying to update items");
-    match db.update_items(bulk_updated_items.items).await {
-        Ok(updated_count) => {
-            if updated_count == 0 {
-                info!("No updated items");
-                let error_response = ErrorResponse {
-                    message: "No items were updated".to_string(),
-                };
-                (StatusCode::NOT_FOUND, Json(error_response)).into_response()
-            } else {
-                info!("Successfuly updated {} item", updated_count);
-                let success_response = SuccessResponse {
-                    message: format!("{} items updated successfully", updated_count),
-                };
-                (StatusCode::OK, Json(success_response)).into_response()
-            }
-        },
-        Err(e) => {
-            error!("Failed to update items");
-            let error_response = ErrorResponse {
-                message: format!("Failed to update items: {}", e),
-            };
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)).into_response()
-        },
+
+    let item_ids: Vec<Uuid> = bulk_updated_items.items.iter().map(|item| item.id).collect();
+    let paid = state.repo.get_paid_item_ids(&item_ids).await?;
+    if let Some(item) = bulk_updated_items.items.iter().find(|item| item.quantity.is_some() && paid.contains(&item.id)) {
+        info!("Rejected quantity change of paid item {}", item.id);
+        return Err(AppError::Conflict(format!("Item with id {} has already been paid", item.id)));
+    }
+
+    let current = state.repo.get_item_quantities(&item_ids).await?;
+
+    check_item_updates(&bulk_updated_items.items, &current).inspect_err(|_| info!("Rejected invalid item update"))?;
+
+    let lowered: Vec<Uuid> = bulk_updated_items
+        .items
+        .iter()
+        .filter(|item| match (item.quantity, current.get(&item.id)) {
+            (Some(update), Some(&(quantity, _))) => update.apply(quantity) < quantity,
+            _ => false,
+        })
+        .map(|item| item.id)
+        .collect();
+    for item in state.repo.get_items(&lowered).await? {
+        ensure_not_being_paid(state.repo.as_ref(), item.tables_id, item.id).await?;
     }
-}
\ No newline at end of file
+
+    let updated_count = state.repo.update_items(bulk_updated_items.items, &identity.actor()).await?.len();
+    if updated_count == 0 {
+        info!("No updated items");
+        return Err(AppError::NotFound("No items were updated".to_string()));
+    }
+
+    info!("Successfuly updated {} item", updated_count);
+    let success_response = SuccessResponse {
+        message: format!("{} items updated successfully", updated_count),
+    };
+    Ok((StatusCode::OK, Json(success_response)))
+}
@@ -0,0 +1,150 @@
+use crate::billing::{
+    payments::{paid_amount, paid_on_check, summarize_payments},
+    refunds::refunded_amounts,
+};
+use crate::db::connection::{AddPaymentOutcome, AddRefundOutcome, NewPaymentRequest, NewRefundRequest};
+use crate::db::repository::Repository;
+use crate::error::{AppError, OrNotFound};
+use crate::models::restaurant_models::Payment;
+use crate::routes::{
+    billing::{bill_rules, current_bill, priced_bill, settled_bill, settlement},
+    checks::table_checks,
+    identity::Identity,
+    state::AppState,
+};
+use crate::validation::{field_error, payments::prepare_payment, refunds::prepare_refund};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use uuid::Uuid;
+
+/// Payments made in the table's current session, if one is open.
+pub(super) async fn open_payments(repo: &dyn Repository, tables_id: Uuid) -> Result<Vec<Payment>, AppError> {
+    match repo.get_open_session(tables_id).await? {
+        Some(session) => Ok(repo.get_payments(session.id).await?),
+        None => Ok(vec![]),
+    }
+}
+
+pub async fn payments_get(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Get payments for table {}", tables_id);
+    let bill = current_bill(&state, tables_id).await?;
+    let session = state.repo.get_open_session(tables_id).await?;
+    let payments = open_payments(state.repo.as_ref(), tables_id).await?;
+
+    Ok(Json(summarize_payments(tables_id, session.map(|session| session.id), payments, bill.total, false)))
+}
+
+pub async fn payment_create(
+    Path(tables_id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Identity,
+    Json(new_payment): Json<NewPaymentRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Recording {:?} payment for table {}", new_payment.tender, tables_id);
+    let rules = bill_rules(&state).await?;
+    let bill = priced_bill(&state, tables_id, &rules).await?;
+    let mut payments = open_payments(state.repo.as_ref(), tables_id).await?;
+    let paid = paid_amount(&payments);
+    let balance = bill.total - paid;
+
+    let check_balance = match new_payment.check_id {
+        Some(check_id) => {
+            let checks = table_checks(&state, tables_id, &bill).await?;
+            let Some(check) = checks.checks.iter().find(|check| check.id == check_id) else {
+                return Err(AppError::Validation {
+                    message: "Payment is invalid".to_string(),
+                    errors: vec![field_error(0, "check_id", format!("Unknown check {}", check_id))],
+                });
+            };
+            Some(check.total - paid_on_check(&payments, check_id))
+        }
+        None => None,
+    };
+
+    let payment = prepare_payment(&new_payment, balance, check_balance).map_err(|errors| {
+        info!("Rejected {} invalid fields in payment for table {}", errors.len(), tables_id);
+        AppError::Validation {
+            message: "Payment is invalid".to_string(),
+            errors,
+        }
+    })?;
+
+    let settlement = (payment.amount == balance).then(|| settlement(&bill, &rules));
+    match state.repo.add_payment(tables_id, payment, paid, settlement, &identity.actor()).await? {
+        AddPaymentOutcome::Stale => Err(AppError::Conflict(format!(
+            "Another payment was made on table with id {} in the meantime, please try again",
+            tables_id
+        ))),
+        AddPaymentOutcome::Recorded { payment, closed } => {
+            info!("Payment {} of {} recorded for table {}", payment.id, payment.amount, tables_id);
+            if closed {
+                info!("Table {} is fully paid, session {} closed", tables_id, payment.session_id);
+            }
+            let session_id = payment.session_id;
+            payments.push(payment);
+            let summary = summarize_payments(tables_id, Some(session_id), payments, bill.total, closed);
+            Ok((StatusCode::CREATED, Json(summary)))
+        }
+    }
+}
+
+pub async fn refund_create(
+    Path(payment_id): Path<Uuid>,
+    State(state): State<AppState>,
+    identity: Identity,
+    Json(new_refund): Json<NewRefundRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Refunding payment {} for {:?}, approved by {}", payment_id, new_refund.reason, new_refund.approved_by);
+    let original = state
+        .repo
+        .get_payment(payment_id)
+        .await
+        .or_not_found(|| format!("Payment with id {} not found", payment_id))?;
+    if original.refund_of.is_some() {
+        return Err(AppError::Conflict(format!("Payment with id {} is itself a refund", payment_id)));
+    }
+
+    let session = state.repo.get_session(original.session_id).await?;
+    if session.closed_at.is_none() {
+        return Err(AppError::Conflict(format!(
+            "Table with id {} has not been settled yet, adjust its bill instead of refunding",
+            session.tables_id
+        )));
+    }
+
+    let payments = state.repo.get_payments(session.id).await?;
+    let items = state.repo.get_refundable_items(session.id).await?;
+    let (refunded_amount, refunded_tip) = refunded_amounts(&payments, payment_id);
+
+    let bill = settled_bill(&state, &session).await?;
+    let refund = prepare_refund(&new_refund, &original, &payments, &items, &bill).map_err(|errors| {
+        info!("Rejected {} invalid fields in refund of payment {}", errors.len(), payment_id);
+        AppError::Validation {
+            message: "Refund is invalid".to_string(),
+            errors,
+        }
+    })?;
+
+    match state
+        .repo
+        .add_refund(payment_id, refund, refunded_amount + refunded_tip, &identity.actor())
+        .await?
+    {
+        AddRefundOutcome::Stale => Err(AppError::Conflict(format!(
+            "Payment with id {} was refunded in the meantime, please try again",
+            payment_id
+        ))),
+        AddRefundOutcome::Recorded(refund) => {
+            info!("Refund {} of {} recorded against payment {}", refund.id, -refund.amount, payment_id);
+            Ok((StatusCode::CREATED, Json(refund)))
+        }
+    }
+}
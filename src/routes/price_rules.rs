@@ -0,0 +1,92 @@
+use std::sync::Arc;
+use crate::db::connection::NewPriceRuleRequest;
+use crate::db::repository::Repository;
+use crate::error::{AppError, OrNotFound};
+use crate::validation::price_rules::validate_price_rule;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use uuid::Uuid;
+
+/// Checks the rule and trims its name, failing with `404` for unknown dishes.
+async fn prepare_rule(repo: &dyn Repository, menu_id: Uuid, rule: NewPriceRuleRequest) -> Result<NewPriceRuleRequest, AppError> {
+    repo.get_menu_item(menu_id)
+        .await
+        .or_not_found(|| format!("Menu item with id {} not found", menu_id))?;
+
+    let errors = validate_price_rule(&rule);
+    if !errors.is_empty() {
+        info!("Rejected {} invalid fields in price rule for dish {}", errors.len(), menu_id);
+        return Err(AppError::Validation {
+            message: "Price rule is invalid".to_string(),
+            errors,
+        });
+    }
+
+    let mut days = rule.days;
+    days.sort_unstable();
+    Ok(NewPriceRuleRequest {
+        name: rule.name.trim().to_string(),
+        days,
+        ..rule
+    })
+}
+
+pub async fn price_rules_list(
+    Path(menu_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    repo.get_menu_item(menu_id)
+        .await
+        .or_not_found(|| format!("Menu item with id {} not found", menu_id))?;
+
+    let rules = repo.get_price_rules(&[menu_id]).await?;
+    info!("{} price rules found for dish {}", rules.len(), menu_id);
+
+    Ok(Json(rules))
+}
+
+pub async fn price_rule_create(
+    Path(menu_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+    Json(new_rule): Json<NewPriceRuleRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let new_rule = prepare_rule(repo.as_ref(), menu_id, new_rule).await?;
+
+    info!("Adding price rule {} at {} for dish {}", new_rule.name, new_rule.price, menu_id);
+    let rule = repo.add_price_rule(menu_id, new_rule).await?;
+
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+pub async fn price_rule_update(
+    Path((menu_id, rule_id)): Path<(Uuid, Uuid)>,
+    State(repo): State<Arc<dyn Repository>>,
+    Json(updated_rule): Json<NewPriceRuleRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let updated_rule = prepare_rule(repo.as_ref(), menu_id, updated_rule).await?;
+
+    info!("Updating price rule {} of dish {}", rule_id, menu_id);
+    let rule = repo
+        .update_price_rule(menu_id, rule_id, updated_rule)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Price rule with id {} not found for dish {}", rule_id, menu_id)))?;
+
+    Ok(Json(rule))
+}
+
+pub async fn price_rule_delete(
+    Path((menu_id, rule_id)): Path<(Uuid, Uuid)>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Deleting price rule {} of dish {}", rule_id, menu_id);
+    if !repo.delete_price_rule(menu_id, rule_id).await? {
+        return Err(AppError::NotFound(format!("Price rule with id {} not found for dish {}", rule_id, menu_id)));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,78 @@
+use crate::error::{AppError, OrNotFound};
+use crate::models::route_models::{ReceiptFormat, ReceiptParams};
+use crate::receipts::{
+    escpos::render_escpos,
+    receipt::{receipt_lines, render_text, Receipt},
+};
+use crate::routes::{
+    billing::{current_bill, settled_bill},
+    state::AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use log::info;
+use uuid::Uuid;
+
+pub async fn table_receipt(
+    Path(tables_id): Path<Uuid>,
+    Query(params): Query<ReceiptParams>,
+    State(state): State<AppState>,
+) -> Result<Response, AppError> {
+    info!("Printing receipt for table {}", tables_id);
+    let table = state
+        .repo
+        .get_table(tables_id)
+        .await
+        .or_not_found(|| format!("Table with id {} not found", tables_id))?;
+
+    let session = match params.session_id {
+        Some(session_id) => {
+            let not_found = || format!("Session with id {} not found for table {}", session_id, tables_id);
+            let session = state.repo.get_session(session_id).await.or_not_found(not_found)?;
+            if session.tables_id != tables_id {
+                return Err(AppError::NotFound(not_found()));
+            }
+            Some(session)
+        }
+        None => match state.repo.get_open_session(tables_id).await? {
+            Some(session) => Some(session),
+            None if current_bill(&state, tables_id).await?.lines.is_empty() => state.repo.get_last_session(tables_id).await?,
+            None => None,
+        },
+    };
+
+    let (bill, payments, settled) = match &session {
+        Some(session) if session.closed_at.is_some() => {
+            (settled_bill(&state, session).await?, state.repo.get_payments(session.id).await?, true)
+        }
+        Some(session) => (current_bill(&state, tables_id).await?, state.repo.get_payments(session.id).await?, false),
+        None => (current_bill(&state, tables_id).await?, vec![], false),
+    };
+
+    let receipt = Receipt {
+        number: session.map(|session| session.receipt_number),
+        table_name: table.name,
+        printed_at: state.clock.now(),
+        bill,
+        payments,
+        settled,
+    };
+    let lines = receipt_lines(&receipt, &state.receipt);
+    info!("Receipt {:?} for table {} has {} lines", receipt.number, tables_id, lines.len());
+
+    Ok(match params.format.unwrap_or_default() {
+        ReceiptFormat::Text => (
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            render_text(&lines, state.receipt.width),
+        )
+            .into_response(),
+        ReceiptFormat::Escpos => (
+            [(header::CONTENT_TYPE, "application/octet-stream")],
+            render_escpos(&lines, state.receipt.width),
+        )
+            .into_response(),
+    })
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use crate::db::connection::UnreportedSales;
+use crate::db::repository::{DayTotals, Repository};
+use crate::error::{AppError, OrNotFound};
+use crate::models::route_models::{DailyReportParams, ZReportTotals};
+use crate::reports::{daily::daily_totals, z_report::z_report_totals};
+use crate::routes::{billing::settled_bill, identity::Identity, state::AppState};
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{Duration, Utc};
+use log::info;
+
+pub async fn daily_report(
+    Query(params): Query<DailyReportParams>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let date = params.date.unwrap_or_else(|| Utc::now().date_naive());
+    info!("Building daily report for {}", date);
+
+    let from = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    let payments = state.repo.get_payments_between(from, from + Duration::days(1)).await?;
+
+    Ok(Json(daily_totals(date, &payments)))
+}
+
+/// Totals settled bills at the rates and service charge they were paid at,
+/// so the report matches the money taken even if the rules changed since.
+#[async_trait]
+impl DayTotals for AppState {
+    async fn totals(&self, sales: &UnreportedSales) -> Result<ZReportTotals, AppError> {
+        let mut bills = Vec::with_capacity(sales.sessions.len());
+        for session in &sales.sessions {
+            bills.push(settled_bill(self, session).await?);
+        }
+        Ok(z_report_totals(&bills, &sales.payments, &sales.voids))
+    }
+}
+
+/// Closes the business day: totals everything settled, paid, refunded and
+/// voided since the last close into the next Z report.
+pub async fn z_report_create(
+    State(state): State<AppState>,
+    identity: Identity,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Closing the business day");
+    let report = state.repo.close_day(&state, &identity.actor()).await?;
+    info!("Z report {} closed with {} sessions", report.number, report.totals.sessions);
+
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+pub async fn z_reports_list(
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let reports = repo.get_z_reports().await?;
+    info!("{} Z reports found", reports.len());
+
+    Ok(Json(reports))
+}
+
+pub async fn z_report_get(
+    Path(number): Path<i64>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Get Z report {}", number);
+    let report = repo
+        .get_z_report(number)
+        .await
+        .or_not_found(|| format!("Z report {} not found", number))?;
+
+    Ok(Json(report))
+}
@@ -0,0 +1,1101 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::{to_bytes, Body, BodyDataStream},
+        http::{Method, Request, StatusCode},
+        Router,
+    };
+    use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+    use futures_util::StreamExt;
+    use rust_decimal::Decimal;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    use crate::clock::{Clock, FixedClock};
+    use crate::config::ReceiptSettings;
+    use crate::db::memory::InMemoryRepository;
+    use crate::db::publishing::PublishingRepository;
+    use crate::db::repository::{KitchenRepository, MenuRepository, Repository, TableRepository};
+    use crate::events::items::ItemEvents;
+    use crate::kitchen::alerts::scan_overdue_items;
+    use crate::models::restaurant_models::ItemStatus;
+    use crate::receipts::receipt::align;
+    use crate::routes::{create_router, state::AppState};
+
+    struct TestApp {
+        router: Router,
+        repo: Arc<InMemoryRepository>,
+        /// The repository the routes and the overdue scan use, which publishes events.
+        publishing: Arc<dyn Repository>,
+        clock: Arc<FixedClock>,
+        device_id: Uuid,
+    }
+
+    impl TestApp {
+        fn new() -> Self {
+            let repo = Arc::new(InMemoryRepository::new());
+            let device_id = repo.add_device("Test Device").id;
+            let clock = Arc::new(FixedClock::new(Utc::now().naive_utc()));
+            let events = Arc::new(ItemEvents::new(1));
+            let publishing: Arc<dyn Repository> = Arc::new(PublishingRepository::new(repo.clone(), events.clone(), clock.clone()));
+            let router = create_router(AppState {
+                repo: publishing.clone(),
+                tax_rate: Decimal::new(10, 2),
+                clock: clock.clone(),
+                receipt: Arc::new(ReceiptSettings {
+                    name: "Test Restaurant".to_string(),
+                    header: vec![],
+                    footer: vec![],
+                    width: 32,
+                }),
+                kitchen_cooks: 2,
+                events,
+            });
+            TestApp { router, repo, publishing, clock, device_id }
+        }
+
+        async fn send(&self, method: Method, uri: &str, device_id: Option<Uuid>, body: Option<Value>) -> (StatusCode, Value) {
+            let mut request = Request::builder().method(method).uri(uri);
+            if let Some(device_id) = device_id {
+                request = request.header("x-device-id", device_id.to_string());
+            }
+            let request = match body {
+                Some(body) => request
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string())),
+                None => request.body(Body::empty()),
+            }
+            .unwrap();
+
+            let response = self.router.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+            (status, body)
+        }
+
+        async fn fetch(&self, uri: &str) -> (StatusCode, Vec<u8>) {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = self.router.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, bytes.to_vec())
+        }
+
+        async fn open_events(&self, uri: &str, last_event_id: Option<u64>) -> (StatusCode, BodyDataStream) {
+            let mut request = Request::builder().uri(uri);
+            if let Some(last_event_id) = last_event_id {
+                request = request.header("last-event-id", last_event_id.to_string());
+            }
+            let response = self.router.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+            (response.status(), response.into_body().into_data_stream())
+        }
+
+        /// Takes an item through the kitchen, as only ready items can be delivered.
+        async fn make_ready(&self, tables_id: Uuid, item_id: &str) {
+            let item_id = Uuid::parse_str(item_id).unwrap();
+            for status in [ItemStatus::InPreparation, ItemStatus::Ready] {
+                self.repo.update_item_status(tables_id, item_id, status, self.clock.now(), "Kitchen").await.unwrap();
+            }
+        }
+    }
+
+    /// Reads the next `count` events of a stream as `(id, event, data)`.
+    async fn next_events(stream: &mut BodyDataStream, count: usize) -> Vec<(u64, String, Value)> {
+        let mut text = String::new();
+        while text.matches("\n\n").count() < count {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(1), stream.next())
+                .await
+                .expect("Timed out waiting for events")
+                .expect("Event stream ended")
+                .unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+
+        text.split("\n\n")
+            .filter(|block| !block.is_empty())
+            .map(|block| {
+                let field = |name: &str| {
+                    block.lines().find_map(|line| line.strip_prefix(name)).unwrap_or_default().trim().to_string()
+                };
+                (field("id:").parse().unwrap(), field("event:"), serde_json::from_str(&field("data:")).unwrap())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_create_and_list_items() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let menu = app.repo.add_menu("Burger".to_string(), Decimal::new(599, 2), 5, None).await.unwrap();
+
+        let items: Vec<Value> = (0..3).map(|_| json!({ "quantity": 1, "menu_id": menu.id })).collect();
+        let uri = format!("/tables/{}/items", table.id);
+        let (status, body) = app.send(Method::POST, &uri, Some(app.device_id), Some(json!({ "items": items }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["items"].as_array().unwrap().len(), 3);
+
+        let (status, body) = app.send(Method::GET, &format!("{}?limit=2", uri), None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 3);
+        assert_eq!(body["items"].as_array().unwrap().len(), 2);
+        assert_eq!(body["next_offset"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_writes_require_a_known_device() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let uri = format!("/tables/{}/items/{}", table.id, Uuid::new_v4());
+
+        let (status, body) = app.send(Method::DELETE, &uri, None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "unauthorized");
+
+        let (status, _) = app.send(Method::DELETE, &uri, Some(Uuid::new_v4()), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, body) = app.send(Method::DELETE, &uri, Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_items_return_field_errors() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let menu = app.repo.add_menu("Burger".to_string(), Decimal::new(599, 2), 5, None).await.unwrap();
+
+        let items = json!({
+            "items": [
+                { "quantity": 1, "menu_id": menu.id },
+                { "quantity": 0, "menu_id": Uuid::new_v4() },
+            ]
+        });
+        let uri = format!("/tables/{}/items", table.id);
+        let (status, body) = app.send(Method::POST, &uri, Some(app.device_id), Some(items)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "validation_failed");
+        let errors = body["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|error| error["index"] == 1));
+
+        let too_many: Vec<Value> = (0..101).map(|_| json!({ "quantity": 1, "menu_id": menu.id })).collect();
+        let (status, body) = app.send(Method::POST, &uri, Some(app.device_id), Some(json!({ "items": too_many }))).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["code"], "limit_exceeded");
+    }
+
+    #[tokio::test]
+    async fn test_table_with_pending_items_cannot_be_deleted() {
+        let app = TestApp::new();
+        let (status, body) = app.send(Method::POST, "/tables", None, Some(json!({ "name": " " }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["message"], "Table name must not be empty");
+        let (status, table) = app.send(Method::POST, "/tables", None, Some(json!({ "name": "Terrace" }))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let menu = app.repo.add_menu("Pizza".to_string(), Decimal::new(899, 2), 8, None).await.unwrap();
+
+        let uri = format!("/tables/{}/items", table["id"].as_str().unwrap());
+        let items = json!({ "items": [{ "quantity": 2, "menu_id": menu.id }] });
+        let (_, created) = app.send(Method::POST, &uri, Some(app.device_id), Some(items)).await;
+        let item_id = created["items"][0]["id"].clone();
+
+        let table_uri = format!("/tables/{}", table["id"].as_str().unwrap());
+        let (status, body) = app.send(Method::DELETE, &table_uri, None, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "conflict");
+
+        let tables_id = Uuid::parse_str(table["id"].as_str().unwrap()).unwrap();
+        app.make_ready(tables_id, item_id.as_str().unwrap()).await;
+        let update = json!({ "items": [{ "id": item_id, "delivered_quantity": { "set": 2 } }] });
+        let (status, _) = app.send(Method::PUT, &uri, Some(app.device_id), Some(update)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = app.send(Method::DELETE, &table_uri, None, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_bill_excludes_deleted_items() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let burger = app.repo.add_menu("Burger".to_string(), Decimal::new(599, 2), 5, None).await.unwrap();
+        let pizza = app.repo.add_menu("Pizza".to_string(), Decimal::new(899, 2), 8, None).await.unwrap();
+
+        let uri = format!("/tables/{}/items", table.id);
+        let items = json!({ "items": [
+            { "quantity": 3, "menu_id": burger.id },
+            { "quantity": 1, "menu_id": pizza.id },
+            { "quantity": 2, "menu_id": pizza.id },
+        ] });
+        let (_, created) = app.send(Method::POST, &uri, Some(app.device_id), Some(items)).await;
+        let deleted_uri = format!("{}/{}", uri, created["items"][2]["id"].as_str().unwrap());
+        let (status, _) = app.send(Method::DELETE, &deleted_uri, Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, bill) = app.send(Method::GET, &format!("/tables/{}/bill", table.id), None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let lines = bill["lines"].as_array().unwrap();
+        assert_eq!(lines.len(), 2);
+        let burger_line = lines.iter().find(|line| line["name"] == "Burger").unwrap();
+        assert_eq!(burger_line["quantity"], 3);
+        assert_eq!(burger_line["line_total"], "17.97");
+        assert_eq!(bill["subtotal"], "26.96");
+        assert_eq!(bill["tax"], "2.70");
+        assert_eq!(bill["total"], "29.66");
+
+        let (status, _) = app.send(Method::GET, &format!("/tables/{}/bill", Uuid::new_v4()), None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_split_checks_reconcile_with_bill() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let menu = app.repo.add_menu("Set Menu".to_string(), Decimal::new(1000, 2), 20, None).await.unwrap();
+
+        let items_uri = format!("/tables/{}/items", table.id);
+        let items = json!({ "items": [{ "quantity": 1, "menu_id": menu.id }, { "quantity": 1, "menu_id": menu.id }] });
+        let (_, created) = app.send(Method::POST, &items_uri, Some(app.device_id), Some(items)).await;
+        let first_item = created["items"][0]["id"].clone();
+        let second_item = created["items"][1]["id"].clone();
+
+        let uri = format!("/tables/{}/checks", table.id);
+        let (status, split) = app.send(Method::PUT, &uri, Some(app.device_id), Some(json!({ "even": 3 }))).await;
+        assert_eq!(status, StatusCode::OK);
+        let totals: Vec<&str> = split["checks"].as_array().unwrap().iter().map(|check| check["total"].as_str().unwrap()).collect();
+        assert_eq!(totals, vec!["7.34", "7.33", "7.33"]);
+        assert_eq!(split["total"], "22.00");
+
+        let by_seat = json!({ "checks": [
+            { "name": "Seat 1", "items": [{ "item_id": first_item }, { "item_id": second_item, "numerator": 1, "denominator": 2 }] },
+            { "name": "Seat 2", "items": [{ "item_id": second_item, "numerator": 2, "denominator": 3 }] },
+        ] });
+        let (status, body) = app.send(Method::PUT, &uri, Some(app.device_id), Some(by_seat)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "items[0].numerator");
+
+        let by_seat = json!({ "checks": [
+            { "name": "Seat 1", "items": [{ "item_id": first_item }, { "item_id": second_item, "numerator": 1, "denominator": 2 }] },
+        ] });
+        let (status, split) = app.send(Method::PUT, &uri, Some(app.device_id), Some(by_seat)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(split["checks"][0]["name"], "Seat 1");
+        assert_eq!(split["checks"][0]["total"], "16.50");
+        assert_eq!(split["unassigned"]["total"], "5.50");
+
+        let (status, _) = app.send(Method::DELETE, &uri, Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, split) = app.send(Method::GET, &uri, None, None).await;
+        assert!(split["checks"].as_array().unwrap().is_empty());
+        assert_eq!(split["unassigned"]["total"], "22.00");
+    }
+
+    #[tokio::test]
+    async fn test_payments_close_the_table_and_lock_paid_items() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let menu = app.repo.add_menu("Set Menu".to_string(), Decimal::new(2000, 2), 20, None).await.unwrap();
+
+        let items_uri = format!("/tables/{}/items", table.id);
+        let items = json!({ "items": [{ "quantity": 2, "menu_id": menu.id }] });
+        let (_, created) = app.send(Method::POST, &items_uri, Some(app.device_id), Some(items)).await;
+        let item_id = created["items"][0]["id"].as_str().unwrap().to_string();
+
+        let uri = format!("/tables/{}/payments", table.id);
+        let card = json!({ "tender": "card", "amount": "20.00", "tip": "3.00" });
+        let (status, summary) = app.send(Method::POST, &uri, Some(app.device_id), Some(card)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(summary["total"], "44.00");
+        assert_eq!(summary["balance"], "24.00");
+        assert_eq!(summary["closed"], false);
+
+        let too_much = json!({ "tender": "cash", "amount": "30.00" });
+        let (status, body) = app.send(Method::POST, &uri, Some(app.device_id), Some(too_much)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "amount");
+
+        let cash = json!({ "tender": "cash", "tendered": "50.00" });
+        let (status, summary) = app.send(Method::POST, &uri, Some(app.device_id), Some(cash)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(summary["payments"][1]["change"], "26.00");
+        assert_eq!(summary["tips"], "3.00");
+        assert_eq!(summary["balance"], "0.00");
+        assert_eq!(summary["closed"], true);
+
+        let item_uri = format!("{}/{}", items_uri, item_id);
+        let (status, _) = app.send(Method::DELETE, &item_uri, Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let update = json!({ "items": [{ "id": item_id, "quantity": { "increment": 1 } }] });
+        let (status, _) = app.send(Method::PUT, &items_uri, Some(app.device_id), Some(update)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        app.make_ready(table.id, &item_id).await;
+        let delivery = json!({ "items": [{ "id": item_id, "delivered_quantity": { "set": 2 } }] });
+        let (status, _) = app.send(Method::PUT, &items_uri, Some(app.device_id), Some(delivery)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, bill) = app.send(Method::GET, &format!("/tables/{}/bill", table.id), None, None).await;
+        assert!(bill["lines"].as_array().unwrap().is_empty());
+        let (_, summary) = app.send(Method::GET, &uri, None, None).await;
+        assert_eq!(summary["session_id"], Value::Null);
+        assert_eq!(summary["balance"], "0.00");
+    }
+
+    #[tokio::test]
+    async fn test_bill_cannot_shrink_below_what_was_paid() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let menu = app.repo.add_menu("Set Menu".to_string(), Decimal::new(2000, 2), 20, None).await.unwrap();
+
+        let items_uri = format!("/tables/{}/items", table.id);
+        let items = json!({ "items": [{ "quantity": 2, "menu_id": menu.id }] });
+        let (_, created) = app.send(Method::POST, &items_uri, Some(app.device_id), Some(items)).await;
+        let item_id = created["items"][0]["id"].as_str().unwrap().to_string();
+
+        let uri = format!("/tables/{}/payments", table.id);
+        let card = json!({ "tender": "card", "amount": "30.00" });
+        let (status, _) = app.send(Method::POST, &uri, Some(app.device_id), Some(card)).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (status, _) = app.send(Method::DELETE, &format!("{}/{}", items_uri, item_id), Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let fewer = json!({ "items": [{ "id": item_id, "quantity": { "increment": -1 } }] });
+        let (status, _) = app.send(Method::PUT, &items_uri, Some(app.device_id), Some(fewer)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let more = json!({ "items": [{ "id": item_id, "quantity": { "set": 3 } }] });
+        let (status, _) = app.send(Method::PUT, &items_uri, Some(app.device_id), Some(more)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, summary) = app.send(Method::POST, &uri, Some(app.device_id), Some(json!({ "tender": "cash" }))).await;
+        assert_eq!(summary["total"], "66.00");
+        assert_eq!(summary["closed"], true);
+    }
+
+    #[tokio::test]
+    async fn test_refunds_need_a_settled_table_and_show_in_daily_report() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let menu = app.repo.add_menu("Set Menu".to_string(), Decimal::new(2000, 2), 20, None).await.unwrap();
+
+        let items = json!({ "items": [{ "quantity": 2, "menu_id": menu.id }] });
+        let (_, created) = app.send(Method::POST, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(items)).await;
+        let item_id = created["items"][0]["id"].as_str().unwrap().to_string();
+
+        let uri = format!("/tables/{}/payments", table.id);
+        let card = json!({ "tender": "card", "amount": "20.00", "tip": "3.00" });
+        let (_, summary) = app.send(Method::POST, &uri, Some(app.device_id), Some(card)).await;
+        let card_id = summary["payments"][0]["id"].as_str().unwrap().to_string();
+
+        let refund = json!({ "reason": "wrong_item", "approved_by": "manager", "items": [{ "item_id": item_id, "quantity": 1 }] });
+        let refund_uri = format!("/payments/{}/refunds", card_id);
+        let (status, _) = app.send(Method::POST, &refund_uri, Some(app.device_id), Some(refund.clone())).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (_, summary) = app.send(Method::POST, &uri, Some(app.device_id), Some(json!({ "tender": "cash" }))).await;
+        assert_eq!(summary["closed"], true);
+        let cash_id = summary["payments"][1]["id"].as_str().unwrap().to_string();
+
+        let (status, _) = app.send(Method::POST, &refund_uri, None, Some(refund.clone())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let refund_uri = format!("/payments/{}/refunds", cash_id);
+        let (status, recorded) = app.send(Method::POST, &refund_uri, Some(app.device_id), Some(refund)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(recorded["amount"], "-22.00");
+        assert_eq!(recorded["tender"], "cash");
+        assert_eq!(recorded["reason"], "wrong_item");
+        assert_eq!(recorded["refund_of"], cash_id.as_str());
+
+        let too_much = json!({ "reason": "goodwill", "approved_by": "manager", "amount": "5.00" });
+        let (status, body) = app.send(Method::POST, &refund_uri, Some(app.device_id), Some(too_much)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "amount");
+
+        let (_, item) = app.send(Method::GET, &format!("/tables/{}/items/{}", table.id, item_id), None, None).await;
+        assert_eq!(item["refunded_quantity"], 1);
+
+        let (status, report) = app.send(Method::GET, "/reports/daily", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["sales"], "44.00");
+        assert_eq!(report["tips"], "3.00");
+        assert_eq!(report["refunded"], "22.00");
+        assert_eq!(report["net"], "25.00");
+        assert_eq!(report["tenders"][0]["refunds"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_bill_applies_tax_categories_and_service_charge() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+
+        let (status, alcohol) = app
+            .send(Method::POST, "/tax-categories", None, Some(json!({ "name": "Alcohol", "rate": "0.20", "inclusive": true })))
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = app.send(Method::POST, "/tax-categories", None, Some(json!({ "name": "Alcohol", "rate": "0.05" }))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = app.send(Method::POST, "/tax-categories", None, Some(json!({ "name": "Food", "rate": "1.5" }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let service = json!({ "name": "Large party", "rate": "0.125", "min_covers": 8 });
+        let (status, _) = app.send(Method::POST, "/service-charges", None, Some(service)).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let unknown = json!({ "name": "Wine", "price": "30.00", "prep_time": 1, "tax_category_id": Uuid::new_v4() });
+        let (status, _) = app.send(Method::POST, "/menu", None, Some(unknown)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let wine = json!({ "name": "Wine", "price": "30.00", "prep_time": 1, "tax_category_id": alcohol["id"] });
+        let (_, wine) = app.send(Method::POST, "/menu", None, Some(wine)).await;
+        let steak = app.repo.add_menu("Steak".to_string(), Decimal::new(2000, 2), 20, None).await.unwrap();
+
+        // A dish can be moved into a category and back out of it.
+        let steak_uri = format!("/menu/{}", steak.id);
+        let (status, _) = app.send(Method::PUT, &steak_uri, None, Some(json!({ "tax_category_id": { "set": Uuid::new_v4() } }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (_, moved) = app.send(Method::PUT, &steak_uri, None, Some(json!({ "tax_category_id": { "set": alcohol["id"] } }))).await;
+        assert_eq!(moved["tax_category_id"], alcohol["id"]);
+        let (_, repriced) = app.send(Method::PUT, &steak_uri, None, Some(json!({ "prep_time": 20 }))).await;
+        assert_eq!(repriced["tax_category_id"], alcohol["id"]);
+        let (_, cleared) = app.send(Method::PUT, &steak_uri, None, Some(json!({ "tax_category_id": "clear" }))).await;
+        assert_eq!(cleared["tax_category_id"], Value::Null);
+
+        let items = json!({ "items": [{ "quantity": 1, "menu_id": wine["id"] }, { "quantity": 2, "menu_id": steak.id }] });
+        app.send(Method::POST, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(items)).await;
+
+        let bill_uri = format!("/tables/{}/bill", table.id);
+        let (_, bill) = app.send(Method::GET, &bill_uri, None, None).await;
+        assert_eq!(bill["subtotal"], "70.00");
+        assert_eq!(bill["taxes"][0]["name"], "Alcohol");
+        assert_eq!(bill["taxes"][0]["tax"], "5.00");
+        assert_eq!(bill["taxes"][1]["name"], "Tax");
+        assert_eq!(bill["taxes"][1]["tax"], "4.00");
+        assert_eq!(bill["service_charge"], Value::Null);
+        assert_eq!(bill["total"], "74.00");
+
+        let (status, seated) = app.send(Method::PUT, &format!("/tables/{}", table.id), None, Some(json!({ "covers": 8 }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(seated["name"], "Table 1");
+        let (_, bill) = app.send(Method::GET, &bill_uri, None, None).await;
+        assert_eq!(bill["service_charge"]["amount"], "8.75");
+        assert_eq!(bill["total"], "82.75");
+
+        let (_, checks) = app.send(Method::PUT, &format!("/tables/{}/checks", table.id), Some(app.device_id), Some(json!({ "even": 2 }))).await;
+        assert_eq!(checks["checks"][0]["total"], "41.38");
+        assert_eq!(checks["checks"][1]["service_charge"], "4.37");
+    }
+    #[tokio::test]
+    async fn test_discounts_and_promo_codes_show_on_the_bill() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let set_menu = app.repo.add_menu("Set Menu".to_string(), Decimal::new(2000, 2), 20, None).await.unwrap();
+        let soda = app.repo.add_menu("Soda".to_string(), Decimal::new(300, 2), 1, None).await.unwrap();
+
+        let items = json!({ "items": [{ "quantity": 2, "menu_id": set_menu.id }, { "quantity": 1, "menu_id": soda.id }] });
+        let (_, created) = app.send(Method::POST, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(items)).await;
+        let soda_id = created["items"][1]["id"].as_str().unwrap().to_string();
+
+        let uri = format!("/tables/{}/discounts", table.id);
+        let comp = json!({ "item_id": soda_id, "kind": "percent", "value": "100", "reason": "Comp", "applied_by": "Alice" });
+        let (status, _) = app.send(Method::POST, &uri, None, Some(comp.clone())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, bill) = app.send(Method::POST, &uri, Some(app.device_id), Some(comp)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(bill["discounts"][0]["amount"], "3.00");
+        assert_eq!(bill["discounts"][0]["applied_by"], "Alice");
+
+        let unknown = json!({ "item_id": Uuid::new_v4(), "kind": "fixed", "value": "1.00", "applied_by": "Alice" });
+        let (status, body) = app.send(Method::POST, &uri, Some(app.device_id), Some(unknown)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["field"], "item_id");
+
+        let promo = json!({ "code": " summer ", "kind": "percent", "value": "10", "max_uses": 1 });
+        let (status, promo) = app.send(Method::POST, "/promo-codes", None, Some(promo)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(promo["code"], "SUMMER");
+
+        let redeem = json!({ "promo_code": "Summer", "applied_by": "Bob" });
+        let (status, bill) = app.send(Method::POST, &uri, Some(app.device_id), Some(redeem.clone())).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(bill["discounts"][1]["reason"], "Promo code SUMMER");
+        assert_eq!(bill["discounts"][1]["amount"], "4.00");
+        assert_eq!(bill["subtotal"], "43.00");
+        assert_eq!(bill["discount"], "7.00");
+        assert_eq!(bill["tax"], "3.60");
+        assert_eq!(bill["total"], "39.60");
+
+        let (status, body) = app.send(Method::POST, &uri, Some(app.device_id), Some(redeem.clone())).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"][0]["message"], "Promo code SUMMER has been used up");
+
+        // Taking the discount off gives the use back.
+        let discount_id = bill["discounts"][1]["discount_id"].as_str().unwrap().to_string();
+        let (status, _) = app.send(Method::DELETE, &format!("{}/{}", uri, discount_id), Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, promo_codes) = app.send(Method::GET, "/promo-codes", None, None).await;
+        assert_eq!(promo_codes[0]["uses"], 0);
+        let (status, _) = app.send(Method::POST, &uri, Some(app.device_id), Some(redeem)).await;
+        assert_eq!(status, StatusCode::CREATED);
+
+        let (_, discounts) = app.send(Method::GET, &uri, None, None).await;
+        assert_eq!(discounts.as_array().unwrap().len(), 2);
+
+        let payments_uri = format!("/tables/{}/payments", table.id);
+        let (_, summary) = app.send(Method::POST, &payments_uri, Some(app.device_id), Some(json!({ "tender": "card", "amount": "10.00" }))).await;
+        assert_eq!(summary["total"], "39.60");
+        let late = json!({ "kind": "fixed", "value": "1.00", "applied_by": "Alice" });
+        let (status, _) = app.send(Method::POST, &uri, Some(app.device_id), Some(late)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let comp_uri = format!("{}/{}", uri, discounts[0]["discount_id"].as_str().unwrap());
+        let (status, _) = app.send(Method::DELETE, &comp_uri, Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (_, summary) = app.send(Method::POST, &payments_uri, Some(app.device_id), Some(json!({ "tender": "cash" }))).await;
+        assert_eq!(summary["closed"], true);
+        let (_, discounts) = app.send(Method::GET, &uri, None, None).await;
+        assert!(discounts.as_array().unwrap().is_empty());
+    }
+    #[tokio::test]
+    async fn test_happy_hour_price_is_captured_when_ordering() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let beer = app.repo.add_menu("Beer".to_string(), Decimal::new(600, 2), 1, None).await.unwrap();
+
+        let uri = format!("/menu/{}/price-rules", beer.id);
+        let happy_hour = json!({ "name": "Happy hour", "price": "4.00", "days": [5, 1, 2, 3, 4], "start_time": "17:00:00", "end_time": "19:00:00" });
+        let (status, rule) = app.send(Method::POST, &uri, None, Some(happy_hour)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(rule["days"], json!([1, 2, 3, 4, 5]));
+        let invalid = json!({ "name": "Never", "price": "4.00", "days": [0], "start_time": "17:00:00", "end_time": "17:00:00" });
+        let (status, body) = app.send(Method::POST, &uri, None, Some(invalid)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["errors"].as_array().unwrap().len(), 2);
+        let (status, _) = app.send(Method::GET, &format!("/menu/{}/price-rules", Uuid::new_v4()), None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // 2 September 2024 was a Monday.
+        let monday = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        let items_uri = format!("/tables/{}/items", table.id);
+        let order = json!({ "items": [{ "quantity": 2, "menu_id": beer.id }] });
+        app.clock.set(monday.and_hms_opt(17, 45, 0).unwrap());
+        let (_, created) = app.send(Method::POST, &items_uri, Some(app.device_id), Some(order.clone())).await;
+        assert_eq!(created["items"][0]["unit_price"], "4.00");
+        app.clock.set(monday.and_hms_opt(19, 15, 0).unwrap());
+        let (_, created) = app.send(Method::POST, &items_uri, Some(app.device_id), Some(order.clone())).await;
+        assert_eq!(created["items"][0]["unit_price"], "6.00");
+
+        // Changing the rule afterwards leaves the items already ordered alone.
+        let rule_uri = format!("{}/{}", uri, rule["id"].as_str().unwrap());
+        let weekend = json!({ "name": "Weekend", "price": "5.00", "days": [6, 7], "start_time": "12:00:00", "end_time": "02:00:00" });
+        let (status, _) = app.send(Method::PUT, &rule_uri, None, Some(weekend)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, bill) = app.send(Method::GET, &format!("/tables/{}/bill", table.id), None, None).await;
+        assert_eq!(bill["subtotal"], "20.00");
+
+        // Sunday's window runs into Monday morning.
+        app.clock.set(monday.and_hms_opt(1, 30, 0).unwrap());
+        let (_, created) = app.send(Method::POST, &items_uri, Some(app.device_id), Some(order)).await;
+        assert_eq!(created["items"][0]["unit_price"], "5.00");
+
+        let (status, _) = app.send(Method::DELETE, &rule_uri, None, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, rules) = app.send(Method::GET, &uri, None, None).await;
+        assert!(rules.as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_receipt_is_numbered_once_paying_starts() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let menu = app.repo.add_menu("Set Menu".to_string(), Decimal::new(2000, 2), 20, None).await.unwrap();
+        app.clock.set(NaiveDate::from_ymd_opt(2024, 9, 6).unwrap().and_hms_opt(21, 15, 0).unwrap());
+
+        let items = json!({ "items": [{ "quantity": 2, "menu_id": menu.id }] });
+        app.send(Method::POST, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(items)).await;
+
+        let uri = format!("/tables/{}/receipt", table.id);
+        let (status, text) = app.fetch(&uri).await;
+        assert_eq!(status, StatusCode::OK);
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("Provisional bill"));
+        assert!(text.contains("2024-09-06 21:15"));
+        assert!(text.contains(&align("TOTAL", "44.00", 32)));
+
+        let payments_uri = format!("/tables/{}/payments", table.id);
+        let card = json!({ "tender": "card", "amount": "20.00" });
+        app.send(Method::POST, &payments_uri, Some(app.device_id), Some(card)).await;
+        let (_, text) = app.fetch(&uri).await;
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains(&align("Receipt", "#000001", 32)));
+        assert!(text.contains(&align("Balance due", "24.00", 32)));
+
+        let cash = json!({ "tender": "cash", "amount": "24.00" });
+        let (_, summary) = app.send(Method::POST, &payments_uri, Some(app.device_id), Some(cash)).await;
+        assert_eq!(summary["closed"], true);
+
+        let (_, text) = app.fetch(&uri).await;
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains(&align("Card", "20.00", 32)));
+        assert!(text.contains(&align("Cash", "24.00", 32)));
+        assert!(!text.contains("Balance due"));
+        let session_uri = format!("{}?session_id={}", uri, summary["session_id"].as_str().unwrap());
+        let (_, again) = app.fetch(&session_uri).await;
+        assert_eq!(String::from_utf8(again).unwrap(), text);
+
+        let (status, escpos) = app.fetch(&format!("{}?format=escpos", uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(escpos.starts_with(b"\x1b@"));
+        assert!(escpos.ends_with(b"\x1dV\x42\x00"));
+
+        let (status, _) = app.fetch(&format!("{}?session_id={}", uri, Uuid::new_v4())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app.fetch(&format!("/tables/{}/receipt", Uuid::new_v4())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_settled_receipt_keeps_the_rates_it_was_paid_at() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        app.send(Method::PUT, &format!("/tables/{}", table.id), Some(app.device_id), Some(json!({ "covers": 8 }))).await;
+
+        let (_, alcohol) = app
+            .send(Method::POST, "/tax-categories", None, Some(json!({ "name": "Alcohol", "rate": "0.20", "inclusive": true })))
+            .await;
+        let service = json!({ "name": "Large party", "rate": "0.125", "min_covers": 8 });
+        let (_, rule) = app.send(Method::POST, "/service-charges", None, Some(service)).await;
+        let wine = json!({ "name": "Wine", "price": "30.00", "prep_time": 1, "tax_category_id": alcohol["id"] });
+        let (_, wine) = app.send(Method::POST, "/menu", None, Some(wine)).await;
+        let steak = app.repo.add_menu("Steak".to_string(), Decimal::new(2000, 2), 20, None).await.unwrap();
+
+        // A dish can be moved into a category and back out of it.
+        let steak_uri = format!("/menu/{}", steak.id);
+        let (status, _) = app.send(Method::PUT, &steak_uri, None, Some(json!({ "tax_category_id": { "set": Uuid::new_v4() } }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (_, moved) = app.send(Method::PUT, &steak_uri, None, Some(json!({ "tax_category_id": { "set": alcohol["id"] } }))).await;
+        assert_eq!(moved["tax_category_id"], alcohol["id"]);
+        let (_, repriced) = app.send(Method::PUT, &steak_uri, None, Some(json!({ "prep_time": 20 }))).await;
+        assert_eq!(repriced["tax_category_id"], alcohol["id"]);
+        let (_, cleared) = app.send(Method::PUT, &steak_uri, None, Some(json!({ "tax_category_id": "clear" }))).await;
+        assert_eq!(cleared["tax_category_id"], Value::Null);
+
+        let items = json!({ "items": [{ "quantity": 1, "menu_id": wine["id"] }, { "quantity": 2, "menu_id": steak.id }] });
+        app.send(Method::POST, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(items)).await;
+
+        let cash = json!({ "tender": "cash", "amount": "82.75" });
+        let (_, summary) = app.send(Method::POST, &format!("/tables/{}/payments", table.id), Some(app.device_id), Some(cash)).await;
+        assert_eq!(summary["closed"], true);
+        let receipt_uri = format!("/tables/{}/receipt?session_id={}", table.id, summary["session_id"].as_str().unwrap());
+        let (_, paid) = app.fetch(&receipt_uri).await;
+        let paid = String::from_utf8(paid).unwrap();
+        assert!(paid.contains(&align("TOTAL", "82.75", 32)));
+
+        let alcohol_uri = format!("/tax-categories/{}", alcohol["id"].as_str().unwrap());
+        let update = json!({ "name": "Spirits", "rate": "0.25", "inclusive": false });
+        let (status, _) = app.send(Method::PUT, &alcohol_uri, None, Some(update)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app.send(Method::DELETE, &alcohol_uri, None, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let rule_uri = format!("/service-charges/{}", rule["id"].as_str().unwrap());
+        let (status, _) = app.send(Method::DELETE, &rule_uri, None, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (_, settled) = app.fetch(&receipt_uri).await;
+        assert_eq!(String::from_utf8(settled).unwrap(), paid);
+    }
+
+    #[tokio::test]
+    async fn test_closing_the_day_reports_and_freezes_its_sales() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        let menu = app.repo.add_menu("Set Menu".to_string(), Decimal::new(2000, 2), 20, None).await.unwrap();
+        let soup = app.repo.add_menu("Soup".to_string(), Decimal::new(500, 2), 5, None).await.unwrap();
+        app.send(Method::PUT, &format!("/tables/{}", table.id), Some(app.device_id), Some(json!({ "covers": 2 }))).await;
+
+        let items_uri = format!("/tables/{}/items", table.id);
+        let items = json!({ "items": [{ "quantity": 2, "menu_id": menu.id }, { "quantity": 1, "menu_id": soup.id }] });
+        let (_, created) = app.send(Method::POST, &items_uri, Some(app.device_id), Some(items)).await;
+        let item_id = created["items"][0]["id"].as_str().unwrap().to_string();
+        let soup_uri = format!("{}/{}", items_uri, created["items"][1]["id"].as_str().unwrap());
+        app.send(Method::DELETE, &soup_uri, Some(app.device_id), None).await;
+
+        let cash = json!({ "tender": "cash", "amount": "44.00" });
+        let (_, summary) = app.send(Method::POST, &format!("/tables/{}/payments", table.id), Some(app.device_id), Some(cash)).await;
+        assert_eq!(summary["closed"], true);
+        let payment_id = summary["payments"][0]["id"].as_str().unwrap().to_string();
+
+        let (status, _) = app.send(Method::POST, "/reports/z", None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, report) = app.send(Method::POST, "/reports/z", Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(report["number"], 1);
+        assert_eq!(report["opened_at"], Value::Null);
+        let totals = &report["totals"];
+        assert_eq!(totals["sessions"], 1);
+        assert_eq!(totals["covers"], 2);
+        assert_eq!(totals["dishes"], json!([{ "menu_id": menu.id, "name": "Set Menu", "quantity": 2, "gross": "40.00" }]));
+        assert_eq!(totals["gross_sales"], "40.00");
+        assert_eq!(totals["tax"], "4.00");
+        assert_eq!(totals["total_sales"], "44.00");
+        assert_eq!(totals["tenders"][0]["sales"], "44.00");
+        assert_eq!(totals["paid"], "44.00");
+        assert_eq!(totals["voids"], json!({ "items": 1, "quantity": 1, "value": "5.00" }));
+
+        // Nothing the closed day reported can change any more, but its payments
+        // can still be refunded, and the refund goes on the next report.
+        let refund = json!({ "reason": "quality", "approved_by": "Sam", "amount": "5.00" });
+        let (status, _) = app.send(Method::POST, &format!("/payments/{}/refunds", payment_id), Some(app.device_id), Some(refund)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, _) = app.send(Method::POST, &format!("{}/restore", soup_uri), Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let delivery = json!({ "items": [{ "id": item_id, "delivered_quantity": { "set": 2 } }] });
+        app.send(Method::PUT, &items_uri, Some(app.device_id), Some(delivery)).await;
+        let (status, _) = app.send(Method::DELETE, &format!("/tables/{}", table.id), Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (status, next) = app.send(Method::POST, "/reports/z", Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(next["number"], 2);
+        assert_eq!(next["opened_at"], report["closed_at"]);
+        assert_eq!(next["totals"]["sessions"], 0);
+        assert_eq!(next["totals"]["paid"], "0.00");
+        assert_eq!(next["totals"]["refunded"], "5.00");
+        assert_eq!(next["totals"]["net"], "-5.00");
+        assert_eq!(next["totals"]["voids"]["items"], 0);
+
+        let (_, reports) = app.send(Method::GET, "/reports/z", None, None).await;
+        assert_eq!(reports.as_array().unwrap().len(), 2);
+        assert_eq!(reports[0]["number"], 2);
+        let (_, first) = app.send(Method::GET, "/reports/z/1", None, None).await;
+        assert_eq!(first, report);
+        let (status, _) = app.send(Method::GET, "/reports/z/3", None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_closing_the_day_uses_the_rates_tables_paid_at() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Table 1".to_string()).await.unwrap();
+        app.send(Method::PUT, &format!("/tables/{}", table.id), Some(app.device_id), Some(json!({ "covers": 8 }))).await;
+
+        let (_, alcohol) = app
+            .send(Method::POST, "/tax-categories", None, Some(json!({ "name": "Alcohol", "rate": "0.20", "inclusive": true })))
+            .await;
+        let service = json!({ "name": "Large party", "rate": "0.125", "min_covers": 8 });
+        let (_, rule) = app.send(Method::POST, "/service-charges", None, Some(service)).await;
+        let wine = json!({ "name": "Wine", "price": "30.00", "prep_time": 1, "tax_category_id": alcohol["id"] });
+        let (_, wine) = app.send(Method::POST, "/menu", None, Some(wine)).await;
+
+        let items = json!({ "items": [{ "quantity": 1, "menu_id": wine["id"] }] });
+        app.send(Method::POST, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(items)).await;
+        let card = json!({ "tender": "card", "amount": "33.75" });
+        let (_, summary) = app.send(Method::POST, &format!("/tables/{}/payments", table.id), Some(app.device_id), Some(card)).await;
+        assert_eq!(summary["closed"], true);
+
+        let alcohol_uri = format!("/tax-categories/{}", alcohol["id"].as_str().unwrap());
+        app.send(Method::PUT, &alcohol_uri, None, Some(json!({ "rate": "0.25", "inclusive": false }))).await;
+        app.send(Method::DELETE, &format!("/service-charges/{}", rule["id"].as_str().unwrap()), None, None).await;
+
+        let (status, report) = app.send(Method::POST, "/reports/z", Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let totals = &report["totals"];
+        assert_eq!(totals["taxes"][0]["name"], "Alcohol");
+        assert_eq!(totals["taxes"][0]["rate"], "0.2");
+        assert_eq!(totals["tax"], "5.00");
+        assert_eq!(totals["service_charges"], "3.75");
+        assert_eq!(totals["total_sales"], "33.75");
+        assert_eq!(totals["paid"], totals["total_sales"]);
+    }
+
+    #[tokio::test]
+    async fn test_kitchen_queue_puts_the_most_overdue_items_first() {
+        let app = TestApp::new();
+        let terrace = app.repo.add_table("Terrace".to_string()).await.unwrap();
+        let window = app.repo.add_table("Window".to_string()).await.unwrap();
+        let steak = app.repo.add_menu("Steak".to_string(), Decimal::new(2500, 2), 30, None).await.unwrap();
+        let salad = app.repo.add_menu("Salad".to_string(), Decimal::new(900, 2), 5, None).await.unwrap();
+
+        let order = |menu_id: Uuid, quantity: i32| json!({ "items": [{ "quantity": quantity, "menu_id": menu_id }] });
+        app.send(Method::POST, &format!("/tables/{}/items", terrace.id), Some(app.device_id), Some(order(steak.id, 2))).await;
+        let (_, served) = app.send(Method::POST, &format!("/tables/{}/items", window.id), Some(app.device_id), Some(order(salad.id, 1))).await;
+        app.send(Method::POST, &format!("/tables/{}/items", window.id), Some(app.device_id), Some(order(salad.id, 2))).await;
+
+        let served_id = served["items"][0]["id"].as_str().unwrap();
+        app.make_ready(window.id, served_id).await;
+        let delivery = json!({ "items": [{ "id": served_id, "delivered_quantity": { "set": 1 } }] });
+        app.send(Method::PUT, &format!("/tables/{}/items", window.id), Some(app.device_id), Some(delivery)).await;
+
+        let (status, queue) = app.send(Method::GET, "/kitchen/queue", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let entries: Vec<(&str, &str, i64)> = queue
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| (entry["table_name"].as_str().unwrap(), entry["menu_name"].as_str().unwrap(), entry["quantity"].as_i64().unwrap()))
+            .collect();
+        assert_eq!(entries, vec![("Window", "Salad", 2), ("Terrace", "Steak", 2)]);
+        let ordered_at = NaiveDateTime::parse_from_str(queue[1]["created_at"].as_str().unwrap(), "%Y-%m-%dT%H:%M:%S%.f").unwrap();
+        let due_at = NaiveDateTime::parse_from_str(queue[1]["due_at"].as_str().unwrap(), "%Y-%m-%dT%H:%M:%S%.f").unwrap();
+        assert_eq!(due_at - ordered_at, Duration::minutes(30));
+
+        let (_, steaks) = app.send(Method::GET, &format!("/kitchen/queue?menu_id={}", steak.id), None, None).await;
+        assert_eq!(steaks.as_array().unwrap().len(), 1);
+        assert_eq!(steaks[0]["tables_id"], json!(terrace.id));
+    }
+
+    #[tokio::test]
+    async fn test_kitchen_moves_items_through_their_statuses() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Terrace".to_string()).await.unwrap();
+        let soup = app.repo.add_menu("Soup".to_string(), Decimal::new(700, 2), 10, None).await.unwrap();
+
+        let order = json!({ "items": [{ "quantity": 2, "menu_id": soup.id }, { "quantity": 1, "menu_id": soup.id }] });
+        let (_, created) = app.send(Method::POST, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(order)).await;
+        let item_id = created["items"][0]["id"].as_str().unwrap();
+        let other_id = created["items"][1]["id"].as_str().unwrap();
+        let status_uri = format!("/tables/{}/items/{}/status", table.id, item_id);
+
+        let (_, item) = app.send(Method::GET, &format!("/tables/{}/items/{}", table.id, item_id), None, None).await;
+        assert_eq!(item["status"], "ordered");
+
+        let (status, _) = app.send(Method::PUT, &status_uri, None, Some(json!({ "status": "in_preparation" }))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = app.send(Method::PUT, &status_uri, Some(app.device_id), Some(json!({ "status": "served" }))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["message"], format!("Item with id {} is ordered and cannot be marked served", item_id));
+
+        let (status, item) = app.send(Method::PUT, &status_uri, Some(app.device_id), Some(json!({ "status": "in_preparation" }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(item["status"], "in_preparation");
+        assert!(item["started_at"].is_string());
+        let (_, item) = app.send(Method::PUT, &status_uri, Some(app.device_id), Some(json!({ "status": "ready" }))).await;
+        assert_eq!(item["status"], "ready");
+        assert!(item["ready_at"].is_string());
+
+        let (_, queue) = app.send(Method::GET, "/kitchen/queue", None, None).await;
+        let status_of = |id: &str| queue.as_array().unwrap().iter().find(|entry| entry["id"] == id).unwrap()["status"].clone();
+        assert_eq!(status_of(item_id), "ready");
+        assert_eq!(status_of(other_id), "ordered");
+
+        let (status, item) = app.send(Method::PUT, &status_uri, Some(app.device_id), Some(json!({ "status": "served" }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(item["status"], "served");
+        assert_eq!(item["delivered_quantity"], 2);
+        assert!(item["served_at"].is_string());
+        let (status, _) = app.send(Method::PUT, &status_uri, Some(app.device_id), Some(json!({ "status": "served" }))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        // Only ready items can be delivered, and delivering the whole item serves it.
+        let items_uri = format!("/tables/{}/items", table.id);
+        let delivery = json!({ "items": [{ "id": other_id, "delivered_quantity": { "set": 1 } }] });
+        let (status, body) = app.send(Method::PUT, &items_uri, Some(app.device_id), Some(delivery.clone())).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["message"], format!("Item with id {} is ordered and cannot be delivered until it is ready", other_id));
+        let (_, other) = app.send(Method::GET, &format!("/tables/{}/items/{}", table.id, other_id), None, None).await;
+        assert_eq!((other["status"].as_str(), other["delivered_quantity"].as_i64()), (Some("ordered"), Some(0)));
+
+        app.make_ready(table.id, other_id).await;
+        let (status, _) = app.send(Method::PUT, &items_uri, Some(app.device_id), Some(delivery)).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, other) = app.send(Method::GET, &format!("/tables/{}/items/{}", table.id, other_id), None, None).await;
+        assert_eq!(other["status"], "served");
+        let correction = json!({ "items": [{ "id": other_id, "delivered_quantity": { "set": 0 } }] });
+        let (status, _) = app.send(Method::PUT, &items_uri, Some(app.device_id), Some(correction)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let unknown_uri = format!("/tables/{}/items/{}/status", table.id, Uuid::new_v4());
+        let (status, _) = app.send(Method::PUT, &unknown_uri, Some(app.device_id), Some(json!({ "status": "ready" }))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_items_estimate_when_they_will_be_ready_from_the_queue_ahead() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Terrace".to_string()).await.unwrap();
+        let soup = app.repo.add_menu("Soup".to_string(), Decimal::new(700, 2), 10, None).await.unwrap();
+        let time = |value: &Value| NaiveDateTime::parse_from_str(value.as_str().unwrap(), "%Y-%m-%dT%H:%M:%S%.f").unwrap();
+
+        // The orders come in a second apart, so the queue takes them in turn.
+        let mut ids = vec![];
+        for _ in 0..3 {
+            app.clock.set(app.clock.now() + Duration::seconds(1));
+            let order = json!({ "items": [{ "quantity": 1, "menu_id": soup.id }] });
+            let (_, created) = app.send(Method::POST, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(order)).await;
+            ids.push(created["items"][0]["id"].as_str().unwrap().to_string());
+        }
+        let item_uri = |id: &str| format!("/tables/{}/items/{}", table.id, id);
+        let (_, first) = app.send(Method::GET, &item_uri(&ids[0]), None, None).await;
+        let (_, third) = app.send(Method::GET, &item_uri(&ids[2]), None, None).await;
+
+        // Two cooks take the first two soups, the third waits for the first to be done.
+        assert_eq!(time(&first["estimated_ready_at"]) - time(&first["created_at"]), Duration::minutes(10));
+        assert_eq!(time(&third["estimated_ready_at"]) - time(&first["created_at"]), Duration::minutes(20));
+
+        let (_, items) = app.send(Method::GET, &format!("/tables/{}/items", table.id), None, None).await;
+        assert!(items["items"].as_array().unwrap().iter().all(|item| item["estimated_ready_at"].is_string()));
+
+        let (status, eta) = app.send(Method::GET, &format!("/tables/{}/eta", table.id), None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(eta["items"].as_array().unwrap().len(), 3);
+        assert_eq!(eta["items"][2]["id"], ids[2]);
+        assert_eq!(eta["estimated_ready_at"], third["estimated_ready_at"]);
+
+        app.make_ready(table.id, &ids[0]).await;
+        let delivery = json!({ "items": [{ "id": ids[0], "delivered_quantity": { "set": 1 } }] });
+        app.send(Method::PUT, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(delivery)).await;
+        let (_, first) = app.send(Method::GET, &item_uri(&ids[0]), None, None).await;
+        assert!(first["estimated_ready_at"].is_null());
+        let (_, third) = app.send(Method::GET, &item_uri(&ids[2]), None, None).await;
+        assert_eq!(time(&third["estimated_ready_at"]) - time(&third["created_at"]), Duration::minutes(10));
+
+        let (_, eta) = app.send(Method::GET, &format!("/tables/{}/eta", table.id), None, None).await;
+        assert_eq!(eta["items"].as_array().unwrap().len(), 2);
+        let (status, _) = app.send(Method::GET, &format!("/tables/{}/eta", Uuid::new_v4()), None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_overdue_items_raise_alerts_until_delivered() {
+        let app = TestApp::new();
+        let table = app.repo.add_table("Terrace".to_string()).await.unwrap();
+        let soup = app.repo.add_menu("Soup".to_string(), Decimal::new(700, 2), 10, None).await.unwrap();
+        let steak = app.repo.add_menu("Steak".to_string(), Decimal::new(2500, 2), 30, None).await.unwrap();
+        let threshold_uri = format!("/menu/{}/alert-threshold", steak.id);
+
+        let (status, _) = app.send(Method::PUT, &threshold_uri, None, Some(json!({ "grace_minutes": -1 }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        let (status, threshold) = app.send(Method::PUT, &threshold_uri, None, Some(json!({ "grace_minutes": 60 }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(threshold["grace_minutes"], 60);
+        let (status, _) = app.send(Method::GET, &format!("/menu/{}/alert-threshold", soup.id), None, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let order = json!({ "items": [{ "quantity": 1, "menu_id": soup.id }, { "quantity": 1, "menu_id": steak.id }] });
+        let (_, created) = app.send(Method::POST, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(order)).await;
+        let soup_id = created["items"][0]["id"].as_str().unwrap();
+        let started_at = app.clock.now();
+        let (_, mut events) = app.open_events(&format!("/tables/{}/events", table.id), None).await;
+
+        // Soup is overdue after its 10 minutes and 5 of grace, steak only after 30 and 60.
+        app.clock.set(started_at + Duration::minutes(20));
+        let raised = scan_overdue_items(app.publishing.as_ref(), app.clock.as_ref(), 5).await.unwrap();
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].menu_name, "Soup");
+        assert!(scan_overdue_items(app.publishing.as_ref(), app.clock.as_ref(), 5).await.unwrap().is_empty());
+        let streamed = next_events(&mut events, 1).await;
+        assert_eq!(streamed[0].1, "alert_raised");
+        assert_eq!(streamed[0].2["alert"]["items_id"], soup_id);
+
+        let (status, alerts) = app.send(Method::GET, "/alerts", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(alerts.as_array().unwrap().len(), 1);
+        assert_eq!(alerts[0]["items_id"], soup_id);
+        assert_eq!(alerts[0]["table_name"], "Terrace");
+
+        let (status, _) = app.send(Method::DELETE, &threshold_uri, None, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        app.clock.set(started_at + Duration::minutes(40));
+        let raised = scan_overdue_items(app.publishing.as_ref(), app.clock.as_ref(), 5).await.unwrap();
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].menu_name, "Steak");
+
+        app.make_ready(table.id, soup_id).await;
+        let delivery = json!({ "items": [{ "id": soup_id, "delivered_quantity": { "set": 1 } }] });
+        app.send(Method::PUT, &format!("/tables/{}/items", table.id), Some(app.device_id), Some(delivery)).await;
+        let streamed: Vec<(String, Value)> = next_events(&mut events, 3).await.into_iter().map(|(_, event, data)| (event, data)).collect();
+        let kinds: Vec<&str> = streamed.iter().map(|(event, _)| event.as_str()).collect();
+        assert_eq!(kinds, vec!["alert_raised", "item_delivered", "alert_resolved"]);
+        assert_eq!(streamed[0].1["alert"]["menu_name"], "Steak");
+        assert_eq!(streamed[2].1["alert"]["items_id"], soup_id);
+        assert!(streamed[2].1["alert"]["resolved_at"].is_string());
+        let (_, alerts) = app.send(Method::GET, "/alerts", None, None).await;
+        let open: Vec<&str> = alerts.as_array().unwrap().iter().map(|alert| alert["menu_name"].as_str().unwrap()).collect();
+        assert_eq!(open, vec!["Steak"]);
+
+        let (_, alerts) = app.send(Method::GET, "/alerts?include_resolved=true", None, None).await;
+        assert_eq!(alerts.as_array().unwrap().len(), 2);
+        assert_eq!(alerts[1]["items_id"], soup_id);
+        assert!(alerts[1]["resolved_at"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_item_changes_are_streamed_and_resumed() {
+        let app = TestApp::new();
+        let terrace = app.repo.add_table("Terrace".to_string()).await.unwrap();
+        let window = app.repo.add_table("Window".to_string()).await.unwrap();
+        let soup = app.repo.add_menu("Soup".to_string(), Decimal::new(700, 2), 10, None).await.unwrap();
+        let order = json!({ "items": [{ "quantity": 1, "menu_id": soup.id }] });
+
+        let (status, mut all) = app.open_events("/events", None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, created) = app.send(Method::POST, &format!("/tables/{}/items", terrace.id), Some(app.device_id), Some(order.clone())).await;
+        let terrace_item = created["items"][0]["id"].as_str().unwrap().to_string();
+        let (_, created) = app.send(Method::POST, &format!("/tables/{}/items", window.id), Some(app.device_id), Some(order.clone())).await;
+        let window_item = created["items"][0]["id"].as_str().unwrap().to_string();
+        app.make_ready(terrace.id, &terrace_item).await;
+        let delivery = json!({ "items": [{ "id": terrace_item, "delivered_quantity": { "set": 1 } }] });
+        app.send(Method::PUT, &format!("/tables/{}/items", terrace.id), Some(app.device_id), Some(delivery)).await;
+        app.send(Method::DELETE, &format!("/tables/{}/items/{}", window.id, window_item), Some(app.device_id), None).await;
+
+        let events = next_events(&mut all, 4).await;
+        let summary: Vec<(u64, &str, &str)> = events
+            .iter()
+            .map(|(id, event, data)| (*id, event.as_str(), data["item"]["id"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "item_created", terrace_item.as_str()),
+                (2, "item_created", window_item.as_str()),
+                (3, "item_delivered", terrace_item.as_str()),
+                (4, "item_deleted", window_item.as_str()),
+            ]
+        );
+        assert_eq!(events[2].2["item"]["delivered_quantity"], 1);
+        assert_eq!(events[3].2["tables_id"], json!(window.id));
+
+        // A terrace screen that saw event 1 catches up on its table, then follows along.
+        let (status, mut table_events) = app.open_events(&format!("/tables/{}/events", terrace.id), Some(1)).await;
+        assert_eq!(status, StatusCode::OK);
+        app.send(Method::POST, &format!("/tables/{}/items", window.id), Some(app.device_id), Some(order.clone())).await;
+        app.send(Method::POST, &format!("/tables/{}/items", terrace.id), Some(app.device_id), Some(order)).await;
+        let events = next_events(&mut table_events, 2).await;
+        let summary: Vec<(u64, &str)> = events.iter().map(|(id, event, _)| (*id, event.as_str())).collect();
+        assert_eq!(summary, vec![(3, "item_delivered"), (6, "item_created")]);
+
+        // Item changes made outside the item routes are published too, such as settling the bill.
+        let payment = json!({ "tender": "cash" });
+        let (_, summary) = app.send(Method::POST, &format!("/tables/{}/payments", terrace.id), Some(app.device_id), Some(payment)).await;
+        assert_eq!(summary["closed"], true);
+        let events = next_events(&mut table_events, 2).await;
+        assert!(events.iter().all(|(_, event, data)| event == "item_updated" && data["item"]["paid_at"].is_string()));
+
+        // Deleting a table deletes its items.
+        let bar = app.repo.add_table("Bar".to_string()).await.unwrap();
+        let (_, mut bar_events) = app.open_events(&format!("/tables/{}/events", bar.id), Some(8)).await;
+        let order = json!({ "items": [{ "quantity": 1, "menu_id": soup.id }] });
+        let (_, created) = app.send(Method::POST, &format!("/tables/{}/items", bar.id), Some(app.device_id), Some(order)).await;
+        let bar_item = created["items"][0]["id"].as_str().unwrap().to_string();
+        app.make_ready(bar.id, &bar_item).await;
+        let delivery = json!({ "items": [{ "id": bar_item, "delivered_quantity": { "set": 1 } }] });
+        app.send(Method::PUT, &format!("/tables/{}/items", bar.id), Some(app.device_id), Some(delivery)).await;
+        let (status, _) = app.send(Method::DELETE, &format!("/tables/{}", bar.id), Some(app.device_id), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let events = next_events(&mut bar_events, 3).await;
+        let summary: Vec<(u64, &str)> = events.iter().map(|(id, event, _)| (*id, event.as_str())).collect();
+        assert_eq!(summary, vec![(9, "item_created"), (10, "item_delivered"), (11, "item_deleted")]);
+
+        // A screen that last saw an event of an earlier run is told to refetch,
+        // and resumes after the latest event.
+        let (_, mut stale) = app.open_events("/events", Some(1000)).await;
+        let events = next_events(&mut stale, 1).await;
+        assert_eq!((events[0].0, events[0].1.as_str()), (11, "resync"));
+
+        let (status, _) = app.open_events(&format!("/tables/{}/events", Uuid::new_v4()), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
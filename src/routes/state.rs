@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use rust_decimal::Decimal;
+
+use crate::clock::Clock;
+use crate::config::ReceiptSettings;
+use crate::db::repository::Repository;
+use crate::events::items::ItemEvents;
+
+/// State shared by every handler. Handlers that only need storage can keep
+/// extracting `State<Arc<dyn Repository>>`.
+#[derive(Clone)]
+pub struct AppState {
+    pub repo: Arc<dyn Repository>,
+    pub tax_rate: Decimal,
+    pub clock: Arc<dyn Clock>,
+    pub receipt: Arc<ReceiptSettings>,
+    pub kitchen_cooks: usize,
+    pub events: Arc<ItemEvents>,
+}
+
+impl FromRef<AppState> for Arc<dyn Repository> {
+    fn from_ref(state: &AppState) -> Self {
+        state.repo.clone()
+    }
+}
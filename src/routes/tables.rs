@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use crate::db::connection::{DeleteTableOutcome, NewTableRequest, UpdateTableRequest};
+use crate::db::repository::Repository;
+use crate::error::{AppError, OrNotFound};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use uuid::Uuid;
+
+fn table_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::invalid("Table name must not be empty"));
+    }
+    Ok(name.to_string())
+}
+
+/// Fails with `404` unless the table exists.
+pub(super) async fn ensure_table(repo: &dyn Repository, tables_id: Uuid) -> Result<(), AppError> {
+    repo.get_table(tables_id)
+        .await
+        .or_not_found(|| format!("Table with id {} not found", tables_id))?;
+    Ok(())
+}
+
+pub async fn tables_list(
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let tables = repo.get_tables().await?;
+    info!("{} tables found", tables.len());
+
+    Ok(Json(tables))
+}
+
+pub async fn table_create(
+    State(repo): State<Arc<dyn Repository>>,
+    Json(new_table): Json<NewTableRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = table_name(&new_table.name)?;
+
+    info!("Creating new table: {}", name);
+    let table = repo.add_table(name).await?;
+
+    Ok((StatusCode::CREATED, Json(table)))
+}
+
+pub async fn table_get(
+    Path(tables_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Get table {}", tables_id);
+    let table = repo
+        .get_table(tables_id)
+        .await
+        .or_not_found(|| format!("Table with id {} not found", tables_id))?;
+
+    Ok(Json(table))
+}
+
+pub async fn table_update(
+    Path(tables_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+    Json(updated_table): Json<UpdateTableRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = updated_table.name.as_deref().map(table_name).transpose()?;
+    if let Some(covers) = updated_table.covers {
+        if covers <= 0 {
+            return Err(AppError::invalid("Covers must be greater than zero"));
+        }
+    }
+
+    info!("Updating table {}: name {:?}, covers {:?}", tables_id, name, updated_table.covers);
+    let table = repo
+        .update_table(tables_id, UpdateTableRequest { name, covers: updated_table.covers })
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Table with id {} not found", tables_id)))?;
+
+    Ok(Json(table))
+}
+
+pub async fn table_delete(
+    Path(tables_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Trying to delete table {}", tables_id);
+    match repo.delete_table(tables_id).await? {
+        DeleteTableOutcome::Deleted(_) => Ok(StatusCode::NO_CONTENT),
+        DeleteTableOutcome::NotFound => Err(AppError::NotFound(format!("Table with id {} not found", tables_id))),
+        DeleteTableOutcome::PendingItems(count) => {
+            info!("Table {} still has {} undelivered items", tables_id, count);
+            Err(AppError::Conflict(format!("Table with id {} still has {} undelivered items", tables_id, count)))
+        }
+        DeleteTableOutcome::HasSales => Err(AppError::Conflict(format!(
+            "Table with id {} has taken payments or voided items and can no longer be deleted",
+            tables_id
+        ))),
+    }
+}
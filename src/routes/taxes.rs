@@ -0,0 +1,167 @@
+use std::sync::Arc;
+use crate::db::connection::{NewServiceChargeRuleRequest, NewTaxCategoryRequest, UpdateTaxCategoryRequest};
+use crate::db::repository::Repository;
+use crate::error::AppError;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use log::info;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// Rates are stored as `DECIMAL(6, 4)`, e.g. `0.125` for 12.5%.
+fn validate_rate(rate: Decimal) -> Result<Decimal, AppError> {
+    let rate = rate.normalize();
+    if (rate.is_sign_negative() && !rate.is_zero()) || rate > Decimal::ONE {
+        return Err(AppError::invalid("Rate must be between 0 and 1"));
+    }
+    if rate.scale() > 4 {
+        return Err(AppError::invalid("Rate must have at most four decimal places"));
+    }
+    Ok(rate.abs())
+}
+
+fn validate_service_charge_rate(rate: Decimal) -> Result<Decimal, AppError> {
+    let rate = validate_rate(rate)?;
+    if rate.is_zero() {
+        return Err(AppError::invalid("Service charge rate must be greater than zero"));
+    }
+    Ok(rate)
+}
+
+fn validate_name(name: &str, what: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::invalid(format!("{} name must not be empty", what)));
+    }
+    Ok(name.to_string())
+}
+
+async fn ensure_unique_name(repo: &dyn Repository, name: &str, tax_category_id: Option<Uuid>) -> Result<(), AppError> {
+    let categories = repo.get_tax_categories().await?;
+    if categories
+        .iter()
+        .any(|category| category.name == name && Some(category.id) != tax_category_id)
+    {
+        return Err(AppError::Conflict(format!("Tax category {} already exists", name)));
+    }
+    Ok(())
+}
+
+pub async fn tax_categories_list(
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let categories = repo.get_tax_categories().await?;
+    info!("{} tax categories found", categories.len());
+
+    Ok(Json(categories))
+}
+
+pub async fn tax_category_create(
+    State(repo): State<Arc<dyn Repository>>,
+    Json(new_category): Json<NewTaxCategoryRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = validate_name(&new_category.name, "Tax category")?;
+    let rate = validate_rate(new_category.rate)?;
+    ensure_unique_name(repo.as_ref(), &name, None).await?;
+
+    info!("Adding tax category {} at {}", name, rate);
+    let category = repo
+        .add_tax_category(NewTaxCategoryRequest {
+            name,
+            rate,
+            inclusive: new_category.inclusive,
+        })
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+pub async fn tax_category_update(
+    Path(tax_category_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+    Json(updated_category): Json<UpdateTaxCategoryRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = updated_category
+        .name
+        .as_deref()
+        .map(|name| validate_name(name, "Tax category"))
+        .transpose()?;
+    let rate = updated_category.rate.map(validate_rate).transpose()?;
+    if let Some(name) = &name {
+        ensure_unique_name(repo.as_ref(), name, Some(tax_category_id)).await?;
+    }
+
+    info!("Updating tax category {}", tax_category_id);
+    let category = repo
+        .update_tax_category(
+            tax_category_id,
+            UpdateTaxCategoryRequest {
+                name,
+                rate,
+                inclusive: updated_category.inclusive,
+            },
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Tax category with id {} not found", tax_category_id)))?;
+
+    Ok(Json(category))
+}
+
+pub async fn tax_category_delete(
+    Path(tax_category_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Deleting tax category {}", tax_category_id);
+    if !repo.delete_tax_category(tax_category_id).await? {
+        return Err(AppError::NotFound(format!("Tax category with id {} not found", tax_category_id)));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn service_charges_list(
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    let rules = repo.get_service_charge_rules().await?;
+    info!("{} service charge rules found", rules.len());
+
+    Ok(Json(rules))
+}
+
+pub async fn service_charge_create(
+    State(repo): State<Arc<dyn Repository>>,
+    Json(new_rule): Json<NewServiceChargeRuleRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = validate_name(&new_rule.name, "Service charge")?;
+    let rate = validate_service_charge_rate(new_rule.rate)?;
+    if new_rule.min_covers <= 0 {
+        return Err(AppError::invalid("Minimum covers must be greater than zero"));
+    }
+
+    info!("Adding service charge {} of {} from {} covers", name, rate, new_rule.min_covers);
+    let rule = repo
+        .add_service_charge_rule(NewServiceChargeRuleRequest {
+            name,
+            rate,
+            min_covers: new_rule.min_covers,
+        })
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+pub async fn service_charge_delete(
+    Path(rule_id): Path<Uuid>,
+    State(repo): State<Arc<dyn Repository>>,
+) -> Result<impl IntoResponse, AppError> {
+    info!("Deleting service charge rule {}", rule_id);
+    if !repo.delete_service_charge_rule(rule_id).await? {
+        return Err(AppError::NotFound(format!("Service charge rule with id {} not found", rule_id)));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
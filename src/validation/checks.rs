@@ -0,0 +1,81 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::db::connection::NewCheckRequest;
+use crate::models::route_models::FieldError;
+use crate::validation::field_error;
+
+/// Most checks a bill can be split into, which also bounds the share denominators.
+pub const MAX_CHECKS: i32 = 100;
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// Adds two fractions, reduced. `None` once the denominators grow too large to combine.
+fn add_fraction((a, b): (i128, i128), (c, d): (i128, i128)) -> Option<(i128, i128)> {
+    let numerator = a.checked_mul(d)?.checked_add(c.checked_mul(b)?)?;
+    let denominator = b.checked_mul(d)?;
+    let divisor = gcd(numerator, denominator);
+    Some((numerator / divisor, denominator / divisor))
+}
+
+/// Checks a split against the items currently on the table. Errors point at the
+/// check by index, and at the share within it through the field name.
+pub fn validate_checks(checks: &[NewCheckRequest], table_item_ids: &HashSet<Uuid>) -> Vec<FieldError> {
+    let mut errors = vec![];
+    let mut allocated: HashMap<Uuid, (i128, i128)> = HashMap::new();
+
+    if checks.is_empty() || checks.len() > MAX_CHECKS as usize {
+        errors.push(field_error(0, "checks".to_string(), format!("A bill is split into 1 to {} checks", MAX_CHECKS)));
+        return errors;
+    }
+
+    for (index, check) in checks.iter().enumerate() {
+        if check.name.trim().is_empty() {
+            errors.push(field_error(index, "name".to_string(), "Check name must not be empty".to_string()));
+        }
+
+        let mut on_check = HashSet::new();
+        for (share_index, share) in check.items.iter().enumerate() {
+            let field = |name: &str| format!("items[{}].{}", share_index, name);
+
+            if !table_item_ids.contains(&share.item_id) {
+                errors.push(field_error(index, field("item_id"), format!("Unknown item {}", share.item_id)));
+                continue;
+            }
+            if !on_check.insert(share.item_id) {
+                errors.push(field_error(index, field("item_id"), format!("Item {} is on this check more than once", share.item_id)));
+                continue;
+            }
+            if share.denominator <= 0 || share.denominator > MAX_CHECKS {
+                errors.push(field_error(index, field("denominator"), format!("Denominator must be between 1 and {}", MAX_CHECKS)));
+                continue;
+            }
+            if share.numerator <= 0 || share.numerator > share.denominator {
+                errors.push(field_error(index, field("numerator"), "Share must be more than nothing and at most the whole item".to_string()));
+                continue;
+            }
+
+            let total = allocated.get(&share.item_id).copied().unwrap_or((0, 1));
+            match add_fraction(total, (share.numerator as i128, share.denominator as i128)) {
+                Some((numerator, denominator)) if numerator <= denominator => {
+                    allocated.insert(share.item_id, (numerator, denominator));
+                }
+                Some(_) => errors.push(field_error(
+                    index,
+                    field("numerator"),
+                    format!("Item {} is split into more than one whole", share.item_id),
+                )),
+                None => errors.push(field_error(
+                    index,
+                    field("denominator"),
+                    format!("Shares of item {} cannot be combined", share.item_id),
+                )),
+            }
+        }
+    }
+
+    errors
+}
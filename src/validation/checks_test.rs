@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use uuid::Uuid;
+
+    use crate::db::connection::{CheckItemShare, NewCheckRequest};
+    use crate::validation::checks::validate_checks;
+
+    fn share(item_id: Uuid, numerator: i32, denominator: i32) -> CheckItemShare {
+        CheckItemShare { item_id, numerator, denominator }
+    }
+
+    #[test]
+    fn test_checks_report_invalid_shares() {
+        let item_id = Uuid::new_v4();
+        let other_id = Uuid::new_v4();
+        let items: HashSet<Uuid> = [item_id, other_id].into_iter().collect();
+
+        let checks = vec![
+            NewCheckRequest {
+                name: "Seat 1".to_string(),
+                items: vec![share(item_id, 1, 3), share(other_id, 1, 1)],
+            },
+            NewCheckRequest {
+                name: "Seat 2".to_string(),
+                items: vec![share(item_id, 2, 3)],
+            },
+        ];
+        assert!(validate_checks(&checks, &items).is_empty());
+
+        let checks = vec![
+            NewCheckRequest {
+                name: " ".to_string(),
+                items: vec![share(item_id, 1, 2), share(item_id, 1, 2), share(Uuid::new_v4(), 1, 1)],
+            },
+            NewCheckRequest {
+                name: "Seat 2".to_string(),
+                items: vec![share(item_id, 2, 3), share(other_id, 0, 1), share(other_id, 1, 101)],
+            },
+        ];
+        let fields: Vec<(usize, String)> = validate_checks(&checks, &items)
+            .into_iter()
+            .map(|error| (error.index, error.field))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (0, "name".to_string()),
+                (0, "items[1].item_id".to_string()),
+                (0, "items[2].item_id".to_string()),
+                (1, "items[0].numerator".to_string()),
+                (1, "items[1].numerator".to_string()),
+                (1, "items[2].item_id".to_string()),
+            ]
+        );
+
+        assert_eq!(validate_checks(&[], &items)[0].field, "checks");
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+
+use crate::db::connection::{NewDiscountRequest, NewPromoCodeRequest};
+use crate::models::{
+    restaurant_models::{DiscountKind, PromoCode},
+    route_models::FieldError,
+};
+use crate::validation::field_error;
+
+/// Percentages go up to 100, fixed amounts must be whole cents.
+fn validate_value(kind: DiscountKind, value: Decimal) -> Option<FieldError> {
+    if value <= Decimal::ZERO || value.normalize().scale() > 2 {
+        return Some(field_error(0, "value", "Value must be greater than zero, with at most two decimals".to_string()));
+    }
+    if kind == DiscountKind::Percent && value > Decimal::ONE_HUNDRED {
+        return Some(field_error(0, "value", "A percentage cannot be more than 100".to_string()));
+    }
+    None
+}
+
+/// Checks a discount request. A promo code carries its own kind and value, so
+/// they can only be given for manual discounts.
+pub fn validate_new_discount(request: &NewDiscountRequest) -> Vec<FieldError> {
+    let mut errors = vec![];
+    if request.applied_by.trim().is_empty() {
+        errors.push(field_error(0, "applied_by", "Name the staff member applying the discount".to_string()));
+    }
+
+    match (&request.promo_code, request.kind, request.value) {
+        (Some(_), None, None) => {}
+        (Some(_), _, _) => errors.push(field_error(
+            0,
+            "promo_code",
+            "A promo code cannot be combined with a kind or value".to_string(),
+        )),
+        (None, Some(kind), Some(value)) => errors.extend(validate_value(kind, value)),
+        (None, None, _) => errors.push(field_error(0, "kind", "Give a kind and value, or a promo code".to_string())),
+        (None, Some(_), None) => errors.push(field_error(0, "value", "Give a kind and value, or a promo code".to_string())),
+    }
+
+    errors
+}
+
+pub fn validate_promo_code(request: &NewPromoCodeRequest) -> Vec<FieldError> {
+    let mut errors = vec![];
+    if request.code.trim().is_empty() {
+        errors.push(field_error(0, "code", "Code must not be empty".to_string()));
+    }
+    errors.extend(validate_value(request.kind, request.value));
+    if let (Some(valid_from), Some(valid_until)) = (request.valid_from, request.valid_until) {
+        if valid_until <= valid_from {
+            errors.push(field_error(0, "valid_until", "The code must stop being valid after it starts".to_string()));
+        }
+    }
+    if request.max_uses.is_some_and(|max_uses| max_uses <= 0) {
+        errors.push(field_error(0, "max_uses", "Maximum uses must be greater than zero".to_string()));
+    }
+    errors
+}
+
+/// Why a promo code cannot be redeemed at `now`, if it cannot.
+pub fn promo_code_unusable(promo_code: &PromoCode, now: NaiveDateTime) -> Option<String> {
+    if promo_code.valid_from.is_some_and(|valid_from| now < valid_from) {
+        return Some(format!("Promo code {} is not valid yet", promo_code.code));
+    }
+    if promo_code.valid_until.is_some_and(|valid_until| now >= valid_until) {
+        return Some(format!("Promo code {} has expired", promo_code.code));
+    }
+    if promo_code.max_uses.is_some_and(|max_uses| promo_code.uses >= max_uses) {
+        return Some(format!("Promo code {} has been used up", promo_code.code));
+    }
+    None
+}
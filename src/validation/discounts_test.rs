@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use crate::db::connection::{NewDiscountRequest, NewPromoCodeRequest};
+    use crate::models::{restaurant_models::{DiscountKind, PromoCode}, route_models::FieldError};
+    use crate::validation::discounts::{promo_code_unusable, validate_new_discount, validate_promo_code};
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 9, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn discount(kind: Option<DiscountKind>, value: Option<Decimal>, promo_code: Option<&str>) -> NewDiscountRequest {
+        NewDiscountRequest {
+            item_id: None,
+            kind,
+            value,
+            promo_code: promo_code.map(str::to_string),
+            reason: None,
+            applied_by: "Alice".to_string(),
+        }
+    }
+
+    fn fields(errors: Vec<FieldError>) -> Vec<String> {
+        errors.into_iter().map(|error| error.field).collect()
+    }
+
+    #[test]
+    fn test_discounts_need_a_value_or_a_promo_code() {
+        assert!(validate_new_discount(&discount(Some(DiscountKind::Percent), Some(Decimal::from(100)), None)).is_empty());
+        assert!(validate_new_discount(&discount(Some(DiscountKind::Fixed), Some(Decimal::new(250, 2)), None)).is_empty());
+        assert!(validate_new_discount(&discount(None, None, Some("summer"))).is_empty());
+
+        assert_eq!(fields(validate_new_discount(&discount(None, None, None))), vec!["kind"]);
+        assert_eq!(fields(validate_new_discount(&discount(Some(DiscountKind::Fixed), None, None))), vec!["value"]);
+        assert_eq!(
+            fields(validate_new_discount(&discount(Some(DiscountKind::Fixed), Some(Decimal::from(5)), Some("summer")))),
+            vec!["promo_code"]
+        );
+        assert_eq!(fields(validate_new_discount(&discount(Some(DiscountKind::Percent), Some(Decimal::new(1005, 1)), None))), vec!["value"]);
+        assert_eq!(fields(validate_new_discount(&discount(Some(DiscountKind::Fixed), Some(Decimal::from(0)), None))), vec!["value"]);
+        assert_eq!(fields(validate_new_discount(&discount(Some(DiscountKind::Fixed), Some(Decimal::new(1005, 3)), None))), vec!["value"]);
+
+        let mut anonymous = discount(Some(DiscountKind::Fixed), Some(Decimal::from(1)), None);
+        anonymous.applied_by = " ".to_string();
+        assert_eq!(fields(validate_new_discount(&anonymous)), vec!["applied_by"]);
+    }
+
+    #[test]
+    fn test_promo_codes_are_checked_against_their_window_and_uses() {
+        let request = NewPromoCodeRequest {
+            code: " ".to_string(),
+            kind: DiscountKind::Percent,
+            value: Decimal::from(150),
+            valid_from: Some(at(2, 0)),
+            valid_until: Some(at(1, 0)),
+            max_uses: Some(0),
+        };
+        assert_eq!(fields(validate_promo_code(&request)), vec!["code", "value", "valid_until", "max_uses"]);
+
+        let mut promo_code = PromoCode {
+            id: Uuid::new_v4(),
+            code: "SUMMER".to_string(),
+            kind: DiscountKind::Percent,
+            value: Decimal::from(10),
+            valid_from: Some(at(1, 12)),
+            valid_until: Some(at(8, 12)),
+            max_uses: Some(2),
+            uses: 1,
+            created_at: at(1, 0),
+        };
+        assert!(promo_code_unusable(&promo_code, at(1, 12)).is_none());
+        assert_eq!(promo_code_unusable(&promo_code, at(1, 11)).unwrap(), "Promo code SUMMER is not valid yet");
+        assert_eq!(promo_code_unusable(&promo_code, at(8, 12)).unwrap(), "Promo code SUMMER has expired");
+
+        promo_code.uses = 2;
+        assert_eq!(promo_code_unusable(&promo_code, at(2, 12)).unwrap(), "Promo code SUMMER has been used up");
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::db::connection::{NewItemRequest, UpdateItemRequest};
+use crate::error::AppError;
+use crate::models::route_models::FieldError;
+use crate::validation::field_error;
+
+/// Checks new items against the dishes that can currently be ordered.
+pub fn validate_new_items(items: &[NewItemRequest], orderable_menu_ids: &HashSet<Uuid>) -> Vec<FieldError> {
+    let mut errors = vec![];
+
+    for (index, item) in items.iter().enumerate() {
+        if item.quantity <= 0 {
+            errors.push(field_error(index, "quantity", "Quantity must be greater than zero".to_string()));
+        }
+        if !orderable_menu_ids.contains(&item.menu_id) {
+            errors.push(field_error(index, "menu_id", format!("Unknown menu item {}", item.menu_id)));
+        }
+    }
+
+    errors
+}
+
+/// Checks updates against the stored `(quantity, delivered_quantity)` of each item.
+/// Items missing from `current` are left to the update itself, which skips them.
+pub fn validate_item_updates(items: &[UpdateItemRequest], current: &HashMap<Uuid, (i32, i32)>) -> Vec<FieldError> {
+    let mut errors = vec![];
+    let mut seen = HashSet::new();
+
+    for (index, item) in items.iter().enumerate() {
+        if !seen.insert(item.id) {
+            errors.push(field_error(index, "id", format!("Item {} is updated more than once", item.id)));
+            continue;
+        }
+
+        let Some(&(quantity, delivered_quantity)) = current.get(&item.id) else {
+            continue;
+        };
+
+        let quantity = item.quantity.map_or(quantity, |update| update.apply(quantity));
+        let delivered_quantity = item
+            .delivered_quantity
+            .map_or(delivered_quantity, |update| update.apply(delivered_quantity));
+
+        if quantity <= 0 {
+            errors.push(field_error(index, "quantity", "Quantity must be greater than zero".to_string()));
+        }
+        if delivered_quantity < 0 {
+            errors.push(field_error(
+                index,
+                "delivered_quantity",
+                "Delivered quantity must not be negative".to_string(),
+            ));
+        } else if delivered_quantity > quantity {
+            errors.push(field_error(
+                index,
+                "delivered_quantity",
+                format!("Delivered quantity {} exceeds quantity {}", delivered_quantity, quantity),
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Like `validate_item_updates`, failing with `422` and the field errors.
+pub fn check_item_updates(items: &[UpdateItemRequest], current: &HashMap<Uuid, (i32, i32)>) -> Result<(), AppError> {
+    let errors = validate_item_updates(items, current);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::Validation {
+        message: "Some item updates are invalid".to_string(),
+        errors,
+    })
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use uuid::Uuid;
+
+    use crate::db::connection::{FieldUpdate, NewItemRequest, UpdateItemRequest};
+    use crate::validation::items::{validate_item_updates, validate_new_items};
+
+    #[test]
+    fn test_new_items_report_index_and_field() {
+        let menu_id = Uuid::new_v4();
+        let unknown_menu_id = Uuid::new_v4();
+        let orderable: HashSet<Uuid> = [menu_id].into_iter().collect();
+
+        let items = vec![
+            NewItemRequest { quantity: 2, menu_id },
+            NewItemRequest { quantity: 0, menu_id },
+            NewItemRequest { quantity: -1, menu_id: unknown_menu_id },
+        ];
+        let errors = validate_new_items(&items, &orderable);
+
+        let fields: Vec<(usize, &str)> = errors.iter().map(|error| (error.index, error.field.as_str())).collect();
+        assert_eq!(fields, vec![(1, "quantity"), (2, "quantity"), (2, "menu_id")]);
+    }
+
+    #[test]
+    fn test_updates_check_resulting_quantities() {
+        let first_id = Uuid::new_v4();
+        let second_id = Uuid::new_v4();
+        let unknown_id = Uuid::new_v4();
+        let current: HashMap<Uuid, (i32, i32)> = [(first_id, (3, 1)), (second_id, (2, 0))].into_iter().collect();
+
+        let items = vec![
+            UpdateItemRequest {
+                id: first_id,
+                quantity: None,
+                delivered_quantity: Some(FieldUpdate::Increment(2)),
+            },
+            UpdateItemRequest {
+                id: second_id,
+                quantity: Some(FieldUpdate::Set(1)),
+                delivered_quantity: Some(FieldUpdate::Set(2)),
+            },
+            UpdateItemRequest {
+                id: unknown_id,
+                quantity: Some(FieldUpdate::Set(-5)),
+                delivered_quantity: None,
+            },
+        ];
+        assert_eq!(validate_item_updates(&items, &current).len(), 1);
+
+        let errors = validate_item_updates(&items[1..], &current);
+        assert_eq!(errors[0].index, 0);
+        assert_eq!(errors[0].field, "delivered_quantity");
+
+        let items = vec![
+            UpdateItemRequest {
+                id: first_id,
+                quantity: Some(FieldUpdate::Increment(-3)),
+                delivered_quantity: Some(FieldUpdate::Increment(-2)),
+            },
+            UpdateItemRequest {
+                id: first_id,
+                quantity: None,
+                delivered_quantity: None,
+            },
+        ];
+        let fields: Vec<(usize, String)> = validate_item_updates(&items, &current)
+            .into_iter()
+            .map(|error| (error.index, error.field))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                (0, "quantity".to_string()),
+                (0, "delivered_quantity".to_string()),
+                (1, "id".to_string()),
+            ]
+        );
+    }
+}
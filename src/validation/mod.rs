@@ -0,0 +1,50 @@
+use rust_decimal::Decimal;
+
+use crate::billing::bill::round_money;
+use crate::models::route_models::FieldError;
+
+pub mod checks;
+pub mod discounts;
+pub mod items;
+pub mod payments;
+pub mod price_rules;
+pub mod refunds;
+mod checks_test;
+mod discounts_test;
+mod items_test;
+mod payments_test;
+mod price_rules_test;
+mod refunds_test;
+
+/// A problem with a field of the request. `index` is the entry of the list
+/// the field belongs to, `0` for requests without a list.
+pub fn field_error(index: usize, field: impl Into<String>, message: impl Into<String>) -> FieldError {
+    FieldError {
+        index,
+        field: field.into(),
+        message: message.into(),
+    }
+}
+
+/// Prices are stored as `DECIMAL(10, 2)`, so they have to stay below 10^8.
+pub const PRICE_LIMIT: Decimal = Decimal::from_parts(100_000_000, 0, 0, false, 0);
+
+/// Checks a price of a dish or price rule, returning it normalized.
+pub fn validate_price(price: Decimal) -> Result<Decimal, String> {
+    let price = price.normalize();
+    if price.is_sign_negative() && !price.is_zero() {
+        return Err("Price must not be negative".to_string());
+    }
+    if price.scale() > 2 {
+        return Err("Price must have at most two decimal places".to_string());
+    }
+    if price >= PRICE_LIMIT {
+        return Err(format!("Price must be lower than {}", PRICE_LIMIT));
+    }
+    Ok(price.abs().round_dp(2))
+}
+
+/// Whether the amount is a whole number of cents.
+pub fn whole_cents(amount: Decimal) -> bool {
+    round_money(amount) == amount
+}
\ No newline at end of file
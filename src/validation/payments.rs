@@ -0,0 +1,60 @@
+use rust_decimal::Decimal;
+
+use crate::db::connection::{NewPayment, NewPaymentRequest};
+use crate::models::{restaurant_models::Tender, route_models::FieldError};
+use crate::validation::{field_error, whole_cents};
+
+/// Checks a payment against what is left to pay, on the check too when it
+/// names one, and works out the change. Only cash gives change.
+pub fn prepare_payment(
+    request: &NewPaymentRequest,
+    balance: Decimal,
+    check_balance: Option<Decimal>,
+) -> Result<NewPayment, Vec<FieldError>> {
+    let payable = check_balance.map_or(balance, |check_balance| check_balance.min(balance));
+    if payable <= Decimal::ZERO {
+        return Err(vec![field_error(0, "amount", "Nothing is left to pay".to_string())]);
+    }
+
+    let mut errors = vec![];
+    let amount = request.amount.unwrap_or(payable);
+    if amount <= Decimal::ZERO || !whole_cents(amount) {
+        errors.push(field_error(0, "amount", "Amount must be a positive amount in whole cents".to_string()));
+    } else if amount > payable {
+        errors.push(field_error(0, "amount", format!("Amount exceeds the {} left to pay", payable)));
+    }
+
+    let tip = request.tip.unwrap_or(Decimal::ZERO);
+    if tip < Decimal::ZERO || !whole_cents(tip) {
+        errors.push(field_error(0, "tip", "Tip must be zero or more, in whole cents".to_string()));
+    } else if tip > Decimal::ZERO && request.tender == Tender::Voucher {
+        errors.push(field_error(0, "tip", "Vouchers cannot be used for tips".to_string()));
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let due = amount + tip;
+    let tendered = request.tendered.unwrap_or(due);
+    if !whole_cents(tendered) {
+        errors.push(field_error(0, "tendered", "Tendered amount must be in whole cents".to_string()));
+    } else if request.tender == Tender::Cash && tendered < due {
+        errors.push(field_error(0, "tendered", format!("Cash tendered is less than the {} due", due)));
+    } else if request.tender != Tender::Cash && tendered != due {
+        errors.push(field_error(0, "tendered", "Only cash payments can be tendered for more than is due".to_string()));
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(NewPayment {
+        check_id: request.check_id,
+        tender: request.tender,
+        amount,
+        tip,
+        tendered,
+        change: tendered - due,
+    })
+}
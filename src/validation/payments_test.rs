@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use crate::validation::payments::prepare_payment;
+    use crate::db::connection::NewPaymentRequest;
+    use crate::models::restaurant_models::Tender;
+
+    fn request(tender: Tender, amount: Option<Decimal>, tip: Option<Decimal>, tendered: Option<Decimal>) -> NewPaymentRequest {
+        NewPaymentRequest {
+            tender,
+            amount,
+            tip,
+            tendered,
+            check_id: None,
+        }
+    }
+
+    fn fields(request: &NewPaymentRequest, balance: Decimal, check_balance: Option<Decimal>) -> Vec<String> {
+        prepare_payment(request, balance, check_balance)
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.field)
+            .collect()
+    }
+
+    #[test]
+    fn test_cash_payment_gives_change() {
+        let cash = request(Tender::Cash, None, Some(Decimal::new(200, 2)), Some(Decimal::new(5000, 2)));
+        let payment = prepare_payment(&cash, Decimal::new(4150, 2), None).unwrap();
+        assert_eq!(payment.amount, Decimal::new(4150, 2));
+        assert_eq!(payment.change, Decimal::new(650, 2));
+
+        let card = request(Tender::Card, Some(Decimal::new(1000, 2)), Some(Decimal::new(150, 2)), None);
+        let payment = prepare_payment(&card, Decimal::new(4150, 2), Some(Decimal::new(2000, 2))).unwrap();
+        assert_eq!(payment.tendered, Decimal::new(1150, 2));
+        assert_eq!(payment.change, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_invalid_payments_report_fields() {
+        let balance = Decimal::new(4150, 2);
+
+        let too_much = request(Tender::Card, Some(Decimal::new(2500, 2)), None, None);
+        assert_eq!(fields(&too_much, balance, Some(Decimal::new(2000, 2))), vec!["amount"]);
+
+        let card_change = request(Tender::Card, Some(Decimal::new(1000, 2)), None, Some(Decimal::new(2000, 2)));
+        assert_eq!(fields(&card_change, balance, None), vec!["tendered"]);
+
+        let voucher_tip = request(Tender::Voucher, Some(Decimal::new(1001, 3)), Some(Decimal::ONE), None);
+        assert_eq!(fields(&voucher_tip, balance, None), vec!["amount", "tip"]);
+
+        let short_cash = request(Tender::Cash, None, None, Some(Decimal::new(4000, 2)));
+        assert_eq!(fields(&short_cash, balance, None), vec!["tendered"]);
+
+        let anything = request(Tender::Cash, None, None, None);
+        assert_eq!(fields(&anything, Decimal::ZERO, None), vec!["amount"]);
+    }
+}
@@ -0,0 +1,27 @@
+use std::collections::HashSet;
+
+use crate::db::connection::NewPriceRuleRequest;
+use crate::models::route_models::FieldError;
+use crate::validation::{field_error, validate_price};
+
+pub fn validate_price_rule(request: &NewPriceRuleRequest) -> Vec<FieldError> {
+    let mut errors = vec![];
+    if request.name.trim().is_empty() {
+        errors.push(field_error(0, "name", "Name must not be empty".to_string()));
+    }
+    if let Err(message) = validate_price(request.price) {
+        errors.push(field_error(0, "price", message));
+    }
+
+    let mut seen = HashSet::new();
+    if request.days.is_empty() {
+        errors.push(field_error(0, "days", "Give at least one day".to_string()));
+    } else if request.days.iter().any(|day| !(1..=7).contains(day) || !seen.insert(*day)) {
+        errors.push(field_error(0, "days", "Days are numbered 1 (Monday) to 7 (Sunday) and listed once".to_string()));
+    }
+
+    if request.start_time == request.end_time {
+        errors.push(field_error(0, "end_time", "The window must end at a different time than it starts".to_string()));
+    }
+    errors
+}
@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+    use rust_decimal::Decimal;
+
+    use crate::db::connection::NewPriceRuleRequest;
+    use crate::validation::price_rules::validate_price_rule;
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_price_rules_need_days_and_a_window() {
+        let mut request = NewPriceRuleRequest {
+            name: "Happy hour".to_string(),
+            price: Decimal::new(400, 2),
+            days: vec![5, 6],
+            start_time: time(22),
+            end_time: time(2),
+        };
+        assert!(validate_price_rule(&request).is_empty());
+
+        request.name = " ".to_string();
+        request.price = Decimal::new(4005, 3);
+        request.days = vec![1, 8];
+        request.end_time = time(22);
+        let fields: Vec<String> = validate_price_rule(&request).into_iter().map(|error| error.field).collect();
+        assert_eq!(fields, vec!["name", "price", "days", "end_time"]);
+
+        request.days = vec![2, 2];
+        assert_eq!(validate_price_rule(&request)[2].field, "days");
+        request.days = vec![];
+        assert_eq!(validate_price_rule(&request)[2].field, "days");
+        request.price = Decimal::new(-1, 0);
+        assert_eq!(validate_price_rule(&request)[1].message, "Price must not be negative");
+        request.price = Decimal::new(100_000_000, 0);
+        assert_eq!(validate_price_rule(&request)[1].message, "Price must be lower than 100000000");
+    }
+}
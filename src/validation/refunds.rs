@@ -0,0 +1,123 @@
+use std::collections::{HashMap, HashSet};
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::billing::bill::{discounted_lines, round_money, tax_on};
+use crate::billing::refunds::refunded_amounts;
+use crate::db::connection::{NewRefund, NewRefundRequest, RefundItemRequest};
+use crate::models::{
+    restaurant_models::{Payment, RefundableItem},
+    route_models::{BillLine, BillResponse, FieldError},
+};
+use crate::validation::{field_error, whole_cents};
+
+/// Checks a refund against what is left to refund of `original`. Without an
+/// `amount` the listed items are refunded at what they came to on the settled
+/// `bill`, after their discounts and their share of the table discounts, with
+/// any tax that was added on top, up to what is left of the payment.
+pub fn prepare_refund(
+    request: &NewRefundRequest,
+    original: &Payment,
+    payments: &[Payment],
+    items: &[RefundableItem],
+    bill: &BillResponse,
+) -> Result<NewRefund, Vec<FieldError>> {
+    let (refunded_amount, refunded_tip) = refunded_amounts(payments, original.id);
+    let refundable_amount = original.amount - refunded_amount;
+    let refundable_tip = original.tip - refunded_tip;
+
+    let mut errors = vec![];
+    if request.approved_by.trim().is_empty() {
+        errors.push(field_error(0, "approved_by", "A manager must approve the refund"));
+    }
+
+    let items_by_id: HashMap<Uuid, &RefundableItem> = items.iter().map(|item| (item.item_id, item)).collect();
+    let lines: HashMap<Uuid, (&BillLine, Decimal)> = bill
+        .lines
+        .iter()
+        .zip(discounted_lines(bill))
+        .map(|(line, amount)| (line.item_id, (line, amount)))
+        .collect();
+    let mut seen = HashSet::new();
+    let mut items_value = Decimal::ZERO;
+    for (index, refunded) in request.items.iter().enumerate() {
+        let Some(item) = items_by_id.get(&refunded.item_id) else {
+            errors.push(field_error(
+                index,
+                "item_id",
+                format!("Item {} was not paid by this payment's bill", refunded.item_id),
+            ));
+            continue;
+        };
+        if !seen.insert(refunded.item_id) {
+            errors.push(field_error(
+                index,
+                "item_id",
+                format!("Item {} is refunded more than once", refunded.item_id),
+            ));
+            continue;
+        }
+        let left = item.quantity - item.refunded_quantity;
+        if refunded.quantity <= 0 || refunded.quantity > left {
+            errors.push(field_error(
+                index,
+                "quantity",
+                format!("Quantity must be between 1 and the {} left to refund", left),
+            ));
+            continue;
+        }
+        if let Some((line, amount)) = lines.get(&item.item_id) {
+            let value = round_money(*amount * Decimal::from(refunded.quantity) / Decimal::from(line.quantity));
+            let added_tax = bill
+                .taxes
+                .iter()
+                .find(|tax| tax.tax_category_id == line.tax_category_id && !tax.inclusive)
+                .map_or(Decimal::ZERO, |tax| tax_on(value, tax.rate, false));
+            items_value += value + added_tax;
+        }
+    }
+
+    let amount = match request.amount {
+        Some(amount) => amount,
+        None if request.items.is_empty() => {
+            errors.push(field_error(0, "amount", "Give an amount or the items to refund"));
+            return Err(errors);
+        }
+        None => items_value.min(refundable_amount),
+    };
+    let tip = request.tip.unwrap_or(Decimal::ZERO);
+
+    if amount < Decimal::ZERO || !whole_cents(amount) {
+        errors.push(field_error(0, "amount", "Amount must be zero or more, in whole cents"));
+    } else if amount > refundable_amount {
+        errors.push(field_error(0, "amount", format!("Amount exceeds the {} left to refund", refundable_amount)));
+    }
+    if tip < Decimal::ZERO || !whole_cents(tip) {
+        errors.push(field_error(0, "tip", "Tip must be zero or more, in whole cents"));
+    } else if tip > refundable_tip {
+        errors.push(field_error(0, "tip", format!("Tip exceeds the {} tip left to refund", refundable_tip)));
+    }
+    if errors.is_empty() && amount + tip == Decimal::ZERO {
+        errors.push(field_error(0, "amount", "Nothing would be refunded"));
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(NewRefund {
+        amount,
+        tip,
+        reason: request.reason,
+        approved_by: request.approved_by.trim().to_string(),
+        items: request
+            .items
+            .iter()
+            .map(|item| RefundItemRequest {
+                item_id: item.item_id,
+                quantity: item.quantity,
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    use crate::billing::bill::{build_bill, BillRules};
+    use crate::validation::refunds::prepare_refund;
+    use crate::db::connection::{NewRefundRequest, RefundItemRequest};
+    use crate::models::{
+        restaurant_models::{BillItem, Discount, DiscountKind, Payment, RefundReason, RefundableItem, Tender},
+        route_models::BillResponse,
+    };
+
+    fn payment(amount: Decimal, tip: Decimal, refund_of: Option<Uuid>) -> Payment {
+        Payment {
+            id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            check_id: None,
+            tender: Tender::Card,
+            amount,
+            tip,
+            tendered: amount + tip,
+            change: Decimal::ZERO,
+            refund_of,
+            reason: refund_of.map(|_| RefundReason::Quality),
+            approved_by: refund_of.map(|_| "manager".to_string()),
+            created_at: Utc::now().naive_utc(),
+            created_by: None,
+        }
+    }
+
+    fn rules(default_tax_rate: Decimal) -> BillRules {
+        BillRules {
+            default_tax_rate,
+            tax_categories: vec![],
+            service_charges: vec![],
+        }
+    }
+
+    fn bill_item(unit_price: Decimal, quantity: i32) -> BillItem {
+        BillItem {
+            item_id: Uuid::new_v4(),
+            menu_id: Uuid::new_v4(),
+            name: "Dish".to_string(),
+            unit_price,
+            quantity,
+            tax_category_id: None,
+        }
+    }
+
+    fn discount(items_id: Option<Uuid>, kind: DiscountKind, value: Decimal) -> Discount {
+        Discount {
+            id: Uuid::new_v4(),
+            tables_id: Uuid::new_v4(),
+            items_id,
+            kind,
+            value,
+            reason: None,
+            promo_code_id: None,
+            applied_by: "manager".to_string(),
+            session_id: None,
+            created_at: Utc::now().naive_utc(),
+            created_by: None,
+        }
+    }
+
+    fn refundable(item: &BillItem, refunded_quantity: i32) -> RefundableItem {
+        RefundableItem {
+            item_id: item.item_id,
+            quantity: item.quantity,
+            refunded_quantity,
+        }
+    }
+
+    fn settled_bill(items: &[BillItem], discounts: &[Discount], default_tax_rate: Decimal) -> BillResponse {
+        build_bill(Uuid::new_v4(), None, items.to_vec(), discounts, &rules(default_tax_rate))
+    }
+
+    fn request(amount: Option<Decimal>, tip: Option<Decimal>, items: Vec<RefundItemRequest>) -> NewRefundRequest {
+        NewRefundRequest {
+            amount,
+            tip,
+            reason: RefundReason::WrongItem,
+            approved_by: "manager".to_string(),
+            items,
+        }
+    }
+
+    #[test]
+    fn test_item_refund_defaults_to_price_with_tax() {
+        let original = payment(Decimal::new(4400, 2), Decimal::new(300, 2), None);
+        let item = bill_item(Decimal::new(999, 2), 3);
+        let items = vec![refundable(&item, 1)];
+
+        let bill = settled_bill(std::slice::from_ref(&item), &[], Decimal::new(10, 2));
+        let refund_items = vec![RefundItemRequest { item_id: item.item_id, quantity: 2 }];
+        let refund = prepare_refund(&request(None, None, refund_items), &original, &[], &items, &bill).unwrap();
+        assert_eq!(refund.amount, Decimal::new(2198, 2));
+        assert_eq!(refund.tip, Decimal::ZERO);
+        assert_eq!(refund.items.len(), 1);
+
+        let bill = settled_bill(std::slice::from_ref(&item), &[], Decimal::ZERO);
+        let refund_items = vec![
+            RefundItemRequest { item_id: Uuid::new_v4(), quantity: 1 },
+            RefundItemRequest { item_id: item.item_id, quantity: 3 },
+        ];
+        let errors = prepare_refund(&request(None, None, refund_items), &original, &[], &items, &bill).unwrap_err();
+        let fields: Vec<(usize, &str)> = errors.iter().map(|error| (error.index, error.field.as_str())).collect();
+        assert_eq!(fields, vec![(0, "item_id"), (1, "quantity")]);
+    }
+
+    #[test]
+    fn test_item_refund_takes_off_the_discounts_it_was_paid_with() {
+        let half_price = bill_item(Decimal::new(1000, 2), 2);
+        let full_price = bill_item(Decimal::new(2000, 2), 1);
+        let discounts = vec![
+            discount(Some(half_price.item_id), DiscountKind::Percent, Decimal::new(50, 0)),
+            discount(None, DiscountKind::Fixed, Decimal::new(300, 2)),
+        ];
+        let items = [half_price.clone(), full_price.clone()];
+        let bill = settled_bill(&items, &discounts, Decimal::new(10, 2));
+        assert_eq!(bill.total, Decimal::new(2970, 2));
+        let original = payment(bill.total, Decimal::ZERO, None);
+        let refundable_items: Vec<RefundableItem> = items.iter().map(|item| refundable(item, 0)).collect();
+
+        // 20.00 less 50% is 10.00, less a third of the table's 3.00 is 9.00 for two.
+        let refund_items = vec![RefundItemRequest { item_id: half_price.item_id, quantity: 1 }];
+        let refund = prepare_refund(&request(None, None, refund_items), &original, &[], &refundable_items, &bill).unwrap();
+        assert_eq!(refund.amount, Decimal::new(495, 2));
+
+        let comp = vec![discount(Some(full_price.item_id), DiscountKind::Percent, Decimal::new(100, 0))];
+        let bill = settled_bill(&items, &comp, Decimal::new(10, 2));
+        let refund_items = vec![RefundItemRequest { item_id: full_price.item_id, quantity: 1 }];
+        let errors = prepare_refund(&request(None, None, refund_items), &original, &[], &refundable_items, &bill).unwrap_err();
+        assert_eq!(errors[0].message, "Nothing would be refunded");
+    }
+
+    #[test]
+    fn test_refunds_cannot_exceed_what_was_paid() {
+        let original = payment(Decimal::new(4400, 2), Decimal::new(300, 2), None);
+        let earlier = payment(Decimal::new(-4000, 2), Decimal::new(-100, 2), Some(original.id));
+        let payments = vec![original.clone(), earlier];
+
+        let refund = prepare_refund(
+            &request(Some(Decimal::new(400, 2)), Some(Decimal::new(200, 2)), vec![]),
+            &original,
+            &payments,
+            &[],
+            &settled_bill(&[], &[], Decimal::ZERO),
+        )
+        .unwrap();
+        assert_eq!(refund.amount + refund.tip, Decimal::new(600, 2));
+
+        let errors = prepare_refund(
+            &request(Some(Decimal::new(401, 2)), Some(Decimal::new(201, 2)), vec![]),
+            &original,
+            &payments,
+            &[],
+            &settled_bill(&[], &[], Decimal::ZERO),
+        )
+        .unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, vec!["amount", "tip"]);
+
+        let mut unapproved = request(None, None, vec![]);
+        unapproved.approved_by = " ".to_string();
+        let errors = prepare_refund(&unapproved, &original, &payments, &[], &settled_bill(&[], &[], Decimal::ZERO)).unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, vec!["approved_by", "amount"]);
+    }
+}